@@ -1,3 +1,6 @@
 fn main() {
-    tauri_build::build()
+    // The command-line tool alone (`--no-default-features`) has no Tauri app to set up.
+    if std::env::var_os("CARGO_FEATURE_DESKTOP").is_some() {
+        tauri_build::build()
+    }
 }
@@ -0,0 +1,154 @@
+//! Headless front end to the indexer, for CI jobs and SSH sessions.
+//!
+//! ```text
+//! slide-indexer [--index <file>] scan <dir>...
+//! slide-indexer [--index <file>] [--json] search <query>...
+//! ```
+//!
+//! The index file uses the same format as the desktop app's `index.json`, so
+//! an existing app index can be pointed at with `--index`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use slides_indexer::{
+    error::{AppError, Result},
+    index::{load_state, persist_state, scan_state, search_items, CorpusCache},
+    models::{AppState, SearchOptions},
+};
+
+const DEFAULT_INDEX_FILE: &str = "slide-index.json";
+
+const USAGE: &str = "usage:
+  slide-indexer [--index <file>] scan <dir>...
+  slide-indexer [--index <file>] [--json] search <query>...
+
+options:
+  --index <file>  index to read/write (default: ./slide-index.json)
+  --json          print the search response as JSON";
+
+fn main() -> ExitCode {
+    match run(env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<()> {
+    let mut index_path = PathBuf::from(DEFAULT_INDEX_FILE);
+    let mut json = false;
+    let mut rest = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--index" => {
+                index_path = args.next().map(PathBuf::from).ok_or_else(usage_error)?;
+            }
+            "--json" => json = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ => rest.push(arg),
+        }
+    }
+
+    let Some((command, operands)) = rest.split_first() else {
+        return Err(usage_error());
+    };
+    match command.as_str() {
+        "scan" if !operands.is_empty() => scan(&index_path, operands),
+        "search" if !operands.is_empty() => search(&index_path, &operands.join(" "), json),
+        _ => Err(usage_error()),
+    }
+}
+
+/// Indexes `directories` into the index file, reusing its cached entries.
+fn scan(index_path: &Path, directories: &[String]) -> Result<()> {
+    let mut state = if index_path.exists() {
+        load_state(index_path)?
+    } else {
+        AppState::default()
+    };
+    // Absolute paths, like the ones the desktop app stores.
+    state.directories = directories
+        .iter()
+        .map(|directory| {
+            fs::canonicalize(directory)
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_else(|_| directory.clone())
+        })
+        .collect();
+
+    let summary = scan_state(&mut state)?;
+    persist_state(index_path, &state)?;
+
+    println!(
+        "Indexed {} files ({} scanned, {} cached) into {}",
+        summary.indexed,
+        summary.scanned.unwrap_or(0),
+        summary.cached.unwrap_or(0),
+        index_path.display()
+    );
+    for warning in &summary.warnings {
+        eprintln!("warning: {warning}");
+    }
+    for error in &summary.scan_errors {
+        eprintln!("error: {}: {}", error.path, error.message);
+    }
+    Ok(())
+}
+
+/// Prints every item in the index file matching `query`.
+fn search(index_path: &Path, query: &str, json: bool) -> Result<()> {
+    if !index_path.exists() {
+        return Err(AppError::Message(format!(
+            "No index at {}; run `slide-indexer scan <dir>` first",
+            index_path.display()
+        )));
+    }
+    let state = load_state(index_path)?;
+    let corpus = CorpusCache::build(&state.items);
+    // A one-shot search gains nothing from building an inverted index first.
+    let response = search_items(&state, &corpus, None, query, &SearchOptions::default());
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&response)?);
+        return Ok(());
+    }
+    for hit in &response.items {
+        println!("{}\t{}", hit.item.display_title(), hit.item.path);
+        if !hit.matched_slides.is_empty() {
+            let slides: Vec<String> = hit
+                .matched_slides
+                .iter()
+                .map(|index| {
+                    let title = hit
+                        .item
+                        .slides
+                        .iter()
+                        .find(|slide| slide.index == *index)
+                        .and_then(|slide| slide.title.as_deref());
+                    match title {
+                        Some(title) => format!("{index} ({title})"),
+                        None => index.to_string(),
+                    }
+                })
+                .collect();
+            println!("\tslides: {}", slides.join(", "));
+        }
+    }
+    println!("{} match(es)", response.total);
+    Ok(())
+}
+
+fn usage_error() -> AppError {
+    AppError::Message(format!("invalid arguments\n{USAGE}"))
+}
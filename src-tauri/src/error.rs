@@ -1,4 +1,4 @@
-use std::io;
+use std::{io, process::ExitStatus};
 
 use thiserror::Error;
 use zip::result::ZipError;
@@ -13,6 +13,29 @@ pub enum AppError {
     Regex(#[from] regex::Error),
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
+    #[error("failed to start {tool}: {source}")]
+    CommandSpawn {
+        tool: &'static str,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{tool} timed out")]
+    CommandTimeout { tool: &'static str },
+    #[error("{tool} exited with {status}")]
+    CommandFailed {
+        tool: &'static str,
+        status: ExitStatus,
+    },
+    #[error("file is password-protected")]
+    PasswordProtected,
+    #[error("archive is corrupt or truncated")]
+    CorruptArchive,
+    #[error("a scan is already running")]
+    ScanInProgress,
+    #[error("index is read-only")]
+    ReadOnly,
+    #[error("the data directory was moved; restart the app to make changes")]
+    RestartRequired,
     #[error("{0}")]
     Message(String),
 }
@@ -0,0 +1,230 @@
+//! Optional read-mostly HTTP interface to the index for other local tools
+//! (editor plugins, dashboards). Bound to 127.0.0.1 only and off by default;
+//! enabled by setting `ScanSettings::http_api_port`.
+//!
+//! Every request must carry the per-install token from `http-api-token` in the
+//! app-data folder as an `X-Slide-Indexer-Token` header. Requests from browsers
+//! (any `Origin` header) or for another host name (DNS rebinding) are refused.
+//!
+//! - `GET /search?q=<query>` → `SearchResponse`
+//! - `GET /items/<id>`       → `SlideIndexItem`
+//! - `POST /rescan`          → `ScanSummary`
+
+use std::{fs, net::Ipv4Addr, path::Path, sync::Arc, thread};
+
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    error::{AppError, Result},
+    models::SearchOptions,
+    state::StateManager,
+};
+
+const TOKEN_FILE: &str = "http-api-token";
+const TOKEN_HEADER: &str = "X-Slide-Indexer-Token";
+/// Requests are served by this many threads, so a long rescan only ties up one.
+const WORKERS: usize = 4;
+
+/// Starts the server on background worker threads; returns once the port is bound.
+pub fn start(manager: Arc<StateManager>, port: u16) -> Result<()> {
+    let token = load_or_create_token(manager.install_dir())?;
+    let server = Server::http((Ipv4Addr::LOCALHOST, port)).map_err(|error| {
+        AppError::Message(format!("HTTP API failed to bind port {port}: {error}"))
+    })?;
+    println!("🌐 HTTP API listening on http://127.0.0.1:{}", port);
+
+    let server = Arc::new(server);
+    let token = Arc::new(token);
+    for _ in 0..WORKERS {
+        let server = Arc::clone(&server);
+        let manager = Arc::clone(&manager);
+        let token = Arc::clone(&token);
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                handle(&manager, &token, port, request);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Reads the install's API token, generating it on first use.
+fn load_or_create_token(dir: &Path) -> Result<String> {
+    let path = dir.join(TOKEN_FILE);
+    if let Ok(existing) = fs::read_to_string(&path) {
+        let existing = existing.trim();
+        if !existing.is_empty() {
+            return Ok(existing.to_string());
+        }
+    }
+
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|error| AppError::Message(format!("Could not generate HTTP API token: {error}")))?;
+    let token = hex::encode(bytes);
+
+    fs::write(&path, &token)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    }
+    println!("🔑 Created HTTP API token at {}", path.display());
+    Ok(token)
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+/// Why a request may not be served, if it can't.
+fn rejection(request: &Request, token: &str, port: u16) -> Option<(u16, &'static str)> {
+    // Browsers always send Origin on cross-site fetches and form posts; local tools don't.
+    if header(request, "Origin").is_some() {
+        return Some((403, "browser requests are not allowed"));
+    }
+    let host_ok = header(request, "Host").is_some_and(|host| {
+        let host = host.to_ascii_lowercase();
+        host == format!("127.0.0.1:{port}") || host == format!("localhost:{port}")
+    });
+    if !host_ok {
+        return Some((403, "unexpected host"));
+    }
+    let presented = header(request, TOKEN_HEADER).unwrap_or_default();
+    if !constant_time_eq(presented.as_bytes(), token.as_bytes()) {
+        return Some((401, "missing or invalid token"));
+    }
+    None
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len() && left.iter().zip(right).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn handle(manager: &StateManager, token: &str, port: u16, request: Request) {
+    if let Some((status, message)) = rejection(&request, token, port) {
+        if let Err(respond_error) = request.respond(error(status, message)) {
+            println!("⚠️  HTTP API failed to send response: {}", respond_error);
+        }
+        return;
+    }
+
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+
+    let response = match (request.method(), path) {
+        (Method::Get, "/search") => {
+            let text = form_urlencoded::parse(query.as_bytes())
+                .find(|(key, _)| key == "q")
+                .map(|(_, value)| value.into_owned())
+                .unwrap_or_default();
+            json(200, &manager.search(&text, &SearchOptions::default()))
+        }
+        (Method::Get, path) if path.starts_with("/items/") => match item_id(path) {
+            Some(id) => match manager.find_item(&id) {
+                Some(item) => json(200, &item),
+                None => error(404, "item not found"),
+            },
+            None => error(400, "item id is not valid UTF-8"),
+        },
+        (Method::Post, "/rescan") => match manager.rescan() {
+            Ok(summary) => json(200, &summary),
+            Err(scan_error @ AppError::ScanInProgress) => error(409, &scan_error.to_string()),
+            Err(scan_error @ (AppError::ReadOnly | AppError::RestartRequired)) => error(403, &scan_error.to_string()),
+            Err(scan_error) => error(500, &scan_error.to_string()),
+        },
+        _ => error(404, "not found"),
+    };
+
+    if let Err(respond_error) = request.respond(response) {
+        println!("⚠️  HTTP API failed to send response: {}", respond_error);
+    }
+}
+
+/// The percent-decoded id from an `/items/<id>` path.
+fn item_id(path: &str) -> Option<String> {
+    let encoded = path.strip_prefix("/items/")?;
+    percent_decode_str(encoded).decode_utf8().ok().map(|id| id.into_owned())
+}
+
+fn json<T: Serialize>(status: u16, body: &T) -> Response<std::io::Cursor<Vec<u8>>> {
+    let payload = serde_json::to_vec(body).unwrap_or_default();
+    let header = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    Response::from_data(payload)
+        .with_status_code(status)
+        .with_header(header)
+}
+
+fn error(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    json(status, &serde_json::json!({ "error": message }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_http::TestRequest;
+
+    const PORT: u16 = 8765;
+    const TOKEN: &str = "secret";
+
+    /// Status a request with these headers is refused with, if any.
+    fn refused_with(headers: &[(&str, &str)]) -> Option<u16> {
+        let request: Request = headers
+            .iter()
+            .fold(TestRequest::new().with_path("/search?q=x"), |request, (field, value)| {
+                request.with_header(Header::from_bytes(*field, *value).unwrap())
+            })
+            .into();
+        rejection(&request, TOKEN, PORT).map(|(status, _)| status)
+    }
+
+    #[test]
+    fn accepts_local_tool_with_token() {
+        assert_eq!(refused_with(&[("Host", "127.0.0.1:8765"), (TOKEN_HEADER, TOKEN)]), None);
+        assert_eq!(refused_with(&[("Host", "LOCALHOST:8765"), (TOKEN_HEADER, TOKEN)]), None);
+    }
+
+    #[test]
+    fn rejects_browser_origin() {
+        let headers = [("Host", "127.0.0.1:8765"), (TOKEN_HEADER, TOKEN), ("Origin", "https://evil.example")];
+        assert_eq!(refused_with(&headers), Some(403));
+    }
+
+    #[test]
+    fn rejects_rebound_host() {
+        assert_eq!(refused_with(&[("Host", "evil.example:8765"), (TOKEN_HEADER, TOKEN)]), Some(403));
+        assert_eq!(refused_with(&[("Host", "127.0.0.1:9999"), (TOKEN_HEADER, TOKEN)]), Some(403));
+        assert_eq!(refused_with(&[(TOKEN_HEADER, TOKEN)]), Some(403));
+    }
+
+    #[test]
+    fn item_ids_are_percent_decoded() {
+        assert_eq!(item_id("/items/abc123").as_deref(), Some("abc123"));
+        assert_eq!(item_id("/items/%2Fdecks%2Fcell%20biology+1.pptx").as_deref(), Some("/decks/cell biology+1.pptx"));
+        assert_eq!(item_id("/items/caf%C3%A9").as_deref(), Some("café"));
+        assert_eq!(item_id("/items/%FF"), None);
+    }
+
+    #[test]
+    fn new_tokens_are_random() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let token = load_or_create_token(first.path()).unwrap();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, load_or_create_token(second.path()).unwrap());
+        // Reused once created.
+        assert_eq!(token, load_or_create_token(first.path()).unwrap());
+    }
+
+    #[test]
+    fn rejects_missing_or_wrong_token() {
+        assert_eq!(refused_with(&[("Host", "127.0.0.1:8765")]), Some(401));
+        assert_eq!(refused_with(&[("Host", "127.0.0.1:8765"), (TOKEN_HEADER, "secreT")]), Some(401));
+    }
+}
@@ -0,0 +1,352 @@
+//! Index operations that need no Tauri runtime, shared by the desktop app's
+//! `StateManager` and the `slide-indexer` command-line tool.
+
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::atomic::AtomicBool,
+};
+
+use crate::{
+    error::Result,
+    inverted_index::InvertedIndex,
+    models::{
+        AppState, HealthIssue, IndexHealth, QueryExplanation, ScanError, ScanSettings, ScanSummary, ScanTiming, SearchFacets, SearchHit, SearchOptions,
+        SearchResponse, SlideIndexItem, SortBy, TermCount,
+    },
+    ocr_cache, text_cache,
+    scanner::{current_timestamp, deck_exists, ocr_status_message, path_key, scan_directories, ScanOutcome},
+    search::{
+        build_search_corpus, explain_match, match_snippets, matched_slide_indices, matches_query_in, relevance,
+        SearchPattern,
+    },
+};
+
+/// Lowercased search text of every item, keyed by id.
+///
+/// Building the corpus joins and lowercases all slide text, so doing it per
+/// item on every keystroke dominated search time on large libraries. Owners
+/// rebuild this whenever items change.
+#[derive(Debug, Default)]
+pub struct CorpusCache {
+    by_id: HashMap<String, String>,
+}
+
+impl CorpusCache {
+    pub fn build(items: &[SlideIndexItem]) -> Self {
+        let by_id = items
+            .iter()
+            .map(|item| (item.id.clone(), build_search_corpus(item)))
+            .collect();
+        Self { by_id }
+    }
+
+    /// (item id, corpus) pairs, in no particular order.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.by_id.iter().map(|(id, corpus)| (id.as_str(), corpus.as_str()))
+    }
+
+    /// Cached corpus for `item`, built on the spot if it isn't cached.
+    pub fn get(&self, item: &SlideIndexItem) -> Cow<'_, str> {
+        match self.by_id.get(&item.id) {
+            Some(corpus) => Cow::Borrowed(corpus),
+            None => Cow::Owned(build_search_corpus(item)),
+        }
+    }
+}
+
+pub fn load_state(path: &Path) -> Result<AppState> {
+    let raw = fs::read_to_string(path)?;
+    let parsed: AppState = serde_json::from_str(&raw)?;
+    Ok(parsed)
+}
+
+pub fn persist_state(path: &Path, state: &AppState) -> Result<()> {
+    let payload = serde_json::to_string_pretty(state)?;
+    fs::write(path, payload)?;
+    Ok(())
+}
+
+/// Scans `state.directories` in one go and replaces `state.items` with the result,
+/// reusing unchanged entries. Used where nothing needs per-file progress.
+pub fn scan_state(state: &mut AppState) -> Result<ScanSummary> {
+    let settings: ScanSettings = state.settings.clone();
+    let ScanOutcome {
+        mut items,
+        errors,
+        warnings,
+        scanned_count,
+        cached_count,
+        timing,
+    } = scan_directories(
+        &state.directories,
+        &state.items,
+        &settings,
+        None,
+        &AtomicBool::new(false),
+        &mut |_, _, _| {},
+        &mut |_| {},
+    )?;
+    carry_over_user_fields(&state.items, &mut items);
+    state.items = items;
+    Ok(finish_scan(
+        state,
+        Some(scanned_count),
+        Some(cached_count),
+        errors,
+        warnings,
+        Some(timing),
+    ))
+}
+
+/// Common tail of every scan once `state.items` holds the new result: orders
+/// the items, stamps the time, builds the summary and stores its warnings.
+pub fn finish_scan(
+    state: &mut AppState,
+    scanned: Option<usize>,
+    cached: Option<usize>,
+    errors: Vec<ScanError>,
+    warnings: Vec<String>,
+    timing: Option<ScanTiming>,
+) -> ScanSummary {
+    state.items.sort_by_key(|item| Reverse(item.updated_at));
+    state.last_indexed_at = Some(current_timestamp());
+
+    let summary = ScanSummary {
+        indexed: state.items.len(),
+        scanned,
+        cached,
+        errors: errors.iter().map(ToString::to_string).collect(),
+        scan_errors: errors,
+        warnings,
+        last_indexed_at: state.last_indexed_at,
+        plan: None,
+        timing,
+    };
+    // Missing tools aren't a scan error; the frontend asks `tool_status` for them.
+    if let Some(message) = ocr_status_message() {
+        println!("⚠️  {}", message);
+    }
+
+    state.warnings = summary.warnings.clone();
+    state.errors = summary.errors.clone();
+    state.scan_errors = summary.scan_errors.clone();
+    ocr_cache::evict_stale(&state.items);
+    text_cache::evict_stale(&state.items);
+    summary
+}
+
+/// Looks for drift in `state.items`: files that no longer exist, items with an
+/// empty id or path, duplicate ids and slide lists out of order. Items with an
+/// empty id or path, and all but the first of a duplicated id, can't be told
+/// apart or opened, so `repair` drops them; the rest is only reported, since a
+/// rescan sorts it out.
+pub fn check_index_health(state: &mut AppState, repair: bool) -> IndexHealth {
+    let mut health = IndexHealth {
+        checked: state.items.len(),
+        ..IndexHealth::default()
+    };
+    let mut seen_ids: HashSet<String> = HashSet::new();
+    let mut issue = |code: &str, item: &SlideIndexItem, repaired: bool| {
+        health.issues.push(HealthIssue {
+            code: code.to_string(),
+            id: item.id.clone(),
+            path: item.path.clone(),
+            repaired,
+        });
+    };
+    state.items.retain(|item| {
+        if item.id.is_empty() || item.path.is_empty() {
+            issue("empty_id_or_path", item, repair);
+            return !repair;
+        }
+        if !seen_ids.insert(item.id.clone()) {
+            issue("duplicate_id", item, repair);
+            return !repair;
+        }
+        if !deck_exists(&item.path) {
+            issue("missing_file", item, false);
+        }
+        if !slides_consistent(item) {
+            issue("inconsistent_slides", item, false);
+        }
+        true
+    });
+    health.repaired = health.issues.iter().filter(|issue| issue.repaired).count();
+    health
+}
+
+/// Slide indices are 1-based and strictly increasing. They aren't checked
+/// against `slide_count`: that counts only slides with text, while an index is
+/// the slide's position among all of them (blank ones included).
+fn slides_consistent(item: &SlideIndexItem) -> bool {
+    let in_order = item.slides.windows(2).all(|pair| pair[0].index < pair[1].index);
+    let first_ok = item.slides.first().is_none_or(|slide| slide.index >= 1);
+    in_order && first_ok
+}
+
+/// Runs `query` over `state.items`. With an `inverted` index only items holding
+/// every plain term are verified; otherwise every item is checked.
+pub fn search_items(
+    state: &AppState,
+    corpus: &CorpusCache,
+    inverted: Option<&InvertedIndex>,
+    query: &str,
+    options: &SearchOptions,
+) -> SearchResponse {
+    let pattern = SearchPattern::new(query)
+        .with_filters(options.filters.clone())
+        .with_whole_word(options.whole_word)
+        .with_fuzzy(options.fuzzy);
+    let candidates =
+        inverted.and_then(|index| index.candidates(pattern.terms(), pattern.is_fuzzy()));
+    let within = options
+        .within
+        .as_deref()
+        .map(str::trim)
+        .filter(|directory| !directory.is_empty());
+    let mut facets = SearchFacets::default();
+    let mut items = state
+        .items
+        .iter()
+        .filter(|item| within.is_none_or(|directory| path_within(&item.path, directory)))
+        .filter(|item| candidates.as_ref().is_none_or(|ids| ids.contains(item.id.as_str())))
+        .filter(|item| matches_query_in(item, &corpus.get(item), &pattern))
+        .inspect(|item| facets.record(&item.kind))
+        .map(|item| {
+            let matched_slides = matched_slide_indices(item, &pattern);
+            SearchHit {
+                relevance: relevance(item, &matched_slides, &pattern),
+                snippets: match_snippets(item, &matched_slides, &pattern),
+                explanation: if options.explain { explain_match(item, &pattern) } else { Vec::new() },
+                matched_slides,
+                item: item.clone(),
+            }
+        })
+        .collect::<Vec<SearchHit>>();
+    sort_hits(&mut items, options.sort_by);
+    if options.favorites_first {
+        // Stable sort, so the `sort_by` order is kept within each group.
+        items.sort_by_key(|hit| !hit.item.favorite);
+    }
+    SearchResponse {
+        total: items.len(),
+        items,
+        last_indexed_at: state.last_indexed_at,
+        facets,
+    }
+}
+
+/// How many items each phrase, term and wildcard of `query` occurs in on its
+/// own, so a query matching nothing shows which part to drop.
+pub fn explain_query(state: &AppState, corpus: &CorpusCache, query: &str) -> QueryExplanation {
+    let pattern = SearchPattern::new(query);
+    let parts = pattern.parts();
+    let mut counts = vec![0; parts.len()];
+    let mut matching_items = 0;
+    for item in &state.items {
+        let text = corpus.get(item);
+        for (count, (_, matches)) in counts.iter_mut().zip(&parts) {
+            if matches(&text) {
+                *count += 1;
+            }
+        }
+        if matches_query_in(item, &text, &pattern) {
+            matching_items += 1;
+        }
+    }
+    QueryExplanation {
+        terms: parts
+            .into_iter()
+            .zip(counts)
+            .map(|((term, _), items)| TermCount { term, items })
+            .collect(),
+        matching_items,
+        total_items: state.items.len(),
+    }
+}
+
+/// Re-applies user fields changed while a scan was running, so an item opened
+/// or tagged mid-scan doesn't lose that when the scan result is committed.
+pub fn carry_over_user_fields(current: &[SlideIndexItem], items: &mut [SlideIndexItem]) {
+    let by_path: HashMap<Cow<str>, &SlideIndexItem> =
+        current.iter().map(|item| (path_key(&item.path), item)).collect();
+    for item in items.iter_mut() {
+        if let Some(previous) = by_path.get(&path_key(&item.path)) {
+            item.carry_user_fields_from(previous);
+        }
+    }
+}
+
+fn sort_hits(hits: &mut [SearchHit], sort_by: SortBy) {
+    match sort_by {
+        SortBy::Newest => hits.sort_by_key(|hit| Reverse(hit.item.updated_at)),
+        SortBy::Oldest => hits.sort_by_key(|hit| hit.item.updated_at),
+        SortBy::Name => hits.sort_by_cached_key(|hit| hit.item.display_title().to_lowercase()),
+        // Items without a count/size sort last (`None < Some` under Reverse).
+        SortBy::SlideCount => hits.sort_by_key(|hit| Reverse(hit.item.slide_count)),
+        SortBy::Size => hits.sort_by_key(|hit| Reverse(hit.item.file_size)),
+        SortBy::WordCount => hits.sort_by_key(|hit| Reverse(hit.item.word_count)),
+        SortBy::Relevance => hits.sort_by_key(|hit| Reverse(hit.relevance)),
+        SortBy::TextCoverage => hits.sort_by(|a, b| match (a.item.text_coverage, b.item.text_coverage) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }),
+    }
+}
+
+pub fn path_within(path: &str, directory: &str) -> bool {
+    let file_path = Path::new(path);
+    let dir_path = Path::new(directory);
+    file_path == dir_path || file_path.starts_with(dir_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::index_path;
+    use std::io::Write;
+
+    /// A deck whose second slide has no text.
+    fn write_deck_with_blank_slide(path: &Path) {
+        let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (number, text) in [(1, Some("Cell structure")), (2, None), (3, Some("Mitochondria"))] {
+            zip.start_file(format!("ppt/slides/slide{number}.xml"), zip::write::FileOptions::default()).unwrap();
+            let body = text.map(|text| format!("<a:p><a:r><a:t>{text}</a:t></a:r></a:p>")).unwrap_or_default();
+            write!(zip, "<p:sld><p:cSld><p:spTree>{body}</p:spTree></p:cSld></p:sld>").unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn deck_with_a_blank_slide_is_consistent() {
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("biology.pptx");
+        write_deck_with_blank_slide(&deck);
+
+        let item = index_path(&deck, &ScanSettings::default()).unwrap();
+        assert_eq!(item.slide_count, Some(2));
+        assert_eq!(item.slides.last().map(|slide| slide.index), Some(3));
+        assert!(slides_consistent(&item));
+
+        let mut state = AppState { items: vec![item], ..AppState::default() };
+        assert!(check_index_health(&mut state, false).issues.is_empty());
+    }
+
+    #[test]
+    fn slides_out_of_order_are_inconsistent() {
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("biology.pptx");
+        write_deck_with_blank_slide(&deck);
+        let mut item = index_path(&deck, &ScanSettings::default()).unwrap();
+
+        item.slides.reverse();
+        assert!(!slides_consistent(&item));
+        item.slides.reverse();
+        item.slides[0].index = 0;
+        assert!(!slides_consistent(&item));
+    }
+}
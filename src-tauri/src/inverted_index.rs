@@ -0,0 +1,74 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{index::CorpusCache, search::is_similar_token};
+
+/// Token → item ids map used to narrow a search before verifying candidates.
+///
+/// Memory grows with the vocabulary plus one id per (token, item) pair, which
+/// for a few thousand decks is a few MB on top of the corpus cache. Below
+/// `ScanSettings::inverted_index_min_items` items it isn't built at all and
+/// search simply scans every cached corpus.
+#[derive(Debug, Default)]
+pub struct InvertedIndex {
+    /// (token, ids of items whose corpus contains it), sorted by token.
+    postings: Vec<(String, Vec<String>)>,
+}
+
+impl InvertedIndex {
+    pub fn build(corpus: &CorpusCache) -> Self {
+        let mut by_token: HashMap<&str, Vec<String>> = HashMap::new();
+        for (id, text) in corpus.entries() {
+            let tokens: HashSet<&str> = tokenize(text).collect();
+            for token in tokens {
+                by_token.entry(token).or_default().push(id.to_string());
+            }
+        }
+
+        let mut postings: Vec<(String, Vec<String>)> = by_token
+            .into_iter()
+            .map(|(token, ids)| (token.to_string(), ids))
+            .collect();
+        postings.sort_by(|a, b| a.0.cmp(&b.0));
+        Self { postings }
+    }
+
+    /// Ids of the items that can contain every indexable term, or `None` when
+    /// no term can be looked up (phrases/wildcards/punctuation only), in which
+    /// case every item remains a candidate.
+    ///
+    /// Terms match as substrings, so a term selects every token containing it
+    /// (plus, with `fuzzy`, every token a few typos away). Only purely
+    /// alphanumeric terms are looked up: any occurrence of those lies inside a
+    /// single token.
+    pub fn candidates(&self, terms: &[String], fuzzy: bool) -> Option<HashSet<&str>> {
+        let mut result: Option<HashSet<&str>> = None;
+        for term in terms.iter().filter(|term| is_indexable(term)) {
+            let matching: HashSet<&str> = self
+                .postings
+                .iter()
+                .filter(|(token, _)| {
+                    if fuzzy {
+                        is_similar_token(token, term)
+                    } else {
+                        token.contains(term.as_str())
+                    }
+                })
+                .flat_map(|(_, ids)| ids.iter().map(String::as_str))
+                .collect();
+            result = Some(match result {
+                Some(previous) => previous.intersection(&matching).copied().collect(),
+                None => matching,
+            });
+        }
+        result
+    }
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+}
+
+fn is_indexable(term: &str) -> bool {
+    !term.is_empty() && term.chars().all(char::is_alphanumeric)
+}
@@ -0,0 +1,205 @@
+//! Slide-by-slide text comparison of two indexed decks, e.g. two versions of
+//! the same lecture.
+
+use std::collections::HashMap;
+
+use similar::{capture_diff_slices, Algorithm, DiffOp, TextDiff};
+
+use crate::models::{ItemDiff, SlideChange, SlideDiff, SlidePreview, SlideIndexItem};
+
+/// How alike (0-1, by words) two slides must be to count as one slide edited
+/// rather than one removed and another added.
+const MIN_MODIFIED_SIMILARITY: f32 = 0.5;
+
+/// Differences from `a` to `b`. Slides are paired by their text, not their
+/// position, so inserting, deleting or moving a slide doesn't show every later
+/// slide as changed: identical text is unchanged wherever it moved, and of the
+/// rest each slide in `a` is paired with the most similar one left in `b`.
+pub fn diff_items(a: &SlideIndexItem, b: &SlideIndexItem) -> ItemDiff {
+    let mut diff = ItemDiff {
+        a_id: a.id.clone(),
+        b_id: b.id.clone(),
+        ..ItemDiff::default()
+    };
+
+    let mut unmatched_b: HashMap<&str, Vec<&SlidePreview>> = HashMap::new();
+    for slide in b.slides.iter().rev() {
+        unmatched_b.entry(slide.text.as_str()).or_default().push(slide);
+    }
+    let mut left_a = Vec::new();
+    for slide in &a.slides {
+        match unmatched_b.get_mut(slide.text.as_str()).and_then(Vec::pop) {
+            Some(_) => diff.slides_unchanged += 1,
+            None => left_a.push(slide),
+        }
+    }
+    let mut left_b: Vec<&SlidePreview> = unmatched_b.into_values().flatten().collect();
+    left_b.sort_by_key(|slide| slide.index);
+
+    for old in left_a {
+        let closest = left_b
+            .iter()
+            .enumerate()
+            .map(|(position, new)| (position, similarity(&old.text, &new.text)))
+            .filter(|(_, score)| *score >= MIN_MODIFIED_SIMILARITY)
+            .max_by(|(_, left), (_, right)| left.total_cmp(right));
+        let change = match closest {
+            Some((position, _)) => {
+                let new = left_b.remove(position);
+                let (added, removed) = diff_words(&old.text, &new.text);
+                diff.slides_modified += 1;
+                SlideDiff {
+                    index: new.index,
+                    change: SlideChange::Modified,
+                    added,
+                    removed,
+                }
+            }
+            None => {
+                diff.slides_removed += 1;
+                SlideDiff {
+                    index: old.index,
+                    change: SlideChange::Removed,
+                    added: Vec::new(),
+                    removed: vec![old.text.clone()],
+                }
+            }
+        };
+        diff.slides.push(change);
+    }
+    for new in left_b {
+        diff.slides_added += 1;
+        diff.slides.push(SlideDiff {
+            index: new.index,
+            change: SlideChange::Added,
+            added: vec![new.text.clone()],
+            removed: Vec::new(),
+        });
+    }
+    diff.slides.sort_by_key(|slide| slide.index);
+    diff
+}
+
+fn similarity(old: &str, new: &str) -> f32 {
+    TextDiff::configure()
+        .algorithm(Algorithm::Myers)
+        .diff_words(old, new)
+        .ratio()
+}
+
+/// Word-level diff: runs of words only in `new` and runs only in `old`.
+fn diff_words(old: &str, new: &str) -> (Vec<String>, Vec<String>) {
+    let old_words: Vec<&str> = old.split_whitespace().collect();
+    let new_words: Vec<&str> = new.split_whitespace().collect();
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, &old_words, &new_words) {
+        match op {
+            DiffOp::Equal { .. } => {}
+            DiffOp::Delete { old_index, old_len, .. } => {
+                removed.push(old_words[old_index..old_index + old_len].join(" "));
+            }
+            DiffOp::Insert { new_index, new_len, .. } => {
+                added.push(new_words[new_index..new_index + new_len].join(" "));
+            }
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => {
+                removed.push(old_words[old_index..old_index + old_len].join(" "));
+                added.push(new_words[new_index..new_index + new_len].join(" "));
+            }
+        }
+    }
+    (added, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deck(id: &str, slides: &[&str]) -> SlideIndexItem {
+        let slides: Vec<serde_json::Value> = slides
+            .iter()
+            .enumerate()
+            .map(|(index, text)| serde_json::json!({ "index": index + 1, "text": text }))
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "path": format!("/decks/{id}.pptx"),
+            "name": format!("{id}.pptx"),
+            "kind": "pptx",
+            "slideCount": slides.len(),
+            "snippet": "",
+            "updatedAt": 1,
+            "slides": slides,
+        }))
+        .unwrap()
+    }
+
+    /// (index, change) of every changed slide.
+    fn changes(diff: &ItemDiff) -> Vec<(u32, SlideChange)> {
+        diff.slides.iter().map(|slide| (slide.index, slide.change)).collect()
+    }
+
+    const INTRO: &str = "Introduction to cell biology";
+    const MEMBRANE: &str = "The cell membrane controls what enters and leaves";
+    const NUCLEUS: &str = "The nucleus holds the genetic material";
+    const SUMMARY: &str = "Summary and further reading";
+
+    #[test]
+    fn inserted_slide_is_the_only_change() {
+        let a = deck("a", &[INTRO, MEMBRANE, NUCLEUS, SUMMARY]);
+        let b = deck("b", &[INTRO, "Quiz: name three organelles", MEMBRANE, NUCLEUS, SUMMARY]);
+
+        let diff = diff_items(&a, &b);
+        assert_eq!(changes(&diff), [(2, SlideChange::Added)]);
+        assert_eq!((diff.slides_added, diff.slides_unchanged, diff.slides_modified), (1, 4, 0));
+    }
+
+    #[test]
+    fn deleted_slide_is_the_only_change() {
+        let a = deck("a", &[INTRO, MEMBRANE, NUCLEUS, SUMMARY]);
+        let b = deck("b", &[INTRO, NUCLEUS, SUMMARY]);
+
+        let diff = diff_items(&a, &b);
+        assert_eq!(changes(&diff), [(2, SlideChange::Removed)]);
+        assert_eq!(diff.slides[0].removed, [MEMBRANE]);
+        assert_eq!((diff.slides_removed, diff.slides_unchanged), (1, 3));
+    }
+
+    #[test]
+    fn reordered_slides_are_unchanged() {
+        let a = deck("a", &[INTRO, MEMBRANE, NUCLEUS, SUMMARY]);
+        let b = deck("b", &[INTRO, NUCLEUS, MEMBRANE, SUMMARY]);
+
+        let diff = diff_items(&a, &b);
+        assert!(diff.slides.is_empty());
+        assert_eq!(diff.slides_unchanged, 4);
+    }
+
+    #[test]
+    fn edited_slide_is_modified_even_after_an_insert() {
+        let a = deck("a", &[INTRO, MEMBRANE, NUCLEUS, SUMMARY]);
+        let b = deck(
+            "b",
+            &[INTRO, "Cells in the wild", MEMBRANE, "The nucleus holds the genetic material as DNA", SUMMARY],
+        );
+
+        let diff = diff_items(&a, &b);
+        assert_eq!(changes(&diff), [(2, SlideChange::Added), (4, SlideChange::Modified)]);
+        assert_eq!(diff.slides[1].added, ["as DNA"]);
+        assert!(diff.slides[1].removed.is_empty());
+    }
+
+    #[test]
+    fn unrelated_replacement_is_a_removal_and_an_addition() {
+        let a = deck("a", &[INTRO, MEMBRANE]);
+        let b = deck("b", &[INTRO, "Photosynthesis happens in chloroplasts"]);
+
+        let diff = diff_items(&a, &b);
+        assert_eq!(changes(&diff), [(2, SlideChange::Removed), (2, SlideChange::Added)]);
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    models::{KeywordSuggestion, SlideIndexItem},
+    scanner::is_noise_token,
+};
+
+/// Sorted vocabulary built from the keywords of every indexed item.
+///
+/// Entries are kept in lexical order so a prefix lookup is a binary search
+/// followed by a short forward scan, which is cheap enough to run per keystroke.
+#[derive(Debug, Default)]
+pub struct KeywordIndex {
+    /// (keyword, number of documents containing it), sorted by keyword.
+    entries: Vec<(String, usize)>,
+}
+
+impl KeywordIndex {
+    pub fn build<'a>(items: impl IntoIterator<Item = &'a SlideIndexItem>) -> Self {
+        let mut document_counts: HashMap<String, usize> = HashMap::new();
+        for item in items {
+            let mut seen = HashSet::new();
+            for keyword in &item.keywords {
+                let normalised = keyword.trim().to_lowercase();
+                if normalised.is_empty() || !seen.insert(normalised.clone()) {
+                    continue;
+                }
+                *document_counts.entry(normalised).or_insert(0) += 1;
+            }
+        }
+
+        let mut entries: Vec<(String, usize)> = document_counts.into_iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Self { entries }
+    }
+
+    /// The `limit` keywords found in the most documents, skipping noise words
+    /// (font names, placeholder labels, ...) that older indexes may still hold.
+    pub fn top(&self, limit: usize) -> Vec<KeywordSuggestion> {
+        let mut ranked: Vec<&(String, usize)> = self
+            .entries
+            .iter()
+            .filter(|(keyword, _)| !is_noise_token(keyword))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(keyword, documents)| KeywordSuggestion {
+                keyword: keyword.clone(),
+                documents: *documents,
+            })
+            .collect()
+    }
+
+    /// Returns up to `limit` keywords starting with `prefix`, most common first.
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<KeywordSuggestion> {
+        let prefix = prefix.trim().to_lowercase();
+        if prefix.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+
+        let start = self
+            .entries
+            .partition_point(|(keyword, _)| keyword.as_str() < prefix.as_str());
+        let mut matches: Vec<&(String, usize)> = self.entries[start..]
+            .iter()
+            .take_while(|(keyword, _)| keyword.starts_with(&prefix))
+            .collect();
+        matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(keyword, documents)| KeywordSuggestion {
+                keyword: keyword.clone(),
+                documents: *documents,
+            })
+            .collect()
+    }
+}
+
+/// Jaccard similarity of two keyword lists (|A ∩ B| / |A ∪ B|), case-insensitive.
+/// Returns 0.0 when either list is empty.
+pub fn keyword_similarity(a: &[String], b: &[String]) -> f64 {
+    let a: HashSet<String> = a.iter().map(|keyword| keyword.to_lowercase()).collect();
+    let b: HashSet<String> = b.iter().map(|keyword| keyword.to_lowercase()).collect();
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let shared = a.intersection(&b).count();
+    let union = a.len() + b.len() - shared;
+    shared as f64 / union as f64
+}
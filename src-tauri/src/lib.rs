@@ -0,0 +1,23 @@
+//! Scanning, indexing and search for Slides Indexer, used by the desktop app
+//! (`main.rs`) and the `slide-indexer` command-line tool (`bin/slide-indexer.rs`).
+//! The modules tied to Tauri are behind the `desktop` feature, so the tool
+//! builds with `--no-default-features` on machines without GUI libraries.
+
+pub mod error;
+#[cfg(feature = "desktop")]
+pub mod http_api;
+pub mod index;
+pub mod inverted_index;
+pub mod item_diff;
+pub mod keyword_index;
+pub mod models;
+pub mod ocr_cache;
+pub mod pdf_fonts;
+pub mod pdf_streams;
+pub mod scanner;
+pub mod search;
+#[cfg(feature = "desktop")]
+pub mod state;
+pub mod text_cache;
+pub mod thumbnails;
+pub mod tool_permits;
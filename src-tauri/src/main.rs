@@ -1,25 +1,38 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod error;
-mod models;
-mod scanner;
-mod state;
-
 use std::{
-    path::{Path, PathBuf},
-    process::{Command, Stdio},
+    io::{self, Read},
+    path::Path,
+    process::{Command, ExitStatus, Stdio},
     sync::Arc,
+    thread,
+    time::{Duration, Instant},
 };
 
-use tauri::{async_runtime, AppHandle, Manager, State};
+use tauri::{async_runtime, AppHandle, ClipboardManager, Manager, RunEvent, State};
 
-use crate::{
-    models::{AppState, ScanSummary, SearchResponse},
+use slides_indexer::{
+    http_api,
+    models::{
+        AppState, IndexHealth, IndexStats, ItemDiff, KeywordSuggestion, OpenResult, OpenStatus, SavedSearch,
+        ScanSettings, ScanSummary, SearchOptions, SearchResponse, SimilarItem, SlideIndexItem,
+        QueryExplanation, ToolStatus,
+    },
+    scanner::{self, deck_exists, launchable_path},
     state::StateManager,
+    thumbnails,
 };
 
 type CommandResult<T> = std::result::Result<T, String>;
 
+/// Pause between launches in `open_many` so the OS handler isn't flooded.
+const OPEN_MANY_DELAY: Duration = Duration::from_millis(150);
+/// How long a launcher is watched for an early failure ("no application for
+/// this file type") before the file is assumed to be open.
+const LAUNCH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+const NO_HANDLER_MESSAGE: &str =
+    "No application is set up to open this file type; opened the containing folder instead";
+
 #[tauri::command]
 fn fetch_state(manager: State<Arc<StateManager>>) -> CommandResult<AppState> {
     Ok(manager.get_state())
@@ -38,9 +51,30 @@ async fn update_directories(
 }
 
 #[tauri::command]
-async fn rescan(manager: State<'_, Arc<StateManager>>) -> CommandResult<ScanSummary> {
+async fn rescan(
+    manager: State<'_, Arc<StateManager>>,
+    dry_run: Option<bool>,
+) -> CommandResult<ScanSummary> {
+    let manager = Arc::clone(manager.inner());
+    async_runtime::spawn_blocking(move || {
+        if dry_run.unwrap_or(false) {
+            manager.plan_rescan()
+        } else {
+            manager.rescan()
+        }
+    })
+    .await
+    .map_err(|error| error.to_string())?
+    .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn rescan_modified_since(
+    manager: State<'_, Arc<StateManager>>,
+    ts: Option<u64>,
+) -> CommandResult<ScanSummary> {
     let manager = Arc::clone(manager.inner());
-    async_runtime::spawn_blocking(move || manager.rescan())
+    async_runtime::spawn_blocking(move || manager.rescan_modified_since(ts))
         .await
         .map_err(|error| error.to_string())?
         .map_err(|error| error.to_string())
@@ -62,27 +96,349 @@ async fn rescan_directory(
 fn search_index(
     manager: State<Arc<StateManager>>,
     query: Option<String>,
+    options: Option<SearchOptions>,
 ) -> CommandResult<SearchResponse> {
     let query = query.unwrap_or_default();
-    Ok(manager.search(&query))
+    let options = options.unwrap_or_default();
+    Ok(manager.search(&query, &options))
 }
 
+/// Called when the user commits a search (Enter or opening a result), so
+/// the prefixes typed on the way there aren't recorded.
 #[tauri::command]
-fn open_slide_deck(
-    _app: AppHandle,
+fn record_search(manager: State<Arc<StateManager>>, query: String) -> CommandResult<()> {
+    manager.record_search(&query);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_recent_searches(manager: State<Arc<StateManager>>) -> CommandResult<Vec<String>> {
+    Ok(manager.get_recent_searches())
+}
+
+#[tauri::command]
+fn get_saved_searches(manager: State<Arc<StateManager>>) -> CommandResult<Vec<SavedSearch>> {
+    Ok(manager.get_saved_searches())
+}
+
+#[tauri::command]
+fn save_search(
     manager: State<Arc<StateManager>>,
+    name: String,
+    query: String,
+) -> CommandResult<Vec<SavedSearch>> {
+    manager
+        .save_search(&name, &query)
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn delete_saved_search(
+    manager: State<Arc<StateManager>>,
+    name: String,
+) -> CommandResult<Vec<SavedSearch>> {
+    manager
+        .delete_saved_search(&name)
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn suggest(
+    manager: State<Arc<StateManager>>,
+    prefix: String,
+    limit: usize,
+) -> CommandResult<Vec<KeywordSuggestion>> {
+    Ok(manager.suggest(&prefix, limit))
+}
+
+#[tauri::command]
+fn top_keywords(
+    manager: State<Arc<StateManager>>,
+    limit: usize,
+    within: Option<String>,
+) -> CommandResult<Vec<KeywordSuggestion>> {
+    Ok(manager.top_keywords(limit, within.as_deref()))
+}
+
+#[tauri::command]
+async fn open_slide_deck(
+    manager: State<'_, Arc<StateManager>>,
     id: String,
 ) -> CommandResult<()> {
+    let manager = Arc::clone(manager.inner());
+    async_runtime::spawn_blocking(move || {
+        let Some(item) = manager.find_item(&id) else {
+            return Err("Slide deck not found".to_string());
+        };
+
+        if !deck_exists(&item.path) {
+            return Err("Slide deck path no longer exists".to_string());
+        }
+        // A deck inside a zip archive is opened from an unpacked copy.
+        let path = launchable_path(&item.path).map_err(|error| error.to_string())?;
+
+        match open_or_reveal(path.as_path()) {
+            Ok(()) => {}
+            Err(OpenFailure::NoHandler) => return Err(NO_HANDLER_MESSAGE.to_string()),
+            Err(OpenFailure::Launch(error)) => return Err(error.to_string()),
+        }
+        if let Err(error) = manager.record_open(&id) {
+            println!("⚠️  Failed to record open for {}: {}", id, error);
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|error| error.to_string())?
+}
+
+/// Opens every id in turn; one failure doesn't stop the rest of the batch.
+#[tauri::command]
+async fn open_many(
+    manager: State<'_, Arc<StateManager>>,
+    ids: Vec<String>,
+) -> CommandResult<Vec<OpenResult>> {
+    let manager = Arc::clone(manager.inner());
+    async_runtime::spawn_blocking(move || {
+        let mut results = Vec::with_capacity(ids.len());
+        let mut launched_any = false;
+        for id in ids {
+            let (status, error) = match manager.find_item(&id) {
+                None => (OpenStatus::NotFound, None),
+                Some(item) if !deck_exists(&item.path) => (OpenStatus::PathMissing, None),
+                Some(item) => {
+                    if launched_any {
+                        thread::sleep(OPEN_MANY_DELAY);
+                    }
+                    launched_any = true;
+                    let opened = launchable_path(&item.path)
+                        .map_err(|error| OpenFailure::Launch(io::Error::other(error.to_string())))
+                        .and_then(|path| open_or_reveal(&path));
+                    match opened {
+                        Ok(()) => {
+                            if let Err(error) = manager.record_open(&id) {
+                                println!("⚠️  Failed to record open for {}: {}", id, error);
+                            }
+                            (OpenStatus::Opened, None)
+                        }
+                        Err(OpenFailure::NoHandler) => {
+                            (OpenStatus::NoHandler, Some(NO_HANDLER_MESSAGE.to_string()))
+                        }
+                        Err(OpenFailure::Launch(error)) => (OpenStatus::Failed, Some(error.to_string())),
+                    }
+                }
+            };
+            results.push(OpenResult { id, status, error });
+        }
+        results
+    })
+    .await
+    .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn add_tag(
+    manager: State<Arc<StateManager>>,
+    id: String,
+    tag: String,
+) -> CommandResult<Vec<String>> {
+    manager.add_tag(&id, &tag).map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn remove_tag(
+    manager: State<Arc<StateManager>>,
+    id: String,
+    tag: String,
+) -> CommandResult<Vec<String>> {
+    manager.remove_tag(&id, &tag).map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn set_display_name(
+    manager: State<Arc<StateManager>>,
+    id: String,
+    name: Option<String>,
+) -> CommandResult<SlideIndexItem> {
+    manager
+        .set_display_name(&id, name)
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn toggle_favorite(manager: State<Arc<StateManager>>, id: String) -> CommandResult<bool> {
+    manager.toggle_favorite(&id).map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn list_favorites(manager: State<Arc<StateManager>>) -> CommandResult<Vec<SlideIndexItem>> {
+    Ok(manager.list_favorites())
+}
+
+/// Copies the item's absolute path to the system clipboard and returns it.
+#[tauri::command]
+fn copy_path(
+    app: AppHandle,
+    manager: State<Arc<StateManager>>,
+    id: String,
+) -> CommandResult<String> {
     let Some(item) = manager.find_item(&id) else {
         return Err("Slide deck not found".to_string());
     };
+    app.clipboard_manager()
+        .write_text(item.path.clone())
+        .map_err(|error| error.to_string())?;
+    Ok(item.path)
+}
 
-    let path = PathBuf::from(item.path);
-    if !path.exists() {
-        return Err("Slide deck path no longer exists".to_string());
-    }
+/// Copies the item's extracted snippet to the system clipboard and returns it.
+#[tauri::command]
+fn copy_snippet(
+    app: AppHandle,
+    manager: State<Arc<StateManager>>,
+    id: String,
+) -> CommandResult<String> {
+    let Some(item) = manager.find_item(&id) else {
+        return Err("Slide deck not found".to_string());
+    };
+    app.clipboard_manager()
+        .write_text(item.snippet.clone())
+        .map_err(|error| error.to_string())?;
+    Ok(item.snippet)
+}
+
+#[tauri::command]
+fn recent_items(
+    manager: State<Arc<StateManager>>,
+    limit: usize,
+) -> CommandResult<Vec<SlideIndexItem>> {
+    Ok(manager.recent_items(limit))
+}
+
+/// Path of the item's first-slide PNG; rendered on first request.
+#[tauri::command]
+async fn get_thumbnail(manager: State<'_, Arc<StateManager>>, id: String) -> CommandResult<String> {
+    let manager = Arc::clone(manager.inner());
+    async_runtime::spawn_blocking(move || manager.get_thumbnail(&id))
+        .await
+        .map_err(|error| error.to_string())?
+        .map_err(|error| error.to_string())
+}
+
+/// Path of a PNG render of one PDF page; `dpi` is clamped to a sane range.
+#[tauri::command]
+async fn render_slide(
+    manager: State<'_, Arc<StateManager>>,
+    id: String,
+    index: u32,
+    dpi: Option<u32>,
+) -> CommandResult<String> {
+    let manager = Arc::clone(manager.inner());
+    let dpi = dpi.unwrap_or(thumbnails::DEFAULT_RENDER_DPI);
+    async_runtime::spawn_blocking(move || manager.render_slide(&id, index, dpi))
+        .await
+        .map_err(|error| error.to_string())?
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn get_stats(manager: State<Arc<StateManager>>) -> CommandResult<IndexStats> {
+    Ok(manager.stats())
+}
+
+/// Which of pdftoppm, pdftotext and tesseract are installed, with a structured
+/// warning (and install hint) for any that are missing.
+#[tauri::command]
+fn tool_status() -> CommandResult<ToolStatus> {
+    Ok(scanner::tool_status())
+}
+
+#[tauri::command]
+fn list_unindexed(manager: State<Arc<StateManager>>) -> CommandResult<Vec<SlideIndexItem>> {
+    Ok(manager.list_unindexed())
+}
+
+#[tauri::command]
+fn similar_items(
+    manager: State<Arc<StateManager>>,
+    id: String,
+    limit: usize,
+) -> CommandResult<Vec<SimilarItem>> {
+    manager
+        .similar_items(&id, limit)
+        .map_err(|error| error.to_string())
+}
 
-    launch_file(path.as_path()).map_err(|error| error.to_string())
+#[tauri::command]
+fn diff_items(
+    manager: State<Arc<StateManager>>,
+    id_a: String,
+    id_b: String,
+) -> CommandResult<ItemDiff> {
+    manager
+        .diff_items(&id_a, &id_b)
+        .map_err(|error| error.to_string())
+}
+
+/// True when the index was opened read-only (shared index); edits are refused.
+#[tauri::command]
+fn is_read_only(manager: State<Arc<StateManager>>) -> bool {
+    manager.is_read_only()
+}
+
+#[tauri::command]
+fn stop_scan(manager: State<Arc<StateManager>>) {
+    manager.stop_scan();
+}
+
+#[tauri::command]
+fn get_data_dir(manager: State<Arc<StateManager>>) -> CommandResult<String> {
+    Ok(manager.data_dir().to_string_lossy().into_owned())
+}
+
+/// Takes effect on the next launch; returns the directory that will be used.
+/// The copy runs off the main thread and reports `data-dir-progress`.
+#[tauri::command]
+async fn set_data_dir(manager: State<'_, Arc<StateManager>>, path: Option<String>) -> CommandResult<String> {
+    let manager = Arc::clone(manager.inner());
+    async_runtime::spawn_blocking(move || manager.set_data_dir(path))
+        .await
+        .map_err(|error| error.to_string())?
+        .map(|directory| directory.to_string_lossy().into_owned())
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn explain_query(manager: State<Arc<StateManager>>, query: String) -> CommandResult<QueryExplanation> {
+    Ok(manager.explain_query(&query))
+}
+
+#[tauri::command]
+fn add_excluded_directory(manager: State<Arc<StateManager>>, path: String) -> CommandResult<Vec<String>> {
+    manager
+        .add_excluded_directory(&path)
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn remove_excluded_directory(manager: State<Arc<StateManager>>, path: String) -> CommandResult<Vec<String>> {
+    manager
+        .remove_excluded_directory(&path)
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn get_settings(manager: State<Arc<StateManager>>) -> CommandResult<ScanSettings> {
+    Ok(manager.get_settings())
+}
+
+#[tauri::command]
+fn update_settings(
+    manager: State<Arc<StateManager>>,
+    settings: ScanSettings,
+) -> CommandResult<ScanSettings> {
+    manager
+        .update_settings(settings)
+        .map_err(|error| error.to_string())
 }
 
 #[tauri::command]
@@ -90,16 +446,140 @@ fn clear_cache(manager: State<Arc<StateManager>>) -> CommandResult<()> {
     manager.clear_cache().map_err(|error| error.to_string())
 }
 
-fn launch_file(path: &Path) -> Result<(), std::io::Error> {
+/// The item's complete extracted text; may re-extract the file, so runs off the main thread.
+#[tauri::command]
+async fn get_full_text(manager: State<'_, Arc<StateManager>>, id: String) -> CommandResult<String> {
+    let manager = Arc::clone(manager.inner());
+    async_runtime::spawn_blocking(move || manager.get_full_text(&id))
+        .await
+        .map_err(|error| error.to_string())?
+        .map_err(|error| error.to_string())
+}
+
+/// Writes edits still waiting for their debounced save.
+#[tauri::command]
+fn flush(manager: State<Arc<StateManager>>) -> CommandResult<()> {
+    manager.flush().map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn index_health(manager: State<Arc<StateManager>>) -> CommandResult<IndexHealth> {
+    manager.index_health().map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn reocr_item(manager: State<'_, Arc<StateManager>>, id: String) -> CommandResult<SlideIndexItem> {
+    let manager = Arc::clone(manager.inner());
+    async_runtime::spawn_blocking(move || manager.reocr_item(&id))
+        .await
+        .map_err(|error| error.to_string())?
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn clear_directory(manager: State<Arc<StateManager>>, path: String) -> CommandResult<usize> {
+    manager
+        .clear_directory(&path)
+        .map_err(|error| error.to_string())
+}
+
+/// Why `open_or_reveal` couldn't open a file.
+enum OpenFailure {
+    /// No application is associated with the file; `open_or_reveal` opened
+    /// its folder instead.
+    NoHandler,
+    /// The file couldn't be opened for any other reason.
+    Launch(io::Error),
+}
+
+/// Opens `path` with its default application. If the launcher reports it has
+/// nothing to open it with (common on minimal Linux desktops), the containing
+/// folder is opened instead so the user can at least find the file. Any other
+/// failure is returned as it is.
+fn open_or_reveal(path: &Path) -> Result<(), OpenFailure> {
+    let error = match launch_file(path) {
+        Ok(()) => return Ok(()),
+        Err(OpenFailure::NoHandler) => io::Error::other("no application is associated with this file"),
+        Err(OpenFailure::Launch(error)) => {
+            println!("⚠️  Could not open {}: {}", path.display(), error);
+            return Err(OpenFailure::Launch(error));
+        }
+    };
+    println!("⚠️  Could not open {}: {}", path.display(), error);
+    let Some(folder) = path.parent() else {
+        return Err(OpenFailure::Launch(error));
+    };
+    match launch_file(folder) {
+        Ok(()) => Err(OpenFailure::NoHandler),
+        Err(_) => Err(OpenFailure::Launch(error)),
+    }
+}
+
+/// Hands `path` to the platform launcher. A launcher that exits with an error
+/// within `LAUNCH_CHECK_TIMEOUT` counts as a failure (`NoHandler` when it
+/// found no application for the file); one still running by then is assumed
+/// to be showing the file and is left to finish on its own.
+fn launch_file(path: &Path) -> Result<(), OpenFailure> {
+    let mut command = launcher_command(path);
+    command.stdin(Stdio::null()).stdout(Stdio::null());
+    // macOS `open` only says why it failed on stderr. It returns right away,
+    // unlike xdg-open, which may exec the viewer itself and so must not be
+    // left holding a pipe.
+    command.stderr(if cfg!(target_os = "macos") { Stdio::piped() } else { Stdio::null() });
+    let mut child = command.spawn().map_err(OpenFailure::Launch)?;
+    let deadline = Instant::now() + LAUNCH_CHECK_TIMEOUT;
+    while Instant::now() < deadline {
+        if let Some(status) = child.try_wait().map_err(OpenFailure::Launch)? {
+            if status.success() {
+                return Ok(());
+            }
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = pipe.read_to_string(&mut stderr);
+            }
+            return Err(if launcher_found_no_handler(status, &stderr) {
+                OpenFailure::NoHandler
+            } else {
+                OpenFailure::Launch(io::Error::other(format!("launcher exited with {status}")))
+            });
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Whether a failed launcher reported that no application is associated
+/// with the file, as opposed to e.g. the file being unreadable.
+fn launcher_found_no_handler(status: ExitStatus, stderr: &str) -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        // LaunchServices' kLSApplicationNotFoundErr.
+        let _ = status;
+        stderr.contains("No application knows how to open") || stderr.contains("-10814")
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // `start` sets ERRORLEVEL to ERROR_NO_ASSOCIATION.
+        let _ = stderr;
+        status.code() == Some(1155)
+    }
+
+    #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+    {
+        // xdg-open: "a required tool could not be found", i.e. nothing to open it with.
+        let _ = stderr;
+        status.code() == Some(3)
+    }
+}
+
+fn launcher_command(path: &Path) -> Command {
     #[cfg(target_os = "macos")]
     {
-        Command::new("open")
-            .arg(path)
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-            .map(|_| ())
+        let mut command = Command::new("open");
+        command.arg(path);
+        command
     }
 
     #[cfg(target_os = "windows")]
@@ -113,22 +593,13 @@ fn launch_file(path: &Path) -> Result<(), std::io::Error> {
         command.args(["/C", "start", "", &quoted_path]);
         command.creation_flags(CREATE_NO_WINDOW);
         command
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-            .map(|_| ())
     }
 
     #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
     {
-        Command::new("xdg-open")
-            .arg(path)
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-            .map(|_| ())
+        let mut command = Command::new("xdg-open");
+        command.arg(path);
+        command
     }
 }
 
@@ -139,6 +610,12 @@ fn main() {
                 StateManager::new(&app.handle())
                     .map_err(|error| -> Box<dyn std::error::Error> { Box::new(error) })?,
             );
+            if let Some(port) = manager.get_settings().http_api_port {
+                // The app still works without the API, so a busy port is only logged.
+                if let Err(error) = http_api::start(Arc::clone(&manager), port) {
+                    println!("⚠️  {}", error);
+                }
+            }
             app.manage(manager);
             
             #[cfg(debug_assertions)]
@@ -153,11 +630,73 @@ fn main() {
             fetch_state,
             update_directories,
             rescan,
+            rescan_modified_since,
             rescan_directory,
             search_index,
+            record_search,
+            get_recent_searches,
+            get_saved_searches,
+            save_search,
+            delete_saved_search,
+            suggest,
+            top_keywords,
             open_slide_deck,
-            clear_cache
+            open_many,
+            recent_items,
+            copy_path,
+            copy_snippet,
+            add_tag,
+            remove_tag,
+            set_display_name,
+            toggle_favorite,
+            list_favorites,
+            get_thumbnail,
+            render_slide,
+            get_stats,
+            tool_status,
+            list_unindexed,
+            similar_items,
+            diff_items,
+            get_settings,
+            update_settings,
+            clear_cache,
+            clear_directory,
+            get_full_text,
+            reocr_item,
+            index_health,
+            flush,
+            add_excluded_directory,
+            remove_excluded_directory,
+            explain_query,
+            get_data_dir,
+            set_data_dir,
+            stop_scan,
+            is_read_only
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                if let Err(error) = app.state::<Arc<StateManager>>().shutdown() {
+                    println!("⚠️  Failed to save index on exit: {}", error);
+                }
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn only_xdg_open_tool_not_found_means_no_handler() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let exited = |code: i32| ExitStatus::from_raw(code << 8);
+        assert!(launcher_found_no_handler(exited(3), ""));
+        // 2: the file doesn't exist; 4: the action failed.
+        assert!(!launcher_found_no_handler(exited(2), ""));
+        assert!(!launcher_found_no_handler(exited(4), ""));
+    }
 }
@@ -1,11 +1,28 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SlidePreview {
     pub index: u32,
+    /// PPTX title placeholder, or for PDFs the first short line of the page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Flattened, single-spaced text; what search and keywords work on.
     pub text: String,
+    /// The same text one paragraph per line, indented by list level, for display.
+    /// Only set for PPTX slides with more than one paragraph.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outline: Option<String>,
 }
 
+/// One indexed file.
+///
+/// Most fields are *derived*: they are re-extracted from the file by
+/// `index_*` every time it is re-scanned. The fields below the
+/// `thumbnail_path` line are *user* fields: they only change through
+/// explicit commands and are carried over to the re-indexed item by
+/// `carry_user_fields_from`. New user fields must be added there as well.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SlideIndexItem {
@@ -24,9 +41,59 @@ pub struct SlideIndexItem {
     pub checksum: Option<String>,
     #[serde(default)]
     pub document_type: Option<DocumentType>,
+    #[serde(default)]
+    pub file_size: Option<u64>, // bytes
+    #[serde(default)]
+    pub links: Vec<String>, // hyperlink targets found in the document
+    #[serde(default)]
+    pub word_count: u32,
+    #[serde(default)]
+    pub reading_minutes: u32, // estimate derived from word_count
+    #[serde(default)]
+    pub language: Option<String>, // ISO 639-3, e.g. "eng"; None when undetermined
+    #[serde(default)]
+    pub presentation_title: Option<String>, // document metadata title, else the first slide's title
+    #[serde(default)]
+    pub encrypted: bool, // PDF needing a user password; nothing could be read from it
+    #[serde(default)]
+    pub text_coverage: Option<f32>, // share of slides/pages that gave text, 0.0-1.0; None when the total is unknown
+    #[serde(default)]
+    pub thumbnail_path: Option<String>, // cached PNG of the first slide/page
+    // --- user fields: preserved across rescans ---
+    #[serde(default)]
+    pub last_opened_at: Option<u64>, // set by open_slide_deck
+    #[serde(default)]
+    pub open_count: u32,
+    #[serde(default)]
+    pub tags: Vec<String>, // user-assigned, lowercase; searchable as `tag:<name>`
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub display_name: Option<String>, // shown instead of `name`; the file is not renamed
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl SlideIndexItem {
+    /// Copies every user field from an earlier version of the same file onto
+    /// this freshly indexed item. Derived fields are left untouched.
+    pub fn carry_user_fields_from(&mut self, previous: &SlideIndexItem) {
+        self.last_opened_at = previous.last_opened_at;
+        self.open_count = previous.open_count;
+        self.tags = previous.tags.clone();
+        self.favorite = previous.favorite;
+        self.display_name = previous.display_name.clone();
+    }
+
+    /// Name to show and sort by: the user's display name, else the deck's own
+    /// title, else the file name.
+    pub fn display_title(&self) -> &str {
+        self.display_name
+            .as_deref()
+            .or(self.presentation_title.as_deref())
+            .unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppState {
     #[serde(default)]
@@ -35,7 +102,104 @@ pub struct AppState {
     pub items: Vec<SlideIndexItem>,
     pub last_indexed_at: Option<u64>,
     #[serde(default)]
-    pub warnings: Vec<String>,
+    pub warnings: Vec<String>, // from the last scan
+    #[serde(default)]
+    pub errors: Vec<String>, // from the last scan, as "<path>: <message>"
+    #[serde(default)]
+    pub scan_errors: Vec<ScanError>, // the same errors, structured
+    #[serde(default)]
+    pub settings: ScanSettings,
+    #[serde(default)]
+    pub recent_searches: Vec<String>, // most recent first
+    #[serde(default)]
+    pub saved_searches: Vec<SavedSearch>,
+}
+
+/// A query the user stored under a name, e.g. "this week's exam decks".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+}
+
+/// User-tunable scan behaviour, persisted with the index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScanSettings {
+    /// Save `index.json` after this many newly indexed files (1 = after every file).
+    pub persist_every_items: usize,
+    /// ...or once this many milliseconds have passed since the last save (0 = every file).
+    /// A crash during a scan loses at most the files indexed since the last save.
+    pub persist_interval_ms: u64,
+    /// When PDFs are sent through tesseract.
+    pub ocr_mode: OcrMode,
+    /// Use pdftotext's output over the built-in PDF parser whenever it finds text.
+    pub prefer_pdftotext: bool,
+    /// Render a first-slide PNG for every item after a scan (needs pdftoppm / LibreOffice).
+    pub generate_thumbnails: bool,
+    /// Serve the local HTTP API on 127.0.0.1 at this port (None = off). Read at startup.
+    pub http_api_port: Option<u16>,
+    /// Build the token → item inverted index for search once the library has at
+    /// least this many items; smaller libraries are fast enough to scan.
+    pub inverted_index_min_items: usize,
+    /// Most external tool processes (pdftoppm, pdftotext, tesseract) running at once; 0 = CPU count.
+    pub max_concurrent_tools: usize,
+    /// Files bigger than this are skipped (with a warning) instead of indexed; 0 = no limit.
+    pub max_file_bytes: u64,
+    /// Keywords kept per item, and characters kept of each item's snippet. Both
+    /// are stored for every item, so raising them grows `index.json` (and the
+    /// memory it takes) roughly in proportion. Items indexed earlier keep their
+    /// old ones until they are indexed again (e.g. after clearing the cache).
+    pub max_keywords: usize,
+    pub snippet_chars: usize,
+    /// Shortest word (letters and digits) that counts as a keyword; 2 lets
+    /// "ai", "os" and "ip" through. Lower values are treated as 2.
+    pub min_token_chars: usize,
+    /// Leave out files and folders whose name starts with `.` (and, on Windows,
+    /// files marked hidden): sync-client shadow copies, caches, system junk.
+    pub skip_hidden: bool,
+    /// How many folder levels below each linked directory to look in (1 = only
+    /// files directly inside it); None = no limit.
+    pub max_depth: Option<usize>,
+    /// Also index decks inside `.zip` archives, stored as `archive.zip!inner/deck.pptx`.
+    /// Off by default: every changed archive is unpacked deck by deck.
+    pub scan_archives: bool,
+    /// Folders inside linked directories that scans leave alone. Decks already
+    /// indexed from them are kept as they are rather than dropped.
+    pub excluded_directories: Vec<String>,
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        Self {
+            persist_every_items: 25,
+            persist_interval_ms: 5_000,
+            ocr_mode: OcrMode::Auto,
+            prefer_pdftotext: false,
+            generate_thumbnails: false,
+            http_api_port: None,
+            inverted_index_min_items: 500,
+            max_concurrent_tools: 0,
+            max_file_bytes: 1_000_000_000,
+            max_keywords: 40,
+            snippet_chars: 240,
+            min_token_chars: 3,
+            skip_hidden: true,
+            max_depth: None,
+            scan_archives: false,
+            excluded_directories: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OcrMode {
+    Off, // never spawn tesseract
+    #[default]
+    Auto, // only when no other extractor found meaningful text
+    Force, // always OCR and prefer its text
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -46,6 +210,14 @@ pub struct ScanProgressPayload {
     pub debug_info: Option<String>, // debug messages for UI
 }
 
+/// Sent as `data-dir-progress` while `set_data_dir` copies the caches.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDirProgressPayload {
+    pub copied: usize,
+    pub total: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ScanSummary {
@@ -54,16 +226,335 @@ pub struct ScanSummary {
     pub scanned: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cached: Option<usize>,
+    /// Files and directories the scan failed on, as "<path>: <message>".
     pub errors: Vec<String>,
+    /// The same failures with path and message apart.
+    #[serde(default)]
+    pub scan_errors: Vec<ScanError>,
+    /// Things worth knowing that didn't fail, e.g. skipped or text-less files.
+    #[serde(default)]
+    pub warnings: Vec<String>,
     pub last_indexed_at: Option<u64>,
+    /// Set only by a dry-run rescan, which indexes nothing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<ScanPlan>,
+    /// Set by scans that walked the directories.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<ScanTiming>,
+}
+
+/// A file or directory a scan couldn't process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanError {
+    pub path: String,
+    pub message: String,
+}
+
+impl ScanError {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Where a scan spent its time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanTiming {
+    pub total_ms: u64,
+    pub indexing_ms: u64, // spent inside the indexers (parsing, pdftotext, OCR)
+    pub slowest: Vec<FileTiming>, // slowest files indexed, slowest first
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTiming {
+    pub path: String,
+    pub millis: u64,
+}
+
+/// What a rescan would do, without doing it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanPlan {
+    pub cached: usize,
+    pub changed: usize,
+    pub new: usize,
+    pub deleted: usize,
+    /// Over `max_file_bytes`.
+    pub skipped: usize,
+    /// The first few files that would not simply be reused from the cache.
+    pub samples: Vec<PlannedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedFile {
+    pub path: String,
+    pub action: PlannedAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlannedAction {
+    Changed,
+    New,
+    Deleted,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub item: SlideIndexItem,
+    #[serde(default)]
+    pub matched_slides: Vec<u32>, // SlidePreview.index values that satisfy the query
+    #[serde(default)]
+    pub relevance: u32, // matched slides, with a title match counting extra; see SortBy::Relevance
+    #[serde(default)]
+    pub snippets: Vec<MatchSnippet>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub explanation: Vec<TermMatch>, // only with SearchOptions::explain
+}
+
+/// From `explain_query`: how many items each part of a query occurs in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryExplanation {
+    pub terms: Vec<TermCount>,
+    pub matching_items: usize, // items matching the whole query, as `search` would return
+    pub total_items: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TermCount {
+    pub term: String, // phrases keep their quotes
+    pub items: usize,
+}
+
+/// Where one query term matched in an item, e.g. `tcp` in `["name", "slide:4"]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TermMatch {
+    pub term: String,
+    pub fields: Vec<String>,
+}
+
+/// A short excerpt of a matched slide around one occurrence of the query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchSnippet {
+    pub slide_index: u32,
+    pub text: String,
+    /// `[start, end)` character offsets into `text` of each query occurrence.
+    pub highlights: Vec<(usize, usize)>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResponse {
-    pub items: Vec<SlideIndexItem>,
+    pub items: Vec<SearchHit>,
     pub total: usize,
     pub last_indexed_at: Option<u64>,
+    #[serde(default)]
+    pub facets: SearchFacets,
+}
+
+/// Outcome of opening one id in an `open_many` batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenResult {
+    pub id: String,
+    pub status: OpenStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>, // launcher error when status is `failed`
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OpenStatus {
+    Opened,
+    NotFound,    // no indexed item with that id
+    PathMissing, // indexed, but the file is gone
+    NoHandler,   // no application for the file type; its folder was opened instead
+    Failed,      // the OS launcher returned an error
+}
+
+/// An item ranked by keyword overlap with another item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarItem {
+    #[serde(flatten)]
+    pub item: SlideIndexItem,
+    pub score: f64, // 0.0..=1.0 Jaccard similarity of the keyword sets
+}
+
+/// Number of matched items per `SlideKind`, counted over the full result set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFacets {
+    pub pptx: usize,
+    pub pdf: usize,
+    pub ppt: usize,
+}
+
+impl SearchFacets {
+    pub fn record(&mut self, kind: &SlideKind) {
+        match kind {
+            SlideKind::Pptx => self.pptx += 1,
+            SlideKind::Pdf => self.pdf += 1,
+            SlideKind::Ppt => self.ppt += 1,
+        }
+    }
+}
+
+/// Optional knobs for `search_index`; every field falls back to its default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchOptions {
+    pub sort_by: SortBy,
+    pub within: Option<String>, // only items under this directory
+    pub favorites_first: bool, // favorites lead, each group keeps `sort_by` order
+    pub whole_word: bool, // plain terms must match whole words instead of substrings
+    pub fuzzy: bool, // plain terms also match words 1-2 typos away
+    pub explain: bool, // fill SearchHit.explanation; off by default as it rescans every field
+    #[serde(flatten)]
+    pub filters: SearchFilters,
+}
+
+/// Metadata filters applied alongside the text query.
+///
+/// Timestamps are Unix epoch **milliseconds**, the same unit as
+/// `SlideIndexItem::updated_at`. Items with an unknown `slide_count` only
+/// pass the slide filters when `min_slides` is unset or 0.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchFilters {
+    pub updated_after: Option<u64>,  // inclusive: updated_at >= value
+    pub updated_before: Option<u64>, // exclusive: updated_at < value
+    pub min_slides: Option<u32>,
+    pub max_slides: Option<u32>,
+    pub language: Option<String>, // ISO 639-3 code, matched case-insensitively
+    pub max_text_coverage: Option<f32>, // inclusive; items with unknown coverage don't pass
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortBy {
+    #[default]
+    Newest, // updated_at, newest first
+    Oldest,
+    Name, // A–Z, case-insensitive
+    SlideCount, // most slides first
+    Size, // largest file first
+    WordCount, // most words first
+    Relevance, // highest SearchHit.relevance first
+    TextCoverage, // least text_coverage first, for triage; unknown last
+}
+
+/// What changed from deck `a_id` to deck `b_id`, from `diff_items`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemDiff {
+    pub a_id: String,
+    pub b_id: String,
+    pub slides_added: usize,
+    pub slides_removed: usize,
+    pub slides_modified: usize,
+    pub slides_unchanged: usize,
+    pub slides: Vec<SlideDiff>, // changed slides only, by index
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlideDiff {
+    pub index: u32, // the slide's place in b (in a for a removed slide)
+    pub change: SlideChange,
+    pub added: Vec<String>, // runs of words only in b (the whole text for an added slide)
+    pub removed: Vec<String>, // runs of words only in a (the whole text for a removed slide)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlideChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Aggregate figures over the whole index, for the dashboard.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStats {
+    pub total_items: usize,
+    pub items_by_kind: SearchFacets,
+    pub total_slides: u64,
+    pub total_size: u64, // bytes, over items with a known size
+    pub items_without_text: usize, // extraction found nothing usable
+    pub encrypted_items: usize, // password-protected PDFs, a subset of items_without_text
+    pub low_coverage_items: usize, // text from fewer than half the pages; likely scans needing OCR
+    pub last_indexed_at: Option<u64>,
+}
+
+/// Which external tools were found at startup, for the status panel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolStatus {
+    pub pdftoppm: bool,
+    pub pdftotext: bool,
+    pub tesseract: bool,
+    pub ocr_available: bool, // pdftoppm and tesseract both present
+    pub warnings: Vec<ToolWarning>,
+}
+
+/// Something about the installed tools the user can fix. `code` is stable, so
+/// the frontend can match on it and word the message itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolWarning {
+    pub code: String, // "tools_missing"
+    pub tools: Vec<String>,
+    pub hint: String, // install suggestion for this platform
+}
+
+/// Result of `check_index_health`: problems found in the loaded index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexHealth {
+    pub checked: usize, // items looked at
+    pub issues: Vec<HealthIssue>,
+    pub repaired: usize, // issues fixed by dropping the item
+}
+
+/// One problem with one item. `code` is stable so the frontend can group them
+/// ("3 indexed files are missing").
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthIssue {
+    pub code: String, // "missing_file", "empty_id_or_path", "duplicate_id" or "inconsistent_slides"
+    pub id: String,
+    pub path: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeywordSuggestion {
+    pub keyword: String,
+    pub documents: usize, // number of items whose keywords contain it
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,14 +571,3 @@ pub enum DocumentType {
     Presentation,
     Book,
 }
-
-impl Default for AppState {
-    fn default() -> Self {
-        Self {
-            directories: Vec::new(),
-            items: Vec::new(),
-            last_indexed_at: None,
-            warnings: Vec::new(),
-        }
-    }
-}
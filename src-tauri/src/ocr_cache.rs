@@ -0,0 +1,120 @@
+//! On-disk cache of per-page OCR text, so rescanning a PDF (or resuming an
+//! interrupted scan) doesn't run tesseract again on pages it already read.
+//!
+//! Entries are keyed by (file checksum, page, DPI, language). The cache is
+//! off until `set_dir` is called; the desktop app points it into its app-data
+//! directory.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use once_cell::sync::OnceCell;
+
+use crate::models::SlideIndexItem;
+
+static CACHE_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Enables the cache, storing entries in `dir`. Only the first call has an effect.
+pub fn set_dir(dir: PathBuf) {
+    let _ = CACHE_DIR.set(dir);
+}
+
+fn entry_path(dir: &Path, checksum: &str, page: usize, dpi: &str, language: &str) -> PathBuf {
+    dir.join(format!("{checksum}-p{page}-{dpi}dpi-{language}.txt"))
+}
+
+/// Cached text of `page` (1-based); an empty string is a page OCR found blank.
+pub fn get(checksum: &str, page: usize, dpi: &str, language: &str) -> Option<String> {
+    let dir = CACHE_DIR.get()?;
+    fs::read_to_string(entry_path(dir, checksum, page, dpi, language)).ok()
+}
+
+pub fn put(checksum: &str, page: usize, dpi: &str, language: &str, text: &str) {
+    let Some(dir) = CACHE_DIR.get() else {
+        return;
+    };
+    let written = fs::create_dir_all(dir)
+        .and_then(|()| fs::write(entry_path(dir, checksum, page, dpi, language), text));
+    if let Err(error) = written {
+        println!("⚠ Could not cache OCR text for page {}: {}", page, error);
+    }
+}
+
+/// Deletes every entry of the file with `checksum`, so the next index of it
+/// runs tesseract on each page again.
+pub fn evict(checksum: &str) {
+    let Some(dir) = CACHE_DIR.get() else {
+        return;
+    };
+    let evicted = evict_in(dir, checksum);
+    if evicted > 0 {
+        println!("🗑️  Evicted {} OCR cache entries for {}", evicted, checksum);
+    }
+}
+
+fn evict_in(dir: &Path, checksum: &str) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.split_once('-'))
+                .is_some_and(|(entry_checksum, _)| entry_checksum == checksum)
+        })
+        .filter(|entry| fs::remove_file(entry.path()).is_ok())
+        .count()
+}
+
+/// Deletes entries whose checksum no longer belongs to any indexed item.
+pub fn evict_stale(items: &[SlideIndexItem]) {
+    let Some(dir) = CACHE_DIR.get() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let live: HashSet<&str> = items.iter().filter_map(|item| item.checksum.as_deref()).collect();
+    let mut evicted = 0;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let is_stale = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.split_once('-'))
+            .is_some_and(|(checksum, _)| !live.contains(checksum));
+        if is_stale && fs::remove_file(entry.path()).is_ok() {
+            evicted += 1;
+        }
+    }
+    if evicted > 0 {
+        println!("🗑️  Evicted {} stale OCR cache entries", evicted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evict_removes_only_the_given_file() {
+        let dir = tempfile::tempdir().unwrap();
+        for (checksum, page, language) in [("abc", 1, "eng"), ("abc", 2, "deu"), ("abcd", 1, "eng"), ("xyz", 1, "eng")] {
+            fs::write(entry_path(dir.path(), checksum, page, "120", language), "text").unwrap();
+        }
+
+        assert_eq!(evict_in(dir.path(), "abc"), 2);
+
+        let mut left: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["abcd-p1-120dpi-eng.txt", "xyz-p1-120dpi-eng.txt"]);
+    }
+}
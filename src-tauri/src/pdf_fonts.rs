@@ -0,0 +1,441 @@
+//! Text of PDFs whose fonts show glyph ids rather than character codes (CID
+//! fonts, subsetted fonts). Read naively their strings are gibberish; each
+//! font's `/ToUnicode` CMap says which characters the codes stand for.
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static OBJECT_HEADER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(\d+)\s+\d+\s+obj\b").expect("valid regex"));
+static FONT_DICT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/Font\s*<<([^>]*)>>").expect("valid regex"));
+static FONT_DICT_REF_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/Font\s+(\d+)\s+\d+\s+R").expect("valid regex"));
+static NAMED_REF_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/([^\s/<>\[\]()]+)\s+(\d+)\s+\d+\s+R").expect("valid regex"));
+static TO_UNICODE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/ToUnicode\s+(\d+)\s+\d+\s+R").expect("valid regex"));
+static OBJSTM_FIRST_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/First\s+(\d+)").expect("valid regex"));
+static CMAP_SECTION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)begin(codespacerange|bfchar|bfrange)(.*?)end(?:codespacerange|bfchar|bfrange)")
+        .expect("valid regex")
+});
+static CMAP_TOKEN_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<([0-9A-Fa-f\s]*)>|\[|\]").expect("valid regex"));
+
+/// Entries a single `bfrange` may add; guards against absurd ranges in broken files.
+const MAX_CMAP_RANGE: u32 = 0x10000;
+/// A `TJ` adjustment (thousandths of an em) at least this far left is a word gap.
+const TJ_SPACE_THRESHOLD: f64 = -250.0;
+
+/// A `/ToUnicode` CMap: character code → the text it stands for.
+#[derive(Debug, Default)]
+pub(crate) struct CMap {
+    codespaces: Vec<(usize, u32, u32)>, // (code length in bytes, low, high)
+    chars: HashMap<u32, String>,
+}
+
+impl CMap {
+    /// Decodes a string operand, skipping codes the map doesn't know.
+    fn decode(&self, bytes: &[u8], out: &mut String) {
+        let mut position = 0;
+        while position < bytes.len() {
+            let length = self.code_length(&bytes[position..]);
+            let code = code_value(&bytes[position..(position + length).min(bytes.len())]);
+            if let Some(text) = self.chars.get(&code) {
+                out.push_str(text);
+            }
+            position += length;
+        }
+    }
+
+    fn code_length(&self, bytes: &[u8]) -> usize {
+        self.codespaces
+            .iter()
+            .find(|(length, low, high)| {
+                bytes.len() >= *length && (*low..=*high).contains(&code_value(&bytes[..*length]))
+            })
+            .map_or_else(
+                || self.codespaces.first().map_or(1, |(length, _, _)| *length),
+                |(length, _, _)| *length,
+            )
+    }
+}
+
+fn code_value(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |value, byte| (value << 8) | u32::from(*byte))
+}
+
+/// Parses a decoded CMap stream; None when it maps nothing.
+pub(crate) fn parse_cmap(data: &[u8]) -> Option<CMap> {
+    let text = String::from_utf8_lossy(data);
+    let mut cmap = CMap::default();
+    for section in CMAP_SECTION_REGEX.captures_iter(&text) {
+        let tokens: Vec<CMapToken> = CMAP_TOKEN_REGEX
+            .captures_iter(&section[2])
+            .map(|caps| match caps.get(1) {
+                Some(hex) => CMapToken::Hex(hex_bytes(hex.as_str())),
+                None if &caps[0] == "[" => CMapToken::Open,
+                None => CMapToken::Close,
+            })
+            .collect();
+        match &section[1] {
+            "codespacerange" => {
+                for pair in tokens.chunks_exact(2) {
+                    if let [CMapToken::Hex(low), CMapToken::Hex(high)] = pair {
+                        cmap.codespaces.push((low.len().max(1), code_value(low), code_value(high)));
+                    }
+                }
+            }
+            "bfchar" => {
+                for pair in tokens.chunks_exact(2) {
+                    if let [CMapToken::Hex(source), CMapToken::Hex(target)] = pair {
+                        cmap.chars.insert(code_value(source), utf16_text(target));
+                    }
+                }
+            }
+            _ => parse_bfrange(&tokens, &mut cmap),
+        }
+    }
+    if cmap.codespaces.is_empty() {
+        // Without a declared codespace, assume 2-byte codes (the CID font norm).
+        cmap.codespaces.push((2, 0, 0xFFFF));
+    }
+    (!cmap.chars.is_empty()).then_some(cmap)
+}
+
+enum CMapToken {
+    Hex(Vec<u8>),
+    Open,
+    Close,
+}
+
+/// `<low> <high> <target>` maps codes to consecutive characters from `target`;
+/// `<low> <high> [<t1> <t2> ...]` lists a target per code.
+fn parse_bfrange(tokens: &[CMapToken], cmap: &mut CMap) {
+    let mut index = 0;
+    while let [CMapToken::Hex(low), CMapToken::Hex(high), rest @ ..] = &tokens[index..] {
+        let (low, high) = (code_value(low), code_value(high));
+        let count = high.saturating_sub(low).min(MAX_CMAP_RANGE - 1) + 1;
+        match rest.first() {
+            Some(CMapToken::Hex(target)) => {
+                let mut units = utf16_units(target);
+                for offset in 0..count {
+                    cmap.chars.insert(low.saturating_add(offset), String::from_utf16_lossy(&units));
+                    if let Some(last) = units.last_mut() {
+                        *last = last.wrapping_add(1);
+                    }
+                }
+                index += 3;
+            }
+            Some(CMapToken::Open) => {
+                index += 3;
+                let mut offset = 0;
+                while let Some(CMapToken::Hex(target)) = tokens.get(index) {
+                    if offset < count {
+                        cmap.chars.insert(low.saturating_add(offset), utf16_text(target));
+                    }
+                    offset += 1;
+                    index += 1;
+                }
+                if matches!(tokens.get(index), Some(CMapToken::Close)) {
+                    index += 1;
+                }
+            }
+            _ => break,
+        }
+    }
+}
+
+fn hex_bytes(hex: &str) -> Vec<u8> {
+    let digits: Vec<u8> = hex.bytes().filter(|byte| byte.is_ascii_hexdigit()).collect();
+    digits
+        .chunks(2)
+        .filter_map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            // An odd final digit is padded with 0, as the spec says.
+            u8::from_str_radix(&format!("{pair:0<2}"), 16).ok()
+        })
+        .collect()
+}
+
+fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]))
+        .collect()
+}
+
+fn utf16_text(bytes: &[u8]) -> String {
+    String::from_utf16_lossy(&utf16_units(bytes))
+}
+
+/// The objects packed into a decoded `/Type /ObjStm` stream, as (number, text).
+/// `header` is the stream's dictionary, which holds the `/First` offset.
+pub(crate) fn object_stream_members(header: &str, decoded: &[u8]) -> Vec<(u32, String)> {
+    let Some(first) = OBJSTM_FIRST_REGEX
+        .captures(header)
+        .and_then(|caps| caps[1].parse::<usize>().ok())
+        .filter(|first| *first <= decoded.len())
+    else {
+        return Vec::new();
+    };
+    let index = String::from_utf8_lossy(&decoded[..first]);
+    let numbers: Vec<usize> = index
+        .split_whitespace()
+        .filter_map(|value| value.parse().ok())
+        .collect();
+    let entries: Vec<(u32, usize)> = numbers
+        .chunks_exact(2)
+        .map(|pair| (pair[0] as u32, first + pair[1]))
+        .collect();
+    entries
+        .iter()
+        .enumerate()
+        .filter_map(|(position, (number, start))| {
+            let end = entries.get(position + 1).map_or(decoded.len(), |(_, next)| *next);
+            let body = decoded.get(*start..end.max(*start))?;
+            Some((*number, String::from_utf8_lossy(body).into_owned()))
+        })
+        .collect()
+}
+
+/// `/ToUnicode` CMaps of a document's fonts, by the resource name content
+/// streams select them with (`/F1 12 Tf`). Resource names are per page, so a
+/// name reused for different fonts on different pages resolves to one of them.
+#[derive(Debug, Default)]
+pub(crate) struct PdfFonts {
+    cmap_by_name: HashMap<String, u32>,
+    cmaps: HashMap<u32, CMap>,
+}
+
+impl PdfFonts {
+    /// Links the font resource names found in `content` (the raw file) and in
+    /// `packed_objects` (members of object streams) to the `cmaps` parsed
+    /// from the file's streams, keyed by the CMap stream's object number.
+    pub(crate) fn build(
+        content: &str,
+        packed_objects: &HashMap<u32, String>,
+        cmaps: HashMap<u32, CMap>,
+    ) -> Self {
+        if cmaps.is_empty() {
+            return Self::default();
+        }
+        let object_starts: HashMap<u32, usize> = OBJECT_HEADER_REGEX
+            .captures_iter(content)
+            .filter_map(|caps| Some((caps[1].parse().ok()?, caps.get(0)?.end())))
+            .collect();
+        let object_body = |number: u32| -> Option<&str> {
+            if let Some(body) = packed_objects.get(&number) {
+                return Some(body);
+            }
+            let rest = &content[*object_starts.get(&number)?..];
+            let end = ["endobj", "stream"]
+                .iter()
+                .filter_map(|marker| rest.find(marker))
+                .min()
+                .unwrap_or(rest.len());
+            Some(&rest[..end])
+        };
+
+        let sources = std::iter::once(content).chain(packed_objects.values().map(String::as_str));
+        let mut font_dicts: Vec<&str> = Vec::new();
+        for source in sources {
+            font_dicts.extend(FONT_DICT_REGEX.captures_iter(source).filter_map(|caps| caps.get(1)).map(|body| body.as_str()));
+            font_dicts.extend(
+                FONT_DICT_REF_REGEX
+                    .captures_iter(source)
+                    .filter_map(|caps| object_body(caps[1].parse().ok()?)),
+            );
+        }
+
+        let mut cmap_by_name = HashMap::new();
+        for dict in font_dicts {
+            for caps in NAMED_REF_REGEX.captures_iter(dict) {
+                let cmap_number = caps[2]
+                    .parse()
+                    .ok()
+                    .and_then(object_body)
+                    .and_then(|font| TO_UNICODE_REGEX.captures(font))
+                    .and_then(|to_unicode| to_unicode[1].parse::<u32>().ok())
+                    .filter(|number| cmaps.contains_key(number));
+                if let Some(number) = cmap_number {
+                    cmap_by_name.insert(caps[1].to_string(), number);
+                }
+            }
+        }
+        Self { cmap_by_name, cmaps }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.cmap_by_name.is_empty()
+    }
+
+    fn cmap(&self, name: &[u8]) -> Option<&CMap> {
+        let number = self.cmap_by_name.get(std::str::from_utf8(name).ok()?)?;
+        self.cmaps.get(number)
+    }
+}
+
+/// Text shown by a content stream, in drawing order. Strings in a font with a
+/// CMap are decoded through it; others are read as single-byte characters.
+pub(crate) fn extract_text(stream: &[u8], fonts: &PdfFonts) -> String {
+    let mut text = String::new();
+    let mut cmap: Option<&CMap> = None;
+    let mut last_name: &[u8] = &[];
+    let mut in_array = false;
+    let mut needs_space = false;
+    let mut position = 0;
+
+    while position < stream.len() {
+        match stream[position] {
+            b'(' => {
+                let (bytes, next) = read_literal_string(stream, position + 1);
+                show_string(&mut text, &bytes, cmap, &mut needs_space);
+                position = next;
+            }
+            b'<' if stream.get(position + 1) == Some(&b'<') => position += 2,
+            b'<' => {
+                let end = stream[position..]
+                    .iter()
+                    .position(|byte| *byte == b'>')
+                    .map_or(stream.len(), |offset| position + offset);
+                let bytes = hex_bytes(&String::from_utf8_lossy(&stream[position + 1..end]));
+                show_string(&mut text, &bytes, cmap, &mut needs_space);
+                position = end + 1;
+            }
+            b'[' => {
+                in_array = true;
+                position += 1;
+            }
+            b']' => {
+                in_array = false;
+                position += 1;
+            }
+            b'/' => {
+                let end = token_end(stream, position + 1);
+                last_name = &stream[position + 1..end];
+                position = end;
+            }
+            b'%' => {
+                position = stream[position..]
+                    .iter()
+                    .position(|byte| *byte == b'\n' || *byte == b'\r')
+                    .map_or(stream.len(), |offset| position + offset);
+            }
+            b'-' | b'+' | b'.' | b'0'..=b'9' => {
+                let end = token_end(stream, position + 1);
+                let adjustment = std::str::from_utf8(&stream[position..end])
+                    .ok()
+                    .and_then(|number| number.parse::<f64>().ok());
+                if in_array && adjustment.is_some_and(|value| value <= TJ_SPACE_THRESHOLD) {
+                    needs_space = true;
+                }
+                position = end;
+            }
+            byte if byte.is_ascii_alphabetic() || byte == b'\'' || byte == b'"' => {
+                let end = token_end(stream, position + 1);
+                match &stream[position..end] {
+                    b"Tf" => cmap = fonts.cmap(last_name),
+                    b"Tj" | b"TJ" | b"'" | b"\"" | b"ET" | b"Td" | b"TD" | b"T*" => needs_space = true,
+                    // Inline image data is binary; skip to its `EI`.
+                    b"ID" => {
+                        position = find_inline_image_end(stream, end);
+                        continue;
+                    }
+                    _ => {}
+                }
+                position = end;
+            }
+            _ => position += 1,
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn show_string(text: &mut String, bytes: &[u8], cmap: Option<&CMap>, needs_space: &mut bool) {
+    if *needs_space && !text.is_empty() && !text.ends_with(char::is_whitespace) {
+        text.push(' ');
+    }
+    *needs_space = false;
+    match cmap {
+        Some(cmap) => cmap.decode(bytes, text),
+        None => text.extend(bytes.iter().map(|byte| char::from(*byte))),
+    }
+}
+
+fn token_end(stream: &[u8], start: usize) -> usize {
+    stream[start..]
+        .iter()
+        .position(|byte| byte.is_ascii_whitespace() || b"()<>[]{}/%".contains(byte))
+        .map_or(stream.len(), |offset| start + offset)
+}
+
+fn find_inline_image_end(stream: &[u8], start: usize) -> usize {
+    stream[start..]
+        .windows(3)
+        .position(|window| window[0].is_ascii_whitespace() && &window[1..] == b"EI")
+        .map_or(stream.len(), |offset| start + offset + 3)
+}
+
+/// Bytes of the literal string starting after its `(`, and the index after
+/// its closing `)`. Balanced parentheses nest; escapes follow the PDF spec.
+fn read_literal_string(stream: &[u8], start: usize) -> (Vec<u8>, usize) {
+    let mut bytes = Vec::new();
+    let mut depth = 0;
+    let mut position = start;
+    while position < stream.len() {
+        let byte = stream[position];
+        position += 1;
+        match byte {
+            b'(' => {
+                depth += 1;
+                bytes.push(byte);
+            }
+            b')' if depth == 0 => break,
+            b')' => {
+                depth -= 1;
+                bytes.push(byte);
+            }
+            b'\\' => {
+                let Some(&escaped) = stream.get(position) else {
+                    break;
+                };
+                position += 1;
+                match escaped {
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    b'b' => bytes.push(0x08),
+                    b'f' => bytes.push(0x0C),
+                    b'0'..=b'7' => {
+                        let mut value = u32::from(escaped - b'0');
+                        for _ in 0..2 {
+                            match stream.get(position) {
+                                Some(digit @ b'0'..=b'7') => {
+                                    value = value * 8 + u32::from(digit - b'0');
+                                    position += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        bytes.push(value as u8);
+                    }
+                    // A backslash at the end of a line continues the string.
+                    b'\r' => {
+                        if stream.get(position) == Some(&b'\n') {
+                            position += 1;
+                        }
+                    }
+                    b'\n' => {}
+                    other => bytes.push(other),
+                }
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    (bytes, position)
+}
@@ -0,0 +1,391 @@
+//! Locating and decoding PDF content streams: finding a stream's dictionary
+//! and declared length, then undoing its filters (Flate, LZW, ASCII85,
+//! ASCIIHex) so the text inside can be read.
+
+use std::io::{Cursor, Read};
+
+use flate2::read::ZlibDecoder;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::error::{AppError, Result};
+
+/// How far before a `stream` keyword its dictionary is looked for.
+const MAX_STREAM_DICTIONARY_BYTES: usize = 64 * 1024;
+
+/// Where the dictionary of the stream whose `stream` keyword is at
+/// `stream_pos` begins: the `<<` matching the `>>` just before the keyword.
+pub(crate) fn stream_dictionary_start(buffer: &[u8], stream_pos: usize) -> Option<usize> {
+    let floor = stream_pos.saturating_sub(MAX_STREAM_DICTIONARY_BYTES);
+    let mut end = stream_pos;
+    while end > floor && buffer[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    if end < floor + 2 || &buffer[end - 2..end] != b">>" {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut position = end;
+    while position >= floor + 2 {
+        match &buffer[position - 2..position] {
+            b">>" => {
+                depth += 1;
+                position -= 2;
+            }
+            b"<<" => {
+                depth -= 1;
+                position -= 2;
+                if depth == 0 {
+                    return Some(position);
+                }
+            }
+            _ => position -= 1,
+        }
+    }
+    None
+}
+
+/// (data start, data end, index after `endstream`) of a stream whose
+/// `dictionary` gives a direct `/Length`, with the data starting after the EOL
+/// at `data_offset`. None when the length is indirect (`/Length 12 0 R`),
+/// absent, or doesn't land on `endstream`, as happens in damaged files.
+pub(crate) fn declared_stream_bounds(
+    buffer: &[u8],
+    dictionary: &[u8],
+    data_offset: usize,
+) -> Option<(usize, usize, usize)> {
+    static LENGTH_REGEX: Lazy<regex::bytes::Regex> = Lazy::new(|| {
+        regex::bytes::Regex::new(r"/Length\s+(\d+)(\s+\d+\s+R)?").expect("valid regex")
+    });
+    let caps = LENGTH_REGEX.captures(dictionary)?;
+    if caps.get(2).is_some() {
+        return None;
+    }
+    let length: usize = std::str::from_utf8(&caps[1]).ok()?.parse().ok()?;
+    let data_start = match buffer.get(data_offset..)? {
+        [b'\r', b'\n', ..] => data_offset + 2,
+        [b'\n' | b'\r', ..] => data_offset + 1,
+        _ => return None,
+    };
+    let data_end = data_start.checked_add(length)?;
+    let after = buffer.get(data_end..)?;
+    let gap = after.iter().take_while(|byte| byte.is_ascii_whitespace()).count();
+    after[gap..]
+        .starts_with(b"endstream")
+        .then_some((data_start, data_end, data_end + gap + "endstream".len()))
+}
+
+/// Names in the `/Filter` entry of a stream dictionary, in the order they
+/// were applied to the data (a single name or an array).
+fn stream_filters(dictionary: &str) -> Vec<String> {
+    static FILTER_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"/Filter\s*(\[[^\]]*\]|/[A-Za-z0-9]+)").expect("valid regex"));
+    FILTER_REGEX
+        .captures(dictionary)
+        .map(|caps| {
+            caps[1]
+                .split('/')
+                .map(|name| name.trim_matches(|ch: char| ch.is_whitespace() || ch == '[' || ch == ']'))
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `raw` stream data undone through its dictionary's filters. Decoding stops
+/// at a filter that fails or isn't a text-preserving one (image codecs), and
+/// the data is returned as it was before that filter.
+pub(crate) fn decode_stream(raw: &[u8], dictionary: &str) -> Vec<u8> {
+    let mut data = raw.to_vec();
+    for filter in stream_filters(dictionary) {
+        let decoded = match filter.as_str() {
+            "FlateDecode" | "Fl" => inflate_data(&data),
+            "LZWDecode" | "LZW" => {
+                // EarlyChange defaults to 1; 0 is the only other legal value.
+                let early_change = !dictionary.contains("/EarlyChange 0");
+                lzw_decode(&data, early_change)
+            }
+            "ASCII85Decode" | "A85" => ascii85_decode(&data),
+            "ASCIIHexDecode" | "AHx" => Ok(ascii_hex_decode(&data)),
+            _ => break,
+        };
+        match decoded {
+            Ok(decoded) => data = decoded,
+            Err(_) => break,
+        }
+    }
+    data
+}
+
+/// LZWDecode: 9- to 12-bit codes, most significant bit first, with 256 as
+/// clear-table and 257 as end-of-data. With `early_change` the code width
+/// grows one code sooner, as most writers do.
+fn lzw_decode(data: &[u8], early_change: bool) -> Result<Vec<u8>> {
+    const CLEAR_TABLE: usize = 256;
+    const END_OF_DATA: usize = 257;
+    const MAX_TABLE_SIZE: usize = 4096;
+
+    let initial_table = || -> Vec<Vec<u8>> {
+        (0..=255u8).map(|byte| vec![byte]).chain([Vec::new(), Vec::new()]).collect()
+    };
+    let mut table = initial_table();
+    let mut code_width = 9;
+    let mut previous: Option<Vec<u8>> = None;
+    let mut output = Vec::new();
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+
+    for byte in data {
+        bits = (bits << 8) | u32::from(*byte);
+        bit_count += 8;
+        while bit_count >= code_width {
+            bit_count -= code_width;
+            let code = ((bits >> bit_count) & ((1 << code_width) - 1)) as usize;
+            bits &= (1 << bit_count) - 1;
+            match code {
+                CLEAR_TABLE => {
+                    table = initial_table();
+                    code_width = 9;
+                    previous = None;
+                    continue;
+                }
+                END_OF_DATA => return Ok(output),
+                _ => {}
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                // The one code that may be used before it's in the table.
+                (None, Some(previous)) if code == table.len() => {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                }
+                _ => return Err(AppError::Message(format!("invalid LZW code {code}"))),
+            };
+            output.extend_from_slice(&entry);
+            if let Some(mut grown) = previous.take() {
+                if table.len() < MAX_TABLE_SIZE {
+                    grown.push(entry[0]);
+                    table.push(grown);
+                }
+            }
+            previous = Some(entry);
+            if table.len() + usize::from(early_change) >= 1 << code_width && code_width < 12 {
+                code_width += 1;
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// ASCII85Decode: groups of five `!`..`u` digits for four bytes, `z` for four
+/// zero bytes, `~>` ending the data; a short final group holds fewer bytes.
+fn ascii85_decode(data: &[u8]) -> Result<Vec<u8>> {
+    let body = data
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .filter(|start| data[*start..].starts_with(b"<~"))
+        .map_or(data, |start| &data[start + 2..]);
+    let mut output = Vec::new();
+    let mut group: u64 = 0;
+    let mut digits = 0;
+    for byte in body {
+        match byte {
+            b'~' => break,
+            b'z' if digits == 0 => output.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group = group * 85 + u64::from(byte - b'!');
+                digits += 1;
+                if digits == 5 {
+                    output.extend_from_slice(&(group as u32).to_be_bytes());
+                    group = 0;
+                    digits = 0;
+                }
+            }
+            byte if byte.is_ascii_whitespace() => {}
+            other => {
+                return Err(AppError::Message(format!(
+                    "invalid ASCII85 byte 0x{other:02x}"
+                )))
+            }
+        }
+    }
+    if digits > 0 {
+        // Pad with the highest digit, then keep one byte less than digits read.
+        for _ in digits..5 {
+            group = group * 85 + 84;
+        }
+        output.extend_from_slice(&(group as u32).to_be_bytes()[..digits - 1]);
+    }
+    Ok(output)
+}
+
+/// ASCIIHexDecode: hex digit pairs up to `>`, whitespace ignored, an odd
+/// final digit padded with 0.
+fn ascii_hex_decode(data: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = data
+        .iter()
+        .take_while(|byte| **byte != b'>')
+        .filter_map(|byte| (*byte as char).to_digit(16))
+        .map(|digit| digit as u8)
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+fn inflate_data(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decoder = ZlibDecoder::new(Cursor::new(data));
+    let mut output = Vec::new();
+    decoder
+        .read_to_end(&mut output)
+        .map_err(|error| AppError::Message(error.to_string()))?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// LZW-encodes `data` the way PDF writers do, for round trips.
+    fn lzw_encode(data: &[u8], early_change: bool) -> Vec<u8> {
+        let mut table: HashMap<Vec<u8>, usize> = (0..=255u8).map(|byte| (vec![byte], usize::from(byte))).collect();
+        let mut next_code = 258;
+        let mut code_width = 9;
+        let mut bits: u64 = 0;
+        let mut bit_count = 0;
+        let mut output = Vec::new();
+        let mut emit = |code: usize, width: u32| {
+            bits = (bits << width) | code as u64;
+            bit_count += width;
+            while bit_count >= 8 {
+                bit_count -= 8;
+                output.push((bits >> bit_count) as u8);
+            }
+        };
+
+        emit(256, code_width);
+        let mut current: Vec<u8> = Vec::new();
+        for &byte in data {
+            let mut extended = current.clone();
+            extended.push(byte);
+            if table.contains_key(&extended) {
+                current = extended;
+                continue;
+            }
+            emit(table[&current], code_width);
+            table.insert(extended, next_code);
+            if next_code + usize::from(early_change) >= 1 << code_width {
+                code_width += 1;
+            }
+            next_code += 1;
+            current = vec![byte];
+        }
+        if !current.is_empty() {
+            emit(table[&current], code_width);
+        }
+        emit(257, code_width);
+        if bit_count > 0 {
+            output.push((bits << (8 - bit_count)) as u8);
+        }
+        output
+    }
+
+    fn ascii85_encode(data: &[u8]) -> Vec<u8> {
+        let mut output = b"<~".to_vec();
+        for chunk in data.chunks(4) {
+            if chunk == [0; 4] {
+                output.push(b'z');
+                continue;
+            }
+            let mut padded = [0u8; 4];
+            padded[..chunk.len()].copy_from_slice(chunk);
+            let mut group = u32::from_be_bytes(padded);
+            let mut digits = [0u8; 5];
+            for digit in digits.iter_mut().rev() {
+                *digit = (group % 85) as u8 + b'!';
+                group /= 85;
+            }
+            output.extend_from_slice(&digits[..chunk.len() + 1]);
+        }
+        output.extend_from_slice(b"~>");
+        output
+    }
+
+    /// Text with enough repetition to grow the LZW table past 511 entries.
+    fn sample_text() -> Vec<u8> {
+        let alphabet = b"BT /F1 12 Tf (Photosynthesis) Tj ET";
+        let mut seed: u32 = 1;
+        (0..6_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                alphabet[(seed >> 16) as usize % alphabet.len()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lzw_decodes_the_pdf_reference_example() {
+        // From the PDF reference: "-----A---B" with EarlyChange 1.
+        let encoded = [0x80, 0x0B, 0x60, 0x50, 0x22, 0x0C, 0x0C, 0x85, 0x01];
+        assert_eq!(lzw_decode(&encoded, true).unwrap(), b"-----A---B");
+    }
+
+    #[test]
+    fn lzw_round_trips_across_code_width_changes() {
+        let text = sample_text();
+        for early_change in [true, false] {
+            let encoded = lzw_encode(&text, early_change);
+            assert_eq!(lzw_decode(&encoded, early_change).unwrap(), text, "early change {early_change}");
+        }
+        // Reading early-change codes a code late gets the widths wrong.
+        assert_ne!(lzw_decode(&lzw_encode(&text, true), false).ok(), Some(text));
+    }
+
+    #[test]
+    fn lzw_honours_early_change_from_the_dictionary() {
+        let text = sample_text();
+        assert_eq!(decode_stream(&lzw_encode(&text, true), "<< /Filter /LZWDecode >>"), text);
+        assert_eq!(
+            decode_stream(&lzw_encode(&text, false), "<< /Filter /LZWDecode /DecodeParms << /EarlyChange 0 >> >>"),
+            text
+        );
+    }
+
+    #[test]
+    fn truncated_lzw_does_not_panic() {
+        let encoded = lzw_encode(&sample_text(), true);
+        for end in [0, 1, 2, encoded.len() / 2, encoded.len() - 1] {
+            let _ = lzw_decode(&encoded[..end], true);
+        }
+    }
+
+    #[test]
+    fn ascii85_decodes_known_groups() {
+        assert_eq!(ascii85_decode(b"<~9jqo^~>").unwrap(), b"Man ");
+        assert_eq!(ascii85_decode(b"<~F*2M7/c~>").unwrap(), b"sure.");
+        assert_eq!(ascii85_decode(b"z~>").unwrap(), [0; 4]);
+        assert_eq!(ascii85_decode(b"  <~9jq\no^~>").unwrap(), b"Man ");
+    }
+
+    #[test]
+    fn ascii85_round_trips_every_final_group_length() {
+        let data = b"\0\0\0\0Photosynthesis\xff\xfe\x00\x01";
+        for end in 0..=data.len() {
+            assert_eq!(ascii85_decode(&ascii85_encode(&data[..end])).unwrap(), &data[..end]);
+        }
+    }
+
+    #[test]
+    fn truncated_ascii85_does_not_panic() {
+        // No `~>`: whatever complete and partial groups there are come out.
+        assert_eq!(ascii85_decode(b"<~9jqo^").unwrap(), b"Man ");
+        assert_eq!(ascii85_decode(b"<~9jqo^F*2M7/c").unwrap(), b"Man sure.");
+        for input in [&b"<~"[..], b"<~9", b"<~9j", b"~", b"", b"uuuuu"] {
+            let _ = ascii85_decode(input);
+        }
+    }
+}
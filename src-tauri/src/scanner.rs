@@ -1,154 +1,109 @@
 use std::{
+    cmp::Reverse,
     collections::{HashMap, HashSet},
-    env,
-    fs::{self, File},
-    io::{Cursor, Read},
-    path::{Path, PathBuf},
-    process::{Command, Stdio},
-    time::{SystemTime, UNIX_EPOCH},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use flate2::read::ZlibDecoder;
-use globwalk::GlobWalkerBuilder;
-use once_cell::sync::Lazy;
-use regex::{escape, Regex, RegexBuilder};
+use globwalk::{DirEntry, GlobWalkerBuilder};
 use sha1::Sha1;
-use sha2::{Digest, Sha256};
-use tempfile::tempdir;
-use zip::ZipArchive;
+use sha2::Digest;
 
 use crate::{
     error::{AppError, Result},
-    models::{SlideIndexItem, SlideKind, SlidePreview},
+    index::path_within,
+    models::{FileTiming, ScanError, ScanSettings, ScanTiming, SlideIndexItem, SlideKind},
+    tool_permits,
 };
 
-const PPTX_GLOB: &str = "**/*.pptx";
-const PPT_GLOB: &str = "**/*.ppt";
-const PDF_GLOB: &str = "**/*.pdf";
-const MAX_SNIPPET_LENGTH: usize = 240;
-const MAX_KEYWORDS: usize = 40;
-const MAX_OCR_PAGES: usize = 40;
-const MIN_OCR_DPI: &str = "120";
-
-static TEXT_RUN_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"(?s)<a:t[^>]*>(.*?)</a:t>").expect("valid regex"));
-static PDF_TEXT_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"\((?:\\.|[^\\)])*\)").expect("valid regex"));
-static PDF_HEX_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"<([0-9A-Fa-f\s]+)>").expect("valid regex"));
-static TOKEN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[a-z0-9]{3,}").expect("valid regex"));
-static PAGE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"/Type\s*/Page\b").expect("valid regex"));
-
-static NOISE_WORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
-    HashSet::from([
-        "rectangle",
-        "title",
-        "subtitle",
-        "body",
-        "outline",
-        "placeholder",
-        "arial",
-        "calibri",
-        "bold",
-        "italic",
-        "regular",
-    ])
-});
-
-static NOISE_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
-    vec![
-        Regex::new(r"^[a-zA-Z]{2}-[a-zA-Z]{2}$").expect("valid regex"),
-        Regex::new(r"^latin-\d+$").expect("valid regex"),
-        Regex::new(r"^slide\d*$").expect("valid regex"),
-        Regex::new(r"^text\d*$").expect("valid regex"),
-    ]
-});
-
-static SEARCH_TOKEN_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r#""([^"]+)"|([^\s]+)"#).expect("valid regex"));
-
-struct CommandPaths {
-    pdftoppm: Option<PathBuf>,
-    tesseract: Option<PathBuf>,
-    pdftotext: Option<PathBuf>,
-}
-
-struct CommandStatus {
-    paths: CommandPaths,
-    missing: Vec<&'static str>,
-}
-
-static COMMAND_STATUS: Lazy<CommandStatus> = Lazy::new(resolve_command_status);
+mod archive;
+mod embedded;
+mod files;
+mod pdf;
+mod pdf_text;
+mod pdf_tools;
+mod plan;
+mod ppt;
+mod pptx;
+mod text;
+mod tools;
+
+pub use archive::{deck_exists, launchable_path, split_archive_path};
+pub use files::path_key;
+pub use plan::{decide_scan_action, decide_scan_action_since, plan_scan, ScanAction};
+pub use text::has_extracted_text;
+pub use tools::{is_ocr_available, ocr_status_message, tool_status};
+
+pub(crate) use text::is_noise_token;
+pub(crate) use tools::{pdftoppm_path, resolve_command, run_tool};
+
+use archive::{deck_sources, ARCHIVE_EXTENSION};
+use files::{calculate_file_checksum, file_modified_ms, file_size_bytes};
+use pdf::{index_pdf, index_pdf_bytes};
+use plan::short_checksum;
+use ppt::{index_ppt, index_ppt_bytes};
+use pptx::{index_pptx, index_pptx_bytes};
+
+/// File types the scanner indexes, by extension. A new format needs an entry
+/// here and an arm dispatching to its `index_*` function in `scan_directories`.
+const DECK_FORMATS: &[(&str, SlideKind)] = &[
+    ("pptx", SlideKind::Pptx),
+    ("ppt", SlideKind::Ppt),
+    ("pdf", SlideKind::Pdf),
+];
+const TEMPORARY_PREFIXES: &[&str] = &["~$", ".~lock.", "._"];
+const TEMPORARY_EXTENSIONS: &[&str] = &["tmp", "crdownload", "part"];
+const MAX_LINKS: usize = 50;
 
 #[derive(Debug)]
 pub struct ScanOutcome {
     pub items: Vec<SlideIndexItem>,
-    pub errors: Vec<String>,
+    pub errors: Vec<ScanError>,
+    pub warnings: Vec<String>,
     pub scanned_count: usize,
     pub cached_count: usize,
+    pub timing: ScanTiming,
 }
 
-#[derive(Debug)]
-pub struct SearchPattern {
-    terms: Vec<String>,
-    phrases: Vec<String>,
-    wildcards: Vec<Regex>,
-    is_empty: bool,
-}
+/// Files listed in `ScanTiming::slowest`.
+const MAX_SLOWEST_FILES: usize = 10;
 
-impl SearchPattern {
-    pub fn new(raw: &str) -> Self {
-        let mut terms = Vec::new();
-        let mut phrases = Vec::new();
-        let mut wildcards = Vec::new();
-
-        for capture in SEARCH_TOKEN_REGEX.captures_iter(raw) {
-            if let Some(phrase) = capture.get(1) {
-                let value = phrase.as_str().trim().to_lowercase();
-                if !value.is_empty() {
-                    phrases.push(value);
-                }
-            } else if let Some(token) = capture.get(2) {
-                let value = token.as_str().trim();
-                if value.is_empty() {
-                    continue;
-                }
-                if value.contains('*') || value.contains('?') {
-                    if let Some(regex) = wildcard_to_regex(value) {
-                        wildcards.push(regex);
-                    }
-                } else {
-                    terms.push(value.to_lowercase());
-                }
-            }
-        }
-
-        let is_empty = terms.is_empty() && phrases.is_empty() && wildcards.is_empty();
-        Self {
-            terms,
-            phrases,
-            wildcards,
-            is_empty,
-        }
-    }
+/// True when `path` lies in one of `settings.excluded_directories`.
+fn is_excluded(path: &str, settings: &ScanSettings) -> bool {
+    settings
+        .excluded_directories
+        .iter()
+        .any(|directory| path_within(path, directory))
 }
 
+/// Indexes every deck under `directories`, reusing `existing` entries that
+/// haven't changed. With `modified_since`, indexed files older than that
+/// timestamp are reused without even a checksum. Setting `stop` ends the scan
+/// before the next file; entries it didn't get to are kept as they were.
 pub fn scan_directories(
     directories: &[String],
     existing: &[SlideIndexItem],
+    settings: &ScanSettings,
+    modified_since: Option<u64>,
+    stop: &AtomicBool,
     progress: &mut dyn FnMut(&str, &str, Option<&str>),
     on_item_indexed: &mut dyn FnMut(SlideIndexItem),
 ) -> Result<ScanOutcome> {
     let mut aggregated = Vec::new();
     let mut errors = Vec::new();
+    let mut warnings = Vec::new();
     let mut existing_map: HashMap<String, SlideIndexItem> = HashMap::new();
     let mut scanned_count = 0;
     let mut cached_count = 0;
     let mut found_files: HashSet<String> = HashSet::new();
+    let scan_started = Instant::now();
+    let mut timing = ScanTiming::default();
+    tool_permits::set_limit(settings.max_concurrent_tools);
     
     // Build map of existing items
     for item in existing {
-        existing_map.insert(item.path.clone(), item.clone());
+        existing_map.insert(path_key(&item.path).into_owned(), item.clone());
     }
     
     println!("\n📊 Scan initialized:");
@@ -159,398 +114,194 @@ pub fn scan_directories(
     }
     println!();
 
-    for directory in directories {
+    let mut stopped = false;
+    'directories: for directory in directories {
         let path = Path::new(directory);
         if !path.exists() {
-            errors.push(format!("Directory not found: {directory}"));
+            errors.push(ScanError::new(directory.as_str(), "Directory not found"));
             continue;
         }
 
-        let pptx_files = GlobWalkerBuilder::from_patterns(path, &[PPTX_GLOB])
-            .max_depth(usize::MAX)
-            .case_insensitive(true)
-            .build()
-            .map_err(|err| AppError::Message(err.to_string()))?;
-
-        for entry in pptx_files.filter_map(|entry| entry.ok()) {
-            let file_path = entry.path().to_path_buf();
-            if is_temporary_deck(&file_path) {
-                continue;
+        for source in deck_sources(path, settings, &mut errors)? {
+            if stop.load(Ordering::SeqCst) {
+                stopped = true;
+                break 'directories;
             }
-            
-            // Track this file was found
-            found_files.insert(file_path.to_string_lossy().to_string());
-            
-            let modified_at = file_modified_ms(&file_path);
-            
-            // Quick check: if mod time unchanged, cache without calculating checksum
-            if let Some(existing) = existing_map.get(file_path.to_string_lossy().as_ref()) {
-                if let Some(mod_time) = modified_at {
-                    if existing.updated_at == mod_time {
-                        println!("✓ Cached (quick): {}", file_path.file_name().unwrap_or_default().to_string_lossy());
-                        let path_string = file_path.to_string_lossy().to_string();
-                        progress(&path_string, "cached", None);
-                        aggregated.push(existing.clone());
-                        cached_count += 1;
-                        continue;
-                    }
-                }
-            }
-            
-            // File changed or new - calculate checksum
-            let checksum = match calculate_file_checksum(&file_path) {
-                Ok(sum) => Some(sum),
-                Err(err) => {
-                    println!("⚠ Checksum failed for {}: {}", 
-                        file_path.file_name().unwrap_or_default().to_string_lossy(), err);
-                    None
-                }
+            let file_path = source.item_path();
+            let Some(kind) = deck_kind(&file_path) else {
+                continue;
             };
-            
-            // Check if checksum matches (content unchanged despite time change)
-            if let Some(existing) = existing_map.get(file_path.to_string_lossy().as_ref()) {
-                if let (Some(existing_checksum), Some(new_checksum)) = (&existing.checksum, &checksum) {
-                    if existing_checksum == new_checksum {
-                        println!("✓ Cached (checksum): {}", file_path.file_name().unwrap_or_default().to_string_lossy());
-                        let path_string = file_path.to_string_lossy().to_string();
-                        progress(&path_string, "cached", None);
-                        aggregated.push(existing.clone());
-                        cached_count += 1;
-                        continue;
-                    }
-                }
-                
-                let reason = match (&existing.checksum, &checksum) {
-                    (None, None) => "both checksums missing".to_string(),
-                    (None, Some(_)) => "existing has no checksum".to_string(),
-                    (Some(_), None) => "new checksum failed to calculate".to_string(),
-                    (Some(old), Some(new)) => format!("checksum changed: {}.. -> {}..", &old[..8], &new[..8]),
-                };
-                println!("⟳ Re-scanning (changed): {} - {}", 
-                    file_path.file_name().unwrap_or_default().to_string_lossy(),
-                    reason
-                );
-            }
-            
-            // Generate scan details for PPTX files being scanned
-            let mut msg = String::new();
-            let lookup_key = file_path.to_string_lossy();
-            
-            if let Some(existing) = existing_map.get(lookup_key.as_ref()) {
-                msg.push_str("📊 Rescan Information:\n");
-                msg.push_str(&format!("File content changed\n"));
-                if let (Some(old), Some(new)) = (&existing.checksum, &checksum) {
-                    if old != new {
-                        msg.push_str(&format!("Old checksum: {:?}\n", &old[..8]));
-                        msg.push_str(&format!("New checksum: {:?}", &new[..8]));
-                    }
-                }
-            } else {
-                msg.push_str("➕ New File Detected\n");
-                msg.push_str("First time indexing this file");
+            if is_temporary_deck(&file_path) {
+                continue;
             }
             
+            // Track this file was found; one reached through two linked directories is indexed once.
             let path_string = file_path.to_string_lossy().to_string();
-            progress(&path_string, "scanning", Some(&msg));
-            match index_pptx(&file_path, modified_at, checksum) {
-                Ok(item) => {
-                    on_item_indexed(item.clone());
-                    aggregated.push(item);
-                    scanned_count += 1;
-                }
-                Err(error) => errors.push(format!(
-                    "Failed to index PPTX {}: {}",
-                    file_path.display(),
-                    error
-                )),
+            if !found_files.insert(path_key(&path_string).into_owned()) {
+                continue;
             }
-        }
-
-        let ppt_files = GlobWalkerBuilder::from_patterns(path, &[PPT_GLOB])
-            .max_depth(usize::MAX)
-            .case_insensitive(true)
-            .build()
-            .map_err(|err| AppError::Message(err.to_string()))?;
+            let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+            
+            let modified_at = source.modified_ms();
+            let previous = existing_map.get(path_key(&path_string).as_ref());
 
-        for entry in ppt_files.filter_map(|entry| entry.ok()) {
-            let file_path = entry.path().to_path_buf();
-            if is_temporary_deck(&file_path) {
+            // Counted as found so an excluded deck isn't dropped as deleted; it just isn't touched.
+            if is_excluded(&path_string, settings) {
+                aggregated.extend(previous.cloned());
                 continue;
             }
             
-            // Track this file was found
-            found_files.insert(file_path.to_string_lossy().to_string());
-            
-            let modified_at = file_modified_ms(&file_path);
-            
             // Quick check: if mod time unchanged, cache without calculating checksum
-            if let Some(existing) = existing_map.get(file_path.to_string_lossy().as_ref()) {
-                if let Some(mod_time) = modified_at {
-                    if existing.updated_at == mod_time {
-                        println!("✓ Cached (quick): {}", file_path.file_name().unwrap_or_default().to_string_lossy());
-                        let path_string = file_path.to_string_lossy().to_string();
-                        progress(&path_string, "cached", None);
-                        aggregated.push(existing.clone());
-                        cached_count += 1;
-                        continue;
-                    }
-                }
+            if let (Some(existing), ScanAction::CachedByTime) =
+                (previous, decide_scan_action_since(previous, modified_at, modified_since))
+            {
+                println!("✓ Cached (quick): {}", file_name);
+                let reason = if modified_at == Some(existing.updated_at) {
+                    "mtime unchanged"
+                } else {
+                    "not modified since cutoff"
+                };
+                progress(&path_string, "cached", Some(reason));
+                aggregated.push(existing.clone());
+                cached_count += 1;
+                continue;
             }
             
+            if let Some(reason) = source.oversized_reason(settings.max_file_bytes) {
+                println!("⏭ Skipped: {} - {}", file_name, reason);
+                progress(&path_string, "skipped", Some(&reason));
+                // A deck that grew past the limit keeps its last indexed entry rather than vanishing.
+                let kept = if previous.is_some() { " (keeping the previous index entry)" } else { "" };
+                warnings.push(format!("Skipped {}: {}{}", file_path.display(), reason, kept));
+                aggregated.extend(previous.cloned());
+                continue;
+            }
+
             // File changed or new - calculate checksum
-            let checksum = match calculate_file_checksum(&file_path) {
+            let checksum = match source.checksum() {
                 Ok(sum) => Some(sum),
                 Err(err) => {
-                    println!("⚠ Checksum failed for {}: {}", 
-                        file_path.file_name().unwrap_or_default().to_string_lossy(), err);
+                    println!("⚠ Checksum failed for {}: {}", file_name, err);
                     None
                 }
             };
             
             // Check if checksum matches (content unchanged despite time change)
-            if let Some(existing) = existing_map.get(file_path.to_string_lossy().as_ref()) {
-                if let (Some(existing_checksum), Some(new_checksum)) = (&existing.checksum, &checksum) {
-                    if existing_checksum == new_checksum {
-                        println!("✓ Cached (checksum): {}", file_path.file_name().unwrap_or_default().to_string_lossy());
-                        let path_string = file_path.to_string_lossy().to_string();
-                        progress(&path_string, "cached", None);
-                        aggregated.push(existing.clone());
-                        cached_count += 1;
-                        continue;
-                    }
+            match (previous, decide_scan_action(previous, modified_at, &checksum)) {
+                (Some(existing), ScanAction::CachedByTime | ScanAction::CachedByChecksum) => {
+                    println!("✓ Cached (checksum): {}", file_name);
+                    progress(&path_string, "cached", Some("checksum match"));
+                    aggregated.push(existing.clone());
+                    cached_count += 1;
+                    continue;
                 }
-                
-                let reason = match (&existing.checksum, &checksum) {
-                    (None, None) => "both checksums missing".to_string(),
-                    (None, Some(_)) => "existing has no checksum".to_string(),
-                    (Some(_), None) => "new checksum failed to calculate".to_string(),
-                    (Some(old), Some(new)) => format!("checksum changed: {}.. -> {}..", &old[..8], &new[..8]),
-                };
-                println!("⟳ Re-scanning (changed): {} - {}", 
-                    file_path.file_name().unwrap_or_default().to_string_lossy(),
-                    reason
-                );
-            }
-            
-            // Generate scan details for PPT files being scanned
-            let mut msg = String::new();
-            let lookup_key = file_path.to_string_lossy();
-            
-            if let Some(existing) = existing_map.get(lookup_key.as_ref()) {
-                msg.push_str("📊 Rescan Information:\n");
-                msg.push_str(&format!("File content changed\n"));
-                if let (Some(old), Some(new)) = (&existing.checksum, &checksum) {
-                    if old != new {
-                        msg.push_str(&format!("Old checksum: {:?}\n", &old[..8]));
-                        msg.push_str(&format!("New checksum: {:?}", &new[..8]));
-                    }
+                (_, ScanAction::Rescan { reason }) => {
+                    println!("⟳ Re-scanning (changed): {} - {}", file_name, reason);
                 }
-            } else {
-                msg.push_str("➕ New File Detected\n");
-                msg.push_str("First time indexing this file");
+                _ => {}
             }
             
-            let path_string = file_path.to_string_lossy().to_string();
+            let msg = rescan_details(previous, modified_at, checksum.as_deref());
             progress(&path_string, "scanning", Some(&msg));
-            match index_ppt(&file_path, modified_at, checksum) {
-                Ok(item) => {
-                    on_item_indexed(item.clone());
-                    aggregated.push(item);
-                    scanned_count += 1;
-                }
-                Err(error) => errors.push(format!(
-                    "Failed to index PPT {}: {}",
-                    file_path.display(),
-                    error
-                )),
-            }
-        }
-
-        let pdf_files = GlobWalkerBuilder::from_patterns(path, &[PDF_GLOB])
-            .max_depth(usize::MAX)
-            .case_insensitive(true)
-            .build()
-            .map_err(|err| AppError::Message(err.to_string()))?;
-
-        for entry in pdf_files.filter_map(|entry| entry.ok()) {
-            let file_path = entry.path().to_path_buf();
-            if is_temporary_deck(&file_path) {
-                continue;
-            }
-            
-            // Track this file was found
-            found_files.insert(file_path.to_string_lossy().to_string());
-            
-            let is_problem_file = file_path.to_string_lossy().contains("LimanWu-NetworkSecurityAudit.pdf") ||
-                                 file_path.to_string_lossy().contains("Data Communications and Networking With TC - Behrouz A. Forouzan.pdf");
-            
-            if is_problem_file {
-                println!("\n=== DEBUG: Problem file detected ===");
-                println!("Path: {}", file_path.display());
-                println!("Path (as string): '{}'", file_path.to_string_lossy());
-                println!("Is in existing_map: {}", existing_map.contains_key(file_path.to_string_lossy().as_ref()));
-                
-                // Show similar paths in existing_map
-                println!("\n--- Searching existing_map for similar paths ---");
-                let current_name = file_path.file_name().unwrap_or_default().to_string_lossy();
-                for (cached_path, _) in &existing_map {
-                    if cached_path.contains(&*current_name) || cached_path.contains("Calibre") {
-                        println!("  Found similar: '{}'", cached_path);
-                    }
-                }
-                println!("--- End similar paths ---\n");
-            }
-            
-            let modified_at = file_modified_ms(&file_path);
-            if is_problem_file {
-                println!("Modified time: {:?}", modified_at);
-            }
-            
-            // Step 1: Quick check - try to cache based on modification time ONLY (no checksum yet)
-            if let Some(existing) = existing_map.get(file_path.to_string_lossy().as_ref()) {
-                // If modification time unchanged, cache immediately without calculating checksum
-                if let Some(mod_time) = modified_at {
-                    if existing.updated_at == mod_time {
-                        if is_problem_file {
-                            println!("=== Quick cache: mod time unchanged ===");
-                            println!("Existing: {}, Current: {}", existing.updated_at, mod_time);
-                            println!();
-                        }
-                        println!("✓ Cached (quick): {}", file_path.file_name().unwrap_or_default().to_string_lossy());
-                        let path_string = file_path.to_string_lossy().to_string();
-                        progress(&path_string, "cached", None);
-                        aggregated.push(existing.clone());
-                        cached_count += 1;
-                        continue;
-                    } else if is_problem_file {
-                        println!("⚠️ Modification time CHANGED!");
-                        println!("  Existing: {}", existing.updated_at);
-                        println!("  Current:  {}", mod_time);
-                    }
-                }
-            }
-            
-            // Step 2: File changed or new - calculate checksum for verification
-            let checksum = match calculate_file_checksum(&file_path) {
-                Ok(sum) => {
-                    if is_problem_file {
-                        println!("Checksum calculated: {}...", &sum[..16]);
-                    }
-                    Some(sum)
-                },
-                Err(err) => {
-                    println!("⚠ Checksum failed for {}: {}", 
-                        file_path.file_name().unwrap_or_default().to_string_lossy(), err);
-                    None
+            let index_started = Instant::now();
+            let archived_bytes = match source.archived_bytes() {
+                Ok(bytes) => bytes,
+                Err(error) => {
+                    errors.push(ScanError::new(
+                        path_string.as_str(),
+                        format!("Failed to extract from archive: {error}"),
+                    ));
+                    continue;
                 }
             };
-            
-            // Step 3: Check if we can cache based on checksum match
-            if let Some(existing) = existing_map.get(file_path.to_string_lossy().as_ref()) {
-                if is_problem_file {
-                    println!("Found in existing map:");
-                    println!("  Existing checksum: {:?}", existing.checksum.as_ref().map(|s| &s[..16]));
-                    println!("  Existing updated_at: {}", existing.updated_at);
-                    println!("  Has content: snippet={}, keywords={}", 
-                        !existing.snippet.trim().is_empty(), 
-                        !existing.keywords.is_empty()
-                    );
-                }
-                
-                // Check if checksums match (content unchanged despite time change)
-                if let (Some(existing_checksum), Some(new_checksum)) = (&existing.checksum, &checksum) {
-                    if existing_checksum == new_checksum {
-                        if is_problem_file {
-                            println!("=== Cache: checksum match (time changed but content same) ===\n");
-                        }
-                        println!("✓ Cached (checksum): {}", file_path.file_name().unwrap_or_default().to_string_lossy());
-                        let path_string = file_path.to_string_lossy().to_string();
-                        progress(&path_string, "cached", None);
-                        aggregated.push(existing.clone());
-                        cached_count += 1;
-                        continue;
+            let previous_language = previous.and_then(|previous| previous.language.as_deref());
+            // An archived deck is indexed from memory under its place in the archive.
+            let indexed = match (kind, &archived_bytes) {
+                (SlideKind::Pptx, None) => index_pptx(&file_path, modified_at, checksum, settings),
+                (SlideKind::Pptx, Some(bytes)) => index_pptx_bytes(bytes, &file_path, modified_at, checksum, settings),
+                (SlideKind::Ppt, None) => index_ppt(&file_path, modified_at, checksum, settings),
+                (SlideKind::Ppt, Some(bytes)) => index_ppt_bytes(bytes, &file_path, modified_at, checksum, settings),
+                (SlideKind::Pdf, None) => index_pdf(
+                    &file_path,
+                    modified_at,
+                    checksum,
+                    settings,
+                    progress,
+                    Some(msg),
+                    previous_language,
+                ),
+                (SlideKind::Pdf, Some(bytes)) => index_pdf_bytes(
+                    bytes,
+                    &file_path,
+                    None,
+                    modified_at,
+                    checksum,
+                    settings,
+                    progress,
+                    Some(msg),
+                    previous_language,
+                ),
+            };
+            record_file_timing(&mut timing, &path_string, index_started.elapsed());
+            match indexed {
+                Ok(mut item) => {
+                    if item.encrypted {
+                        warnings.push(format!(
+                            "Indexed {} without text: PDF is password-protected",
+                            file_path.display()
+                        ));
                     }
-                }
-                
-                // If we get here, file needs to be rescanned
-                let reason = match (&existing.checksum, &checksum) {
-                    (None, None) => "both checksums missing".to_string(),
-                    (None, Some(_)) => "existing has no checksum".to_string(),
-                    (Some(_), None) => "new checksum failed to calculate".to_string(),
-                    (Some(old), Some(new)) => {
-                        format!("checksum changed: {}.. -> {}..", &old[..8], &new[..8])
+                    if let Some(previous) = previous {
+                        item.carry_user_fields_from(previous);
                     }
-                };
-                if is_problem_file {
-                    println!("=== NOT Caching - will rescan ===");
-                    println!("Reason: {}", reason);
-                }
-                println!("⟳ Re-scanning (changed): {} - {}", 
-                    file_path.file_name().unwrap_or_default().to_string_lossy(),
-                    reason
-                );
-            } else if is_problem_file {
-                println!("NOT found in existing map - new file");
-                println!("=== Will scan as new file ===\n");
-            }
-            
-            let path_string = file_path.to_string_lossy().to_string();
-            
-            // Always generate debug info for files being scanned
-            let mut msg = String::new();
-            let lookup_key = file_path.to_string_lossy();
-            
-            if let Some(existing) = existing_map.get(lookup_key.as_ref()) {
-                msg.push_str("📊 Rescan Information:\n");
-                msg.push_str(&format!("Cached checksum: {:?}\n", existing.checksum.as_ref().map(|s| &s[..8])));
-                msg.push_str(&format!("Current checksum: {:?}\n", checksum.as_ref().map(|s| &s[..8])));
-                msg.push_str(&format!("Cached mod_time: {}\n", existing.updated_at));
-                msg.push_str(&format!("Current mod_time: {:?}\n", modified_at));
-                
-                if let (Some(old), Some(new)) = (&existing.checksum, &checksum) {
-                    if old == new {
-                        msg.push_str("\n✅ Checksums MATCH\nContent unchanged, rescanning due to time change");
-                    } else {
-                        msg.push_str("\n❌ File content CHANGED\nChecksum mismatch detected");
-                    }
-                } else if existing.checksum.is_none() {
-                    msg.push_str("\n⚠️ No cached checksum\nFirst scan or old cache format");
-                }
-            } else {
-                msg.push_str("➕ New File Detected\n");
-                msg.push_str(&format!("First time indexing this file\n"));
-                msg.push_str(&format!("Current mod_time: {:?}\n", modified_at));
-                msg.push_str(&format!("Checksum: {:?}", checksum.as_ref().map(|s| &s[..8])));
-            }
-            
-            let debug_msg = Some(msg.clone());
-            
-            progress(&path_string, "scanning", debug_msg.as_deref());
-            match index_pdf(&file_path, modified_at, checksum, progress, Some(msg)) {
-                Ok(item) => {
                     on_item_indexed(item.clone());
                     aggregated.push(item);
                     scanned_count += 1;
                 }
-                Err(error) => errors.push(format!(
-                    "Failed to index PDF {}: {}",
-                    file_path.display(),
-                    error
+                // A deck that got a password since it was indexed keeps its last entry,
+                // like an oversized one, rather than vanishing from the index.
+                Err(AppError::PasswordProtected) => {
+                    println!("🔒 Skipped (password-protected): {}", file_name);
+                    progress(&path_string, "skipped", Some("File is password-protected"));
+                    let kept = if previous.is_some() { " (keeping the previous index entry)" } else { "" };
+                    errors.push(ScanError::new(
+                        path_string.as_str(),
+                        format!("File is password-protected{kept}"),
+                    ));
+                    aggregated.extend(previous.cloned());
+                }
+                Err(error) => errors.push(ScanError::new(
+                    path_string.as_str(),
+                    format!("Failed to index {}: {}", kind_label(kind), error),
                 )),
             }
         }
     }
 
-    aggregated.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    if stopped {
+        println!("⏹ Scan stopped; files not reached keep their cached entries");
+        for (key, item) in &existing_map {
+            if found_files.insert(key.clone()) {
+                aggregated.push(item.clone());
+            }
+        }
+    }
+
+    // Items cached from an older index predate `file_size`; fill it in cheaply.
+    for item in aggregated.iter_mut().filter(|item| item.file_size.is_none()) {
+        item.file_size = file_size_bytes(Path::new(&item.path));
+    }
+
+    aggregated.sort_by_key(|item| Reverse(item.updated_at));
 
     // Print scan summary
     let total = scanned_count + cached_count;
     // Clean up: Remove deleted files from cache
     let mut deleted_count = 0;
-    for (cached_path, _) in &existing_map {
-        if !found_files.contains(cached_path) {
+    for (cached_key, cached_item) in &existing_map {
+        if !found_files.contains(cached_key) {
             println!("🗑️  Removed from cache (deleted): {}", 
-                Path::new(cached_path)
+                Path::new(&cached_item.path)
                     .file_name()
                     .unwrap_or_default()
                     .to_string_lossy()
@@ -571,803 +322,199 @@ pub fn scan_directories(
         if !errors.is_empty() {
             println!("  Errors:        {}", errors.len());
         }
+        if !warnings.is_empty() {
+            println!("  Warnings:      {}", warnings.len());
+        }
+        if let Some(slowest) = timing.slowest.first() {
+            println!("  Slowest:       {} ({:.1}s)", slowest.path, slowest.millis as f64 / 1000.0);
+        }
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
     }
 
+    timing.total_ms = scan_started.elapsed().as_millis() as u64;
     Ok(ScanOutcome {
         items: aggregated,
         errors,
+        warnings,
         scanned_count,
         cached_count,
+        timing,
     })
 }
 
-fn is_temporary_deck(path: &PathBuf) -> bool {
-    path.file_name()
-        .and_then(|name| name.to_str())
-        .map(|name| name.starts_with("~$"))
-        .unwrap_or(false)
+/// Indexes the single deck at `path`, outside of any scan: no cache, no
+/// progress events, no user fields. For tools and tests that want one file's
+/// extraction result.
+pub fn index_path(path: &Path, settings: &ScanSettings) -> Result<SlideIndexItem> {
+    index_path_with_language(path, settings, None)
 }
 
-fn index_pptx(path: &PathBuf, modified_at: Option<u64>, checksum: Option<String>) -> Result<SlideIndexItem> {
-    let file = File::open(path)?;
-    let mut archive = ZipArchive::new(file)?;
-    let mut slide_entries = Vec::new();
-
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-        let name = file.name().to_string();
-        if name.starts_with("ppt/slides/slide") && name.ends_with(".xml") {
-            let mut xml = String::new();
-            file.read_to_string(&mut xml)?;
-            slide_entries.push(xml);
-        }
-    }
-
-    let mut previews = Vec::new();
-    let mut combined_text = String::new();
-    for (index, xml) in slide_entries.into_iter().enumerate() {
-        let runs = extract_text_runs(&xml);
-        let stripped = strip_xml_tags(&runs);
-        let sanitized = strip_binary_artifacts(&stripped);
-        let filtered = filter_noise_tokens(&sanitized);
-        let text = cleanup_whitespace(&filtered);
-        if !text.is_empty() {
-            previews.push(SlidePreview {
-                index: index as u32 + 1,
-                text: text.clone(),
-            });
-            if !combined_text.is_empty() {
-                combined_text.push(' ');
-            }
-            combined_text.push_str(&text);
-        }
-    }
-
-    let cleaned_text = cleanup_whitespace(&combined_text);
-    let keywords = derive_keywords(&cleaned_text, &previews);
-
-    Ok(SlideIndexItem {
-        id: hash_of(path.to_string_lossy()),
-        path: path.to_string_lossy().to_string(),
-        name: path
-            .file_name()
-            .map(|name| name.to_string_lossy().to_string())
-            .unwrap_or_else(|| path.display().to_string()),
-        kind: SlideKind::Pptx,
-        slide_count: if previews.is_empty() {
-            None
-        } else {
-            Some(previews.len() as u32)
-        },
-        snippet: truncate_snippet(&cleaned_text),
-        keywords,
-        updated_at: modified_at.unwrap_or_else(current_timestamp),
-        slides: previews,
-        checksum,
-        document_type: Some(crate::models::DocumentType::Presentation),  // PPTX files are always presentations
-    })
-}
-
-fn index_pdf(
-    path: &PathBuf, 
-    modified_at: Option<u64>, 
-    checksum: Option<String>,
-    progress: &mut dyn FnMut(&str, &str, Option<&str>),
-    initial_scan_details: Option<String>,
+/// `index_path` for a file indexed before in `previous_language`, which OCR
+/// falls back to when the PDF's own text doesn't reveal its language.
+pub fn index_path_with_language(
+    path: &Path,
+    settings: &ScanSettings,
+    previous_language: Option<&str>,
 ) -> Result<SlideIndexItem> {
-    let mut file = File::open(path)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-
-    let contents = extract_pdf_contents(&buffer);
-    let stripped = strip_xml_tags(&contents.text);
-    let sanitized = strip_binary_artifacts(&stripped);
-    let filtered = filter_noise_tokens(&sanitized);
-    let cleaned = cleanup_whitespace(&filtered);
-
-    let (mut previews, combined_from_pages) = build_previews_from_pages(&contents.pages);
-    let mut snippet_source = if has_meaningful_text(&cleaned) {
-        cleaned.clone()
-    } else {
-        String::new()
-    };
-    let mut keyword_source = if has_meaningful_text(&combined_from_pages) {
-        combined_from_pages.clone()
-    } else {
-        String::new()
-    };
-
-    if !has_meaningful_text(&keyword_source) && has_meaningful_text(&snippet_source) {
-        keyword_source = snippet_source.clone();
-    }
-    if !has_meaningful_text(&snippet_source) && has_meaningful_text(&keyword_source) {
-        snippet_source = keyword_source.clone();
-    }
-
-    if (!has_meaningful_text(&snippet_source) || previews.is_empty())
-        && COMMAND_STATUS.paths.pdftotext.is_some()
+    let kind = deck_kind(path)
+        .ok_or_else(|| AppError::Message(format!("Not a slide deck: {}", path.display())))?;
+    let modified_at = file_modified_ms(path);
+    let checksum = calculate_file_checksum(path).ok();
+    match kind {
+        SlideKind::Pptx => index_pptx(path, modified_at, checksum, settings),
+        SlideKind::Ppt => index_ppt(path, modified_at, checksum, settings),
+        SlideKind::Pdf => index_pdf(path, modified_at, checksum, settings, &mut |_, _, _| {}, None, previous_language),
+    }
+}
+
+/// Adds one indexed file to `timing`, keeping only the `MAX_SLOWEST_FILES`
+/// slowest so a huge library doesn't grow the summary.
+fn record_file_timing(timing: &mut ScanTiming, path: &str, elapsed: Duration) {
+    let millis = elapsed.as_millis() as u64;
+    timing.indexing_ms += millis;
+    if timing.slowest.len() == MAX_SLOWEST_FILES
+        && timing.slowest.last().is_some_and(|fastest| fastest.millis >= millis)
     {
-        if let Ok(pdftotext_pages) = extract_pdf_with_pdftotext(path) {
-            let (text_previews, combined) = build_previews_from_pages(&pdftotext_pages);
-            if !text_previews.is_empty() {
-                previews = text_previews;
-            }
-            if has_meaningful_text(&combined) {
-                if !has_meaningful_text(&keyword_source) {
-                    keyword_source = combined.clone();
-                }
-                if !has_meaningful_text(&snippet_source) {
-                    snippet_source = combined.clone();
-                }
-            }
-        }
-    }
-
-    if !has_meaningful_text(&snippet_source) || previews.is_empty() {
-        println!("⟳ Running OCR on PDF: {}", path.file_name().unwrap_or_default().to_string_lossy());
-        let path_string = path.to_string_lossy().to_string();
-        
-        // Combine initial scan details with OCR status
-        let mut combined_msg = initial_scan_details.unwrap_or_default();
-        if !combined_msg.is_empty() {
-            combined_msg.push_str("\n\n━━━━━━━━━━━━━━━━━━━━━━\n\n");
-        }
-        combined_msg.push_str("🔍 OCR Processing:\nExtracting text from images...\nThis may take a few moments");
-        
-        progress(&path_string, "ocr", Some(&combined_msg));
-        
-        if let Ok(ocr_pages) = extract_pdf_with_ocr(path) {
-            let (ocr_previews, combined) = build_previews_from_pages(&ocr_pages);
-            if !ocr_previews.is_empty() {
-                previews = ocr_previews;
-            }
-            if has_meaningful_text(&combined) {
-                if !has_meaningful_text(&keyword_source) {
-                    keyword_source = combined.clone();
-                }
-                if !has_meaningful_text(&snippet_source) {
-                    snippet_source = combined.clone();
-                }
-            }
-        }
-    }
-
-    if !has_meaningful_text(&keyword_source) && has_meaningful_text(&snippet_source) {
-        keyword_source = snippet_source.clone();
-    }
-
-    let keywords = if has_meaningful_text(&keyword_source) {
-        derive_keywords(&keyword_source, &previews)
-    } else {
-        Vec::new()
-    };
-    let snippet = truncate_snippet(&snippet_source);
-
-    // Determine document type based on page orientation
-    let document_type = contents.is_landscape.map(|is_landscape| {
-        if is_landscape {
-            crate::models::DocumentType::Presentation
-        } else {
-            crate::models::DocumentType::Book
-        }
-    });
-    
-    Ok(SlideIndexItem {
-        id: hash_of(path.to_string_lossy()),
-        path: path.to_string_lossy().to_string(),
-        name: path
-            .file_name()
-            .map(|name| name.to_string_lossy().to_string())
-            .unwrap_or_else(|| path.display().to_string()),
-        kind: SlideKind::Pdf,
-        slide_count: contents.page_count.map(|value| value as u32),
-        snippet,
-        keywords,
-        updated_at: modified_at.unwrap_or_else(current_timestamp),
-        slides: previews,
-        checksum,
-        document_type,
-    })
-}
-
-fn index_ppt(path: &PathBuf, modified_at: Option<u64>, checksum: Option<String>) -> Result<SlideIndexItem> {
-    let buffer = fs::read(path)?;
-    let ascii: String = buffer
+        return;
+    }
+    let position = timing.slowest.partition_point(|entry| entry.millis >= millis);
+    timing.slowest.insert(position, FileTiming { path: path.to_string(), millis });
+    timing.slowest.truncate(MAX_SLOWEST_FILES);
+}
+
+/// Walks every file under `directory` with an extension from `DECK_FORMATS`,
+/// at most `max_depth` levels down. With `skip_hidden`, dot-named files and
+/// folders below `directory` (and, on Windows, files with the hidden
+/// attribute) are left out; hidden folders aren't entered at all.
+fn deck_walker(
+    directory: &Path,
+    settings: &ScanSettings,
+) -> Result<impl Iterator<Item = DirEntry>> {
+    let mut patterns: Vec<String> = DECK_FORMATS
         .iter()
-        .map(|byte| match byte {
-            0x09 | 0x0A | 0x0D => *byte as char,
-            0x20..=0x7E => *byte as char,
-            _ => ' ',
-        })
+        .map(|(extension, _)| format!("**/*.{extension}"))
         .collect();
-    let cleaned = cleanup_whitespace(&filter_noise_tokens(&strip_binary_artifacts(
-        &strip_xml_tags(&ascii),
-    )));
-    let previews = if cleaned.is_empty() || is_gibberish(&cleaned) {
-        Vec::new()
-    } else {
-        vec![SlidePreview {
-            index: 1,
-            text: cleaned.clone(),
-        }]
-    };
-    let effective_snippet = if previews.is_empty() {
-        String::new()
-    } else {
-        cleaned.clone()
-    };
-    let keywords = derive_keywords(&effective_snippet, &previews);
-
-    Ok(SlideIndexItem {
-        id: hash_of(path.to_string_lossy()),
-        path: path.to_string_lossy().to_string(),
-        name: path
-            .file_name()
-            .map(|name| name.to_string_lossy().to_string())
-            .unwrap_or_else(|| path.display().to_string()),
-        kind: SlideKind::Ppt,
-        slide_count: None,
-        snippet: truncate_snippet(&effective_snippet),
-        keywords,
-        updated_at: modified_at.unwrap_or_else(current_timestamp),
-        slides: previews,
-        checksum,
-        document_type: Some(crate::models::DocumentType::Presentation),  // PPT files are always presentations
-    })
-}
-
-struct PdfContents {
-    text: String,
-    page_count: Option<usize>,
-    pages: Vec<String>,
-    is_landscape: Option<bool>,
-}
-
-fn extract_pdf_contents(buffer: &[u8]) -> PdfContents {
-    let content = String::from_utf8_lossy(buffer);
-    let page_count = {
-        let count = PAGE_REGEX.find_iter(&content).count();
-        if count == 0 {
-            None
-        } else {
-            Some(count)
-        }
-    };
-
-    let mut segments = Vec::new();
-    let mut cursor = 0usize;
-
-    while let Some(stream_pos) = find_subsequence(&buffer[cursor..], b"stream") {
-        let absolute_stream_pos = cursor + stream_pos;
-        let data_offset = absolute_stream_pos + "stream".len();
-
-        let mut data_start = data_offset;
-        while data_start < buffer.len()
-            && (buffer[data_start] == b'\r' || buffer[data_start] == b'\n')
-        {
-            data_start += 1;
-        }
-        if data_start >= buffer.len() {
-            break;
-        }
-
-        if let Some(end_pos) = find_subsequence(&buffer[data_start..], b"endstream") {
-            let data_end = data_start + end_pos;
-            let raw = &buffer[data_start..data_end];
-
-            let header_start = absolute_stream_pos.saturating_sub(256);
-            let header_slice = &buffer[header_start..absolute_stream_pos];
-            let header = String::from_utf8_lossy(header_slice);
-            let has_flate = header.contains("/FlateDecode");
-
-            let decoded = if has_flate {
-                match inflate_data(raw) {
-                    Ok(decoded) => decoded,
-                    Err(_) => raw.to_vec(),
-                }
-            } else {
-                raw.to_vec()
-            };
-
-            let extracted = extract_text_from_pdf_stream(&decoded);
-            if !extracted.is_empty() {
-                segments.push(extracted);
-            }
-
-            cursor = data_end + "endstream".len();
-        } else {
-            break;
-        }
+    if settings.scan_archives {
+        patterns.push(format!("**/*.{ARCHIVE_EXTENSION}"));
     }
-
-    let is_landscape = detect_pdf_orientation(&content);
-    
-    PdfContents {
-        text: segments.join(" "),
-        page_count,
-        pages: segments,
-        is_landscape,
-    }
-}
-
-fn detect_pdf_orientation(content: &str) -> Option<bool> {
-    // Look for MediaBox in PDF structure: /MediaBox [x1 y1 x2 y2]
-    // x2-x1 = width, y2-y1 = height
-    // landscape = width > height
-    
-    let mediabox_regex = regex::Regex::new(r"/MediaBox\s*\[\s*(-?[\d.]+)\s+(-?[\d.]+)\s+(-?[\d.]+)\s+(-?[\d.]+)\s*\]").ok()?;
-    
-    if let Some(caps) = mediabox_regex.captures(content) {
-        let x1: f64 = caps.get(1)?.as_str().parse().ok()?;
-        let y1: f64 = caps.get(2)?.as_str().parse().ok()?;
-        let x2: f64 = caps.get(3)?.as_str().parse().ok()?;
-        let y2: f64 = caps.get(4)?.as_str().parse().ok()?;
-        
-        let width = (x2 - x1).abs();
-        let height = (y2 - y1).abs();
-        
-        Some(width > height)
-    } else {
-        None
+    if settings.skip_hidden {
+        // Later patterns win, and an excluded directory isn't descended into.
+        patterns.push("!.*".to_string());
     }
-}
-
-fn extract_pdf_with_pdftotext(path: &Path) -> Result<Vec<String>> {
-    let Some(pdftotext) = COMMAND_STATUS.paths.pdftotext.as_ref() else {
-        return Ok(Vec::new());
-    };
-
-    let output = Command::new(pdftotext)
-        .arg("-layout")
-        .arg("-enc")
-        .arg("UTF-8")
-        .arg(path)
-        .arg("-")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .output()
-        .map_err(|error| AppError::Message(error.to_string()))?;
-    if !output.status.success() {
-        return Ok(Vec::new());
-    }
-
-    let raw = String::from_utf8_lossy(&output.stdout);
-    let pages = raw
-        .split('\u{c}')
-        .map(|segment| segment.trim())
-        .filter(|segment| !segment.is_empty())
-        .map(|segment| segment.to_string())
-        .collect::<Vec<String>>();
-    Ok(pages)
-}
-
-fn extract_pdf_with_ocr(path: &Path) -> Result<Vec<String>> {
-    let commands = &COMMAND_STATUS.paths;
-    let (Some(pdftoppm), Some(tesseract)) = (&commands.pdftoppm, &commands.tesseract) else {
-        return Ok(Vec::new());
-    };
-
-    let temp_dir = tempdir().map_err(|error| AppError::Message(error.to_string()))?;
-    let prefix = temp_dir.path().join("page");
-
-    let status = Command::new(pdftoppm)
-        .arg("-png")
-        .arg("-r")
-        .arg(MIN_OCR_DPI)
-        .arg(path)
-        .arg(prefix.as_os_str())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .map_err(|error| AppError::Message(error.to_string()))?;
-    if !status.success() {
-        return Ok(Vec::new());
-    }
-
-    let mut images: Vec<PathBuf> = fs::read_dir(temp_dir.path())
-        .map_err(|error| AppError::Message(error.to_string()))?
+    let skip_hidden = settings.skip_hidden;
+    let walker = GlobWalkerBuilder::from_patterns(directory, &patterns)
+        .max_depth(settings.max_depth.unwrap_or(usize::MAX))
+        .case_insensitive(true)
+        .build()
+        .map_err(|err| AppError::Message(err.to_string()))?;
+    Ok(walker
         .filter_map(|entry| entry.ok())
-        .map(|entry| entry.path())
-        .filter(|path| {
-            path.extension()
-                .and_then(|ext| ext.to_str())
-                .map(|ext| ext.eq_ignore_ascii_case("png"))
-                .unwrap_or(false)
-        })
-        .collect();
-
-    images.sort();
-
-    let mut pages = Vec::new();
-    for (index, image_path) in images.into_iter().enumerate() {
-        if index >= MAX_OCR_PAGES {
-            break;
-        }
-        let output = Command::new(tesseract)
-            .arg(&image_path)
-            .arg("stdout")
-            .arg("-l")
-            .arg("eng")
-            .arg("--psm")
-            .arg("6")
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .output()
-            .map_err(|error| AppError::Message(error.to_string()))?;
-        if !output.status.success() {
-            continue;
-        }
-        let text = String::from_utf8_lossy(&output.stdout).to_string();
-        if text.trim().is_empty() {
-            continue;
-        }
-        pages.push(text);
-    }
-
-    Ok(pages)
+        .filter(move |entry| !(skip_hidden && has_hidden_attribute(entry))))
 }
 
-fn extract_text_from_pdf_stream(stream: &[u8]) -> String {
-    let content = String::from_utf8_lossy(stream);
-    let mut segments: Vec<String> = Vec::new();
-
-    for mat in PDF_TEXT_REGEX.find_iter(&content) {
-        let raw = mat.as_str();
-        if raw.len() < 2 {
-            continue;
-        }
-        let decoded = decode_pdf_string(&raw[1..raw.len() - 1]);
-        if !decoded.is_empty() {
-            segments.push(decoded);
-        }
-    }
-
-    for caps in PDF_HEX_REGEX.captures_iter(&content) {
-        if let Some(segment) = caps.get(1) {
-            let decoded = decode_pdf_hex_string(segment.as_str());
-            if !decoded.is_empty() {
-                segments.push(decoded);
-            }
-        }
-    }
-
-    segments.join(" ")
+#[cfg(windows)]
+fn has_hidden_attribute(entry: &DirEntry) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    entry
+        .metadata()
+        .is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
 }
 
-fn decode_pdf_string(input: &str) -> String {
-    let mut chars = input.chars().peekable();
-    let mut result = String::new();
-
-    while let Some(ch) = chars.next() {
-        if ch != '\\' {
-            result.push(ch);
-            continue;
-        }
-        match chars.peek() {
-            Some('n') => {
-                result.push('\n');
-                chars.next();
-            }
-            Some('r') => {
-                result.push('\r');
-                chars.next();
-            }
-            Some('t') => {
-                result.push('\t');
-                chars.next();
-            }
-            Some('b') => {
-                result.push('\u{0008}');
-                chars.next();
-            }
-            Some('f') => {
-                result.push('\u{000C}');
-                chars.next();
-            }
-            Some('(') => {
-                result.push('(');
-                chars.next();
-            }
-            Some(')') => {
-                result.push(')');
-                chars.next();
-            }
-            Some('\\') => {
-                result.push('\\');
-                chars.next();
-            }
-            Some(oct @ '0'..='7') => {
-                let mut octal = String::new();
-                octal.push(*oct);
-                chars.next();
-                for _ in 0..2 {
-                    if let Some(next) = chars.peek() {
-                        if next.is_ascii_digit() && *next < '8' {
-                            octal.push(*next);
-                            chars.next();
-                        } else {
-                            break;
-                        }
-                    }
-                }
-                if let Ok(value) = u8::from_str_radix(&octal, 8) {
-                    result.push(value as char);
-                }
-            }
-            Some(other) => {
-                result.push(*other);
-                chars.next();
-            }
-            None => {}
-        }
-    }
-
-    result
+#[cfg(not(windows))]
+fn has_hidden_attribute(_entry: &DirEntry) -> bool {
+    false
 }
 
-fn decode_pdf_hex_string(input: &str) -> String {
-    let sanitized: String = input.chars().filter(|ch| !ch.is_whitespace()).collect();
-    if sanitized.is_empty() {
-        return String::new();
-    }
-
-    let mut bytes = Vec::new();
-    let mut chars = sanitized.chars().peekable();
-    while let Some(first) = chars.next() {
-        let second = chars.peek().copied().unwrap_or('0');
-        let pair = format!("{first}{second}");
-        if let Ok(value) = u8::from_str_radix(&pair, 16) {
-            bytes.push(value);
-        }
-        if chars.peek().is_some() {
-            chars.next();
-        }
-    }
-
-    decode_pdf_encoded_bytes(&bytes)
-}
-
-fn decode_pdf_encoded_bytes(bytes: &[u8]) -> String {
-    if bytes.len() >= 2 {
-        match (bytes[0], bytes[1]) {
-            (0xFE, 0xFF) => {
-                let units: Vec<u16> = bytes[2..]
-                    .chunks(2)
-                    .filter_map(|chunk| {
-                        if chunk.len() == 2 {
-                            Some(u16::from_be_bytes([chunk[0], chunk[1]]))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                if let Ok(value) = String::from_utf16(&units) {
-                    return value;
-                }
-            }
-            (0xFF, 0xFE) => {
-                let units: Vec<u16> = bytes[2..]
-                    .chunks(2)
-                    .filter_map(|chunk| {
-                        if chunk.len() == 2 {
-                            Some(u16::from_le_bytes([chunk[0], chunk[1]]))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                if let Ok(value) = String::from_utf16(&units) {
-                    return value;
-                }
+fn deck_kind(path: &Path) -> Option<&'static SlideKind> {
+    let extension = path.extension()?.to_str()?;
+    DECK_FORMATS
+        .iter()
+        .find(|(known, _)| extension.eq_ignore_ascii_case(known))
+        .map(|(_, kind)| kind)
+}
+
+fn kind_label(kind: &SlideKind) -> &'static str {
+    match kind {
+        SlideKind::Pptx => "PPTX",
+        SlideKind::Ppt => "PPT",
+        SlideKind::Pdf => "PDF",
+    }
+}
+
+/// Progress details shown while a changed or new file is being indexed.
+fn rescan_details(
+    existing: Option<&SlideIndexItem>,
+    modified_at: Option<u64>,
+    checksum: Option<&str>,
+) -> String {
+    let mut msg = String::new();
+    if let Some(existing) = existing {
+        msg.push_str("📊 Rescan Information:\n");
+        msg.push_str(&format!("Cached checksum: {:?}\n", existing.checksum.as_deref().map(short_checksum)));
+        msg.push_str(&format!("Current checksum: {:?}\n", checksum.map(short_checksum)));
+        msg.push_str(&format!("Cached mod_time: {}\n", existing.updated_at));
+        msg.push_str(&format!("Current mod_time: {:?}\n", modified_at));
+
+        if let (Some(old), Some(new)) = (existing.checksum.as_deref(), checksum) {
+            if old == new {
+                msg.push_str("\n✅ Checksums MATCH\nContent unchanged, rescanning due to time change");
+            } else {
+                msg.push_str("\n❌ File content CHANGED\nChecksum mismatch detected");
             }
-            _ => {}
+        } else if existing.checksum.is_none() {
+            msg.push_str("\n⚠️ No cached checksum\nFirst scan or old cache format");
         }
+    } else {
+        msg.push_str("➕ New File Detected\n");
+        msg.push_str("First time indexing this file\n");
+        msg.push_str(&format!("Current mod_time: {:?}\n", modified_at));
+        msg.push_str(&format!("Checksum: {:?}", checksum.map(short_checksum)));
     }
-    match String::from_utf8(bytes.to_vec()) {
-        Ok(value) => value,
-        Err(_) => bytes.iter().map(|&byte| byte as char).collect::<String>(),
-    }
+    msg
 }
 
-fn extract_text_runs(xml: &str) -> String {
-    TEXT_RUN_REGEX
-        .captures_iter(xml)
-        .filter_map(|capture| capture.get(1))
-        .map(|segment| decode_xml(segment.as_str()))
-        .filter(|segment| !segment.trim().is_empty())
-        .map(|segment| segment.trim().to_string())
-        .collect::<Vec<String>>()
-        .join(" ")
-}
-
-fn decode_xml(input: &str) -> String {
-    let mut output = input
-        .replace("&lt;", "<")
-        .replace("&gt;", ">")
-        .replace("&amp;", "&")
-        .replace("&quot;", "\"")
-        .replace("&apos;", "'");
-
-    static DECIMAL_ENTITY: Lazy<Regex> =
-        Lazy::new(|| Regex::new(r"&#(\d+);").expect("valid regex"));
-    static HEX_ENTITY: Lazy<Regex> =
-        Lazy::new(|| Regex::new(r"&#x([0-9a-fA-F]+);").expect("valid regex"));
-
-    output = DECIMAL_ENTITY
-        .replace_all(&output, |caps: &regex::Captures| {
-            caps.get(1)
-                .and_then(|m| m.as_str().parse::<u32>().ok())
-                .and_then(char::from_u32)
-                .map(|c| c.to_string())
-                .unwrap_or_default()
-        })
-        .into_owned();
-
-    output = HEX_ENTITY
-        .replace_all(&output, |caps: &regex::Captures| {
-            caps.get(1)
-                .and_then(|m| u32::from_str_radix(m.as_str(), 16).ok())
-                .and_then(char::from_u32)
-                .map(|c| c.to_string())
-                .unwrap_or_default()
-        })
-        .into_owned();
-
-    output
-}
-
-fn strip_binary_artifacts(input: &str) -> String {
-    input
-        .chars()
-        .map(|ch| {
-            if ch == '\u{FFFD}' || (ch.is_control() && !matches!(ch, '\n' | '\r' | '\t')) {
-                ' '
-            } else {
-                ch
-            }
-        })
-        .collect()
-}
-
-fn strip_xml_tags(input: &str) -> String {
-    static TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").expect("valid regex"));
-    TAG_REGEX.replace_all(input, " ").to_string()
-}
-
-fn cleanup_whitespace(input: &str) -> String {
-    input.split_whitespace().collect::<Vec<&str>>().join(" ")
-}
-
-fn filter_noise_tokens(input: &str) -> String {
-    input
-        .split_whitespace()
-        .filter(|token| !is_noise_token(token))
-        .collect::<Vec<&str>>()
-        .join(" ")
-}
-
-fn is_noise_token(token: &str) -> bool {
-    let stripped = token.replace(['(', ')'], "");
-    if !stripped.chars().any(|ch| ch.is_ascii_alphabetic()) {
-        return true;
-    }
-    let lowered = stripped.to_lowercase();
-    if NOISE_WORDS.contains(lowered.as_str()) {
+/// Transient files that look like decks but aren't worth indexing: Office
+/// (`~$`) and LibreOffice (`.~lock.`) lock files, macOS AppleDouble (`._`)
+/// resource forks, and half-written downloads or saves.
+fn is_temporary_deck(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    if TEMPORARY_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
         return true;
     }
-    NOISE_PATTERNS
-        .iter()
-        .any(|pattern| pattern.is_match(&lowered))
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            TEMPORARY_EXTENSIONS
+                .iter()
+                .any(|temporary| extension.eq_ignore_ascii_case(temporary))
+        })
 }
 
-fn derive_keywords(text: &str, slides: &[SlidePreview]) -> Vec<String> {
-    let mut frequencies: HashMap<String, usize> = HashMap::new();
-    for capture in TOKEN_REGEX.find_iter(&text.to_lowercase()) {
-        let token = capture.as_str().to_string();
-        *frequencies.entry(token).or_insert(0) += 1;
-    }
-
-    let mut slide_tokens: HashSet<String> = HashSet::new();
-    for slide in slides {
-        let lowered = slide.text.to_lowercase();
-        for capture in TOKEN_REGEX.find_iter(&lowered) {
-            slide_tokens.insert(capture.as_str().to_string());
-        }
-    }
-
-    let mut items: Vec<(String, usize)> = frequencies
-        .into_iter()
-        .filter(|(token, _)| !slide_tokens.contains(token))
-        .collect();
-    items.sort_by(|a, b| b.1.cmp(&a.1));
-    items
+/// Trims, drops duplicates (keeping first-seen order) and caps at `MAX_LINKS`.
+fn dedupe_links(links: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    links
         .into_iter()
-        .take(MAX_KEYWORDS)
-        .map(|(token, _)| token)
+        .map(|link| link.trim().to_string())
+        .filter(|link| !link.is_empty() && seen.insert(link.clone()))
+        .take(MAX_LINKS)
         .collect()
 }
 
-fn build_previews_from_pages(raw_pages: &[String]) -> (Vec<SlidePreview>, String) {
-    let mut previews = Vec::new();
-    let mut combined = String::new();
-
-    for (index, raw_page) in raw_pages.iter().enumerate() {
-        let stripped_page = strip_xml_tags(raw_page);
-        let sanitized = strip_binary_artifacts(&stripped_page);
-        let filtered = filter_noise_tokens(&sanitized);
-        let cleaned = cleanup_whitespace(&filtered);
-        if !has_meaningful_text(&cleaned) {
-            continue;
-        }
-        if !combined.is_empty() {
-            combined.push(' ');
-        }
-        combined.push_str(&cleaned);
-        previews.push(SlidePreview {
-            index: index as u32 + 1,
-            text: cleaned,
-        });
-    }
-
-    (previews, combined)
-}
-
-fn has_meaningful_text(text: &str) -> bool {
-    let trimmed = text.trim();
-    if trimmed.is_empty() {
-        return false;
-    }
-    if trimmed.len() < 12 {
-        return trimmed.chars().any(|ch| ch.is_ascii_alphanumeric());
-    }
-    if is_gibberish(trimmed) {
-        return false;
-    }
-    true
-}
-
-fn is_gibberish(text: &str) -> bool {
-    let compact: String = text.chars().filter(|ch| !ch.is_whitespace()).collect();
-    if compact.len() < 40 {
-        return false;
-    }
-    let alpha = compact
-        .chars()
-        .filter(|ch| ch.is_ascii_alphabetic())
-        .count();
-    if alpha == 0 {
-        return true;
-    }
-    let alpha_ratio = alpha as f64 / compact.len() as f64;
-    if alpha_ratio < 0.35 {
-        return true;
-    }
-    let upper = compact.chars().filter(|ch| ch.is_ascii_uppercase()).count();
-    if alpha > 80 && (upper as f64 / alpha as f64) > 0.9 {
-        return true;
-    }
-    let long_tokens = text
-        .split_whitespace()
-        .filter(|token| token.chars().count() > 40)
-        .count();
-    long_tokens > 2
-}
-
-fn file_modified_ms(path: &Path) -> Option<u64> {
-    fs::metadata(path)
-        .ok()
-        .and_then(|meta| meta.modified().ok())
-        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
-        .map(|duration| duration.as_millis() as u64)
+fn trailing_number(name: &str) -> Option<u32> {
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    stem[stem.len() - digits..].parse().ok()
 }
 
-fn truncate_snippet(text: &str) -> String {
-    if text.chars().count() <= MAX_SNIPPET_LENGTH {
-        text.to_string()
-    } else {
-        text.chars().take(MAX_SNIPPET_LENGTH).collect()
-    }
+/// Orders `slide2.xml` before `slide10.xml`: by trailing number, then by name
+/// (unnumbered names last).
+fn numbered_name_order(a: &str, b: &str) -> std::cmp::Ordering {
+    let key = |name: &str| trailing_number(name).unwrap_or(u32::MAX);
+    key(a).cmp(&key(b)).then_with(|| a.cmp(b))
 }
 
 pub(crate) fn current_timestamp() -> u64 {
@@ -1383,164 +530,14 @@ fn hash_of(input: impl AsRef<str>) -> String {
     hex::encode(hasher.finalize())
 }
 
-fn calculate_file_checksum(path: &Path) -> Result<String> {
-    let mut file = File::open(path)?;
-    let mut hasher = Sha256::new();
-    let mut buffer = [0u8; 8192]; // 8KB buffer for streaming
-    
-    loop {
-        let bytes_read = file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        hasher.update(&buffer[..bytes_read]);
-    }
-    
-    Ok(hex::encode(hasher.finalize()))
-}
+/// Signature of an OLE compound file (legacy Office documents, encrypted OOXML).
+const OLE_MAGIC: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
 
-fn resolve_command_status() -> CommandStatus {
-    let pdftoppm = resolve_command("pdftoppm");
-    let tesseract = resolve_command("tesseract");
-    let pdftotext = resolve_command("pdftotext");
-
-    let mut missing: Vec<&'static str> = Vec::new();
-    if pdftoppm.is_none() {
-        missing.push("pdftoppm");
-    }
-    if tesseract.is_none() {
-        missing.push("tesseract");
-    }
-    if pdftotext.is_none() {
-        missing.push("pdftotext");
-    }
-
-    CommandStatus {
-        paths: CommandPaths {
-            pdftoppm,
-            tesseract,
-            pdftotext,
-        },
-        missing,
-    }
-}
-
-fn resolve_command(command: &str) -> Option<PathBuf> {
-    let mut search_dirs: Vec<PathBuf> = Vec::new();
-    if let Some(path_var) = env::var_os("PATH") {
-        search_dirs.extend(env::split_paths(&path_var));
-    }
-
-    search_dirs.extend(default_command_dirs());
-
-    #[allow(unused_mut)]
-    let mut candidate_names = vec![command.to_string()];
-    #[cfg(target_os = "windows")]
-    {
-        if !command.ends_with(".exe") {
-            candidate_names.push(format!("{command}.exe"));
-        }
-    }
-
-    for dir in search_dirs {
-        for name in &candidate_names {
-            let candidate = dir.join(name);
-            if is_executable_path(&candidate) {
-                return Some(candidate);
-            }
-        }
-    }
-
-    None
-}
-
-fn default_command_dirs() -> Vec<PathBuf> {
-    let mut dirs: Vec<PathBuf> = Vec::new();
-
-    #[cfg(target_os = "macos")]
-    {
-        dirs.extend(
-            [
-                "/opt/homebrew/bin",
-                "/usr/local/bin",
-                "/usr/bin",
-                "/bin",
-                "/opt/local/bin",
-            ]
-            .into_iter()
-            .map(PathBuf::from),
-        );
-    }
-
-    #[cfg(target_os = "linux")]
-    {
-        dirs.extend(
-            ["/usr/local/bin", "/usr/bin", "/bin", "/snap/bin"]
-                .into_iter()
-                .map(PathBuf::from),
-        );
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        dirs.extend(
-            [
-                r"C:\\Program Files\\Tesseract-OCR",
-                r"C:\\Program Files (x86)\\Tesseract-OCR",
-                r"C:\\Program Files\\poppler\\bin",
-                r"C:\\Program Files (x86)\\poppler\\bin",
-            ]
-            .into_iter()
-            .map(PathBuf::from),
-        );
-    }
-
-    dirs
-}
-
-fn is_executable_path(path: &Path) -> bool {
-    match fs::metadata(path) {
-        Ok(metadata) => {
-            if !metadata.is_file() {
-                return false;
-            }
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                return metadata.permissions().mode() & 0o111 != 0;
-            }
-            #[cfg(not(unix))]
-            {
-                true
-            }
-        }
-        Err(_) => false,
-    }
-}
-
-#[allow(dead_code)]
-pub fn is_ocr_available() -> bool {
-    COMMAND_STATUS.paths.pdftoppm.is_some() && COMMAND_STATUS.paths.tesseract.is_some()
-}
-
-pub fn ocr_status_message() -> Option<String> {
-    if COMMAND_STATUS.missing.is_empty() {
-        None
-    } else {
-        Some(format!(
-            "PDF extraction tools missing: {}. Install them to enable full PDF scanning (e.g. `brew install poppler tesseract`).",
-            COMMAND_STATUS.missing.join(", ")
-        ))
-    }
-}
-
-fn inflate_data(data: &[u8]) -> Result<Vec<u8>> {
-    let mut decoder = ZlibDecoder::new(Cursor::new(data));
-    let mut output = Vec::new();
-    decoder
-        .read_to_end(&mut output)
-        .map_err(|error| AppError::Message(error.to_string()))?;
-    Ok(output)
+/// True when an OLE compound file has a directory entry called `name`.
+/// Entry names are stored as UTF-16LE.
+fn has_ole_stream(buffer: &[u8], name: &str) -> bool {
+    let encoded: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    find_subsequence(buffer, &encoded).is_some()
 }
 
 fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
@@ -1549,61 +546,144 @@ fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
         .position(|window| window == needle)
 }
 
-fn wildcard_to_regex(pattern: &str) -> Option<Regex> {
-    let mut converted = String::new();
-    for ch in pattern.chars() {
-        match ch {
-            '*' => converted.push_str(".*"),
-            '?' => converted.push('.'),
-            _ => converted.push_str(&escape(&ch.to_string())),
-        }
-    }
-    if converted.is_empty() {
-        return None;
-    }
-    let final_pattern = format!(".*{}.*", converted);
-    RegexBuilder::new(&final_pattern)
-        .case_insensitive(true)
-        .dot_matches_new_line(true)
-        .build()
-        .ok()
-}
-
-fn build_search_corpus(item: &SlideIndexItem) -> String {
-    let mut parts = Vec::new();
-    parts.push(item.name.to_lowercase());
-    parts.push(item.path.to_lowercase());
-    if !item.snippet.is_empty() {
-        parts.push(item.snippet.to_lowercase());
-    }
-    if !item.slides.is_empty() {
-        parts.extend(item.slides.iter().map(|slide| slide.text.to_lowercase()));
-    }
-    if !item.keywords.is_empty() {
-        parts.push(item.keywords.join(" ").to_lowercase());
-    }
-    parts.join(" ")
-}
-
-pub fn matches_query(item: &SlideIndexItem, pattern: &SearchPattern) -> bool {
-    if pattern.is_empty {
-        return true;
-    }
-    let corpus = build_search_corpus(item);
-    for phrase in &pattern.phrases {
-        if !corpus.contains(phrase) {
-            return false;
-        }
-    }
-    for term in &pattern.terms {
-        if !corpus.contains(term) {
-            return false;
-        }
-    }
-    for wildcard in &pattern.wildcards {
-        if !wildcard.is_match(&corpus) {
-            return false;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+    use tempfile::tempdir;
+
+    /// A minimal indexed item for `path`; only the fields scans compare are set.
+    pub(super) fn item(path: &str, updated_at: u64, checksum: Option<&str>) -> SlideIndexItem {
+        serde_json::from_value(serde_json::json!({
+            "id": path,
+            "path": path,
+            "name": Path::new(path).file_name().unwrap().to_string_lossy(),
+            "kind": "pptx",
+            "slideCount": 1,
+            "snippet": "",
+            "updatedAt": updated_at,
+            "checksum": checksum,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn oversized_deck_keeps_its_previous_entry() {
+        let dir = tempdir().unwrap();
+        let deck = dir.path().join("deck.pptx");
+        fs::write(&deck, vec![0u8; 2_000]).unwrap();
+        let previous = item(&deck.to_string_lossy(), 1, Some("old"));
+        let settings = ScanSettings { max_file_bytes: 1_000, ..ScanSettings::default() };
+
+        let outcome = scan_directories(
+            &[dir.path().to_string_lossy().into_owned()],
+            &[previous],
+            &settings,
+            None,
+            &AtomicBool::new(false),
+            &mut |_, _, _| {},
+            &mut |_| {},
+        )
+        .unwrap();
+
+        assert_eq!(outcome.items.len(), 1);
+        assert_eq!(outcome.items[0].checksum.as_deref(), Some("old"));
+        assert!(outcome.warnings[0].contains("keeping the previous index entry"));
+    }
+
+    #[test]
+    fn password_protected_deck_keeps_its_previous_entry() {
+        let dir = tempdir().unwrap();
+        let deck = dir.path().join("deck.pptx");
+        // An OLE container holding an encrypted package, as PowerPoint saves it.
+        let mut encrypted = OLE_MAGIC.to_vec();
+        encrypted.extend("EncryptionInfo".encode_utf16().flat_map(u16::to_le_bytes));
+        fs::write(&deck, encrypted).unwrap();
+        let previous = item(&deck.to_string_lossy(), 1, Some("old"));
+
+        let outcome = scan_directories(
+            &[dir.path().to_string_lossy().into_owned()],
+            &[previous],
+            &ScanSettings::default(),
+            None,
+            &AtomicBool::new(false),
+            &mut |_, _, _| {},
+            &mut |_| {},
+        )
+        .unwrap();
+
+        assert_eq!(outcome.items.len(), 1);
+        assert_eq!(outcome.items[0].checksum.as_deref(), Some("old"));
+        assert_eq!(outcome.errors.len(), 1);
+        assert_eq!(outcome.errors[0].path, deck.to_string_lossy());
+        assert!(outcome.errors[0].message.contains("password-protected"));
+    }
+
+    #[test]
+    fn truncated_pptx_is_a_scan_error() {
+        let sample = fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.pptx")).unwrap();
+        let dir = tempdir().unwrap();
+        let deck = dir.path().join("truncated.pptx");
+        // Cut off the central directory at the end of the archive.
+        fs::write(&deck, &sample[..sample.len() / 2]).unwrap();
+
+        assert!(matches!(index_path(&deck, &ScanSettings::default()), Err(AppError::CorruptArchive)));
+
+        let outcome = scan_directories(
+            &[dir.path().to_string_lossy().into_owned()],
+            &[],
+            &ScanSettings::default(),
+            None,
+            &AtomicBool::new(false),
+            &mut |_, _, _| {},
+            &mut |_| {},
+        )
+        .unwrap();
+        assert!(outcome.items.is_empty());
+        assert_eq!(outcome.errors.len(), 1);
+        assert_eq!(outcome.errors[0].path, deck.to_string_lossy());
+    }
+
+    #[test]
+    fn decks_index_from_bytes_under_their_archive_path() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let settings = ScanSettings::default();
+        let sample = fs::read(fixtures.join("sample.pptx")).unwrap();
+        let from_file = index_path(&fixtures.join("sample.pptx"), &settings).unwrap();
+
+        let archived = PathBuf::from("/courses/week1.zip!slides/sample.pptx");
+        let item = index_pptx_bytes(&sample, &archived, None, None, &settings).unwrap();
+        assert_eq!(item.path, archived.to_string_lossy());
+        assert_eq!(item.id, hash_of(archived.to_string_lossy()));
+        assert_eq!(item.file_size, Some(sample.len() as u64));
+        assert_eq!(item.slides.len(), from_file.slides.len());
+        assert_eq!(item.slides[0].text, from_file.slides[0].text);
+
+        let sample = fs::read(fixtures.join("sample.ppt")).unwrap();
+        let archived = PathBuf::from("/courses/week1.zip!slides/sample.ppt");
+        let item = index_ppt_bytes(&sample, &archived, None, None, &settings).unwrap();
+        assert_eq!(item.path, archived.to_string_lossy());
+        assert!(!item.slides.is_empty());
+    }
+
+    #[test]
+    fn office_and_download_leftovers_are_temporary() {
+        for name in [
+            "~$deck.pptx",
+            ".~lock.deck.pptx#",
+            "._deck.pdf",
+            "deck.pptx.tmp",
+            "deck.pdf.crdownload",
+            "deck.pdf.PART",
+        ] {
+            assert!(is_temporary_deck(&Path::new("/decks").join(name)), "{name}");
+        }
+    }
+
+    #[test]
+    fn decks_with_a_tilde_in_the_name_are_kept() {
+        for name in ["week~3 deck.pptx", "deck~$.pptx", "notes_~draft.pdf", "deck.pptx"] {
+            assert!(!is_temporary_deck(&Path::new("/decks").join(name)), "{name}");
         }
     }
-    true
 }
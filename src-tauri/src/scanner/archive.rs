@@ -0,0 +1,205 @@
+//! Decks inside zip archives: finding them during a scan, reading them
+//! for indexing, and unpacking them for a viewer. An archived deck's item path
+//! is the archive's path and its name inside, joined by `!`.
+
+use std::{env, fs::{self, File}, io::{self, Read, Seek}, path::{Path, PathBuf}};
+
+use sha2::{Digest, Sha256};
+use zip::{result::ZipError, ZipArchive};
+
+use crate::{error::{AppError, Result}, models::{ScanError, ScanSettings}};
+
+use super::{
+    deck_kind,
+    deck_walker,
+    files::{
+        calculate_file_checksum, file_modified_ms, long_path, oversized_reason, retry_io,
+        size_limit_reason,
+    },
+    hash_of,
+};
+
+/// Archives `scan_archives` looks inside, and what separates an archive's path
+/// from a deck's name inside it in item paths.
+pub(super) const ARCHIVE_EXTENSION: &str = "zip";
+const ARCHIVE_PATH_SEPARATOR: char = '!';
+
+/// A deck a scan found: a file of its own, or one inside a zip archive.
+pub(super) enum DeckSource {
+    File(PathBuf),
+    Archived { archive: PathBuf, inner: String },
+}
+
+impl DeckSource {
+    /// Path the deck is indexed under; `archive.zip!inner/deck.pptx` for archived decks.
+    pub(super) fn item_path(&self) -> PathBuf {
+        match self {
+            DeckSource::File(path) => path.clone(),
+            DeckSource::Archived { archive, inner } => PathBuf::from(format!(
+                "{}{ARCHIVE_PATH_SEPARATOR}{inner}",
+                archive.to_string_lossy()
+            )),
+        }
+    }
+
+    /// An archived deck changes whenever its archive does.
+    pub(super) fn modified_ms(&self) -> Option<u64> {
+        match self {
+            DeckSource::File(path) => file_modified_ms(path),
+            DeckSource::Archived { archive, .. } => file_modified_ms(archive),
+        }
+    }
+
+    pub(super) fn oversized_reason(&self, max_bytes: u64) -> Option<String> {
+        match self {
+            DeckSource::File(path) => oversized_reason(path, max_bytes),
+            DeckSource::Archived { archive, inner } => {
+                let size = open_archive(archive).ok()?.by_name(inner).ok()?.size();
+                size_limit_reason(size, max_bytes)
+            }
+        }
+    }
+
+    pub(super) fn checksum(&self) -> Result<String> {
+        match self {
+            DeckSource::File(path) => calculate_file_checksum(path),
+            DeckSource::Archived { archive, inner } => {
+                let mut archive = open_archive(archive)?;
+                let mut entry = archive.by_name(inner)?;
+                let mut hasher = Sha256::new();
+                io::copy(&mut entry, &mut hasher)?;
+                Ok(hex::encode(hasher.finalize()))
+            }
+        }
+    }
+
+    /// The contents of an archived deck, for the `index_*_bytes` functions;
+    /// `None` for plain files, which the `index_*` functions read themselves.
+    pub(super) fn archived_bytes(&self) -> Result<Option<Vec<u8>>> {
+        let DeckSource::Archived { archive, inner } = self else {
+            return Ok(None);
+        };
+        let mut archive = open_archive(archive)?;
+        let mut entry = archive.by_name(inner)?;
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+}
+
+/// Decks under `directory`: the files `deck_walker` finds and, with
+/// `scan_archives`, the decks inside every zip archive. An unreadable archive
+/// is reported in `errors` and skipped.
+pub(super) fn deck_sources(
+    directory: &Path,
+    settings: &ScanSettings,
+    errors: &mut Vec<ScanError>,
+) -> Result<Vec<DeckSource>> {
+    let mut sources = Vec::new();
+    for entry in deck_walker(directory, settings)? {
+        let path = entry.path();
+        if !(settings.scan_archives && is_zip_archive(path)) {
+            sources.push(DeckSource::File(path.to_path_buf()));
+            continue;
+        }
+        match archived_decks(path, settings) {
+            Ok(decks) => sources.extend(decks.into_iter().map(|inner| DeckSource::Archived {
+                archive: path.to_path_buf(),
+                inner,
+            })),
+            Err(error) => {
+                println!("⚠ Skipping unreadable archive {}: {}", path.display(), error);
+                errors.push(ScanError::new(
+                    path.to_string_lossy(),
+                    format!("Failed to read archive: {error}"),
+                ));
+            }
+        }
+    }
+    // Walk order depends on the filesystem; a fixed order makes scans reproducible.
+    sources.sort_by_key(|source| source.item_path());
+    Ok(sources)
+}
+
+fn is_zip_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(ARCHIVE_EXTENSION))
+}
+
+/// Names of the decks inside a zip archive, skipping macOS metadata folders and,
+/// with `skip_hidden`, dot-named entries.
+fn archived_decks(archive: &Path, settings: &ScanSettings) -> Result<Vec<String>> {
+    let archive = open_archive(archive)?;
+    let mut decks: Vec<String> = archive
+        .file_names()
+        .filter(|name| !name.ends_with('/') && deck_kind(Path::new(name)).is_some())
+        .filter(|name| {
+            !name.split('/').any(|part| {
+                part == "__MACOSX" || (settings.skip_hidden && part.starts_with('.'))
+            })
+        })
+        .map(str::to_string)
+        .collect();
+    decks.sort();
+    Ok(decks)
+}
+
+fn open_archive(archive: &Path) -> Result<ZipArchive<File>> {
+    let file = retry_io(|| File::open(long_path(archive)))?;
+    zip_archive(file)
+}
+
+/// Opens a ZIP container, telling a damaged one (`CorruptArchive`) from one
+/// that couldn't be read at all.
+pub(super) fn zip_archive<R: Read + Seek>(reader: R) -> Result<ZipArchive<R>> {
+    ZipArchive::new(reader).map_err(|error| match error {
+        ZipError::Io(error) if error.kind() != io::ErrorKind::UnexpectedEof => AppError::Io(error),
+        _ => AppError::CorruptArchive,
+    })
+}
+
+fn extract_archived_deck(archive: &Path, inner: &str, target: &Path) -> Result<()> {
+    let mut archive = open_archive(archive)?;
+    let mut entry = archive.by_name(inner)?;
+    let mut file = File::create(target)?;
+    io::copy(&mut entry, &mut file)?;
+    Ok(())
+}
+
+/// Splits an item path like `course.zip!week1/intro.pptx` into the archive's
+/// path and the deck's name inside it; `None` for ordinary files.
+pub fn split_archive_path(path: &str) -> Option<(&str, &str)> {
+    path.match_indices(ARCHIVE_PATH_SEPARATOR)
+        .map(|(index, _)| index)
+        .find(|&index| {
+            path.get(index.saturating_sub(ARCHIVE_EXTENSION.len() + 1)..index)
+                .is_some_and(|suffix| suffix.eq_ignore_ascii_case(&format!(".{ARCHIVE_EXTENSION}")))
+        })
+        .map(|index| (&path[..index], &path[index + 1..]))
+}
+
+/// Whether the deck behind an item path is still there (for archived decks:
+/// the archive).
+pub fn deck_exists(path: &str) -> bool {
+    match split_archive_path(path) {
+        Some((archive, _)) => Path::new(archive).exists(),
+        None => Path::new(path).exists(),
+    }
+}
+
+/// A file an application can open for an item path. Archived decks are
+/// unpacked to the temp folder first; the copy is left there for the viewer.
+pub fn launchable_path(path: &str) -> Result<PathBuf> {
+    let Some((archive, inner)) = split_archive_path(path) else {
+        return Ok(PathBuf::from(path));
+    };
+    let Some(file_name) = Path::new(inner).file_name() else {
+        return Err(AppError::Message(format!("No file name in {inner}")));
+    };
+    let target_dir = env::temp_dir().join("slides-indexer-archives").join(hash_of(path));
+    fs::create_dir_all(&target_dir)?;
+    let target = target_dir.join(file_name);
+    extract_archived_deck(Path::new(archive), inner, &target)?;
+    Ok(target)
+}
@@ -0,0 +1,79 @@
+//! Text of documents embedded in a PPTX (PDFs and Office files).
+
+use std::io::{Cursor, Read};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{
+    archive::zip_archive,
+    pdf::extract_pdf_contents,
+    pptx::decode_xml,
+    text::{
+        cleanup_whitespace, filter_noise_tokens, has_meaningful_text, strip_binary_artifacts,
+        strip_xml_tags,
+    },
+    OLE_MAGIC,
+};
+
+/// Text of a document embedded in a PPTX: a PDF (bare, or wrapped in an OLE
+/// object as PowerPoint stores inserted PDFs) through the built-in PDF parser,
+/// or an Office Open XML file (DOCX, XLSX, PPTX) through its text runs. Other
+/// objects, and embeddings without any readable text, give `None`.
+pub(super) fn extract_embedded_text(bytes: &[u8]) -> Option<String> {
+    let text = if bytes.starts_with(b"%PDF") {
+        embedded_pdf_text(bytes)
+    } else if bytes.starts_with(b"PK\x03\x04") {
+        embedded_ooxml_text(bytes)
+    } else if bytes.starts_with(&OLE_MAGIC) {
+        let start = bytes.windows(5).position(|window| window == b"%PDF-")?;
+        embedded_pdf_text(&bytes[start..])
+    } else {
+        return None;
+    };
+    has_meaningful_text(&text).then_some(text)
+}
+
+fn embedded_pdf_text(bytes: &[u8]) -> String {
+    let contents = extract_pdf_contents(bytes);
+    let stripped = strip_xml_tags(&contents.text);
+    let sanitized = strip_binary_artifacts(&stripped);
+    cleanup_whitespace(&filter_noise_tokens(&sanitized))
+}
+
+fn embedded_ooxml_text(bytes: &[u8]) -> String {
+    static OOXML_TEXT_REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"<(?:[aw]:)?t(?:\s[^>]*)?>([^<]*)</(?:[aw]:)?t>").expect("valid regex")
+    });
+    let Ok(mut archive) = zip_archive(Cursor::new(bytes)) else {
+        return String::new();
+    };
+    let mut names: Vec<String> = archive
+        .file_names()
+        .filter(|name| {
+            *name == "word/document.xml"
+                || *name == "xl/sharedStrings.xml"
+                || (name.starts_with("ppt/slides/slide") && name.ends_with(".xml"))
+        })
+        .map(str::to_string)
+        .collect();
+    // Shorter first puts slide2.xml before slide10.xml.
+    names.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    let mut segments = Vec::new();
+    for name in names {
+        let Ok(mut part) = archive.by_name(&name) else {
+            continue;
+        };
+        let mut xml = String::new();
+        if part.read_to_string(&mut xml).is_err() {
+            continue;
+        }
+        segments.extend(
+            OOXML_TEXT_REGEX
+                .captures_iter(&xml)
+                .map(|caps| decode_xml(&caps[1]))
+                .filter(|segment| !segment.trim().is_empty()),
+        );
+    }
+    cleanup_whitespace(&segments.join(" "))
+}
@@ -0,0 +1,221 @@
+//! Reading deck files: long Windows paths, retrying transient I/O errors,
+//! and file sizes, modification times and checksums.
+
+use std::{
+    borrow::Cow,
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+    thread,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::Result;
+
+/// Pauses before each retry of a failed file read (see `retry_io`).
+const IO_RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_millis(100),
+    Duration::from_millis(400),
+    Duration::from_millis(1500),
+];
+
+pub(super) fn file_modified_ms(path: &Path) -> Option<u64> {
+    retry_io(|| fs::metadata(long_path(path)))
+        .ok()
+        .and_then(|meta| meta.modified().ok())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+}
+
+pub(super) fn file_size_bytes(path: &Path) -> Option<u64> {
+    retry_io(|| fs::metadata(long_path(path))).ok().map(|meta| meta.len())
+}
+
+/// `path` in a form Windows opens at any length: paths past MAX_PATH (260
+/// characters), common in deep OneDrive/SharePoint sync trees, need the `\\?\`
+/// prefix. Item paths and cache keys keep the plain form.
+#[cfg(windows)]
+fn long_path(path: &Path) -> Cow<'_, Path> {
+    const MAX_PATH: usize = 260;
+    // Prefixed paths skip all normalisation, so only plain absolute ones that
+    // can be rewritten exactly are touched.
+    let Some(text) = path.to_str() else {
+        return Cow::Borrowed(path);
+    };
+    if text.encode_utf16().count() < MAX_PATH || !path.is_absolute() || text.starts_with(r"\\?\") {
+        return Cow::Borrowed(path);
+    }
+    let text = text.replace('/', r"\");
+    let prefixed = match text.strip_prefix(r"\\") {
+        Some(share) => format!(r"\\?\UNC\{share}"),
+        None => format!(r"\\?\{text}"),
+    };
+    Cow::Owned(PathBuf::from(prefixed))
+}
+
+#[cfg(not(windows))]
+pub(super) fn long_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+/// Key identifying the file at `path` when matching scan results against the
+/// index. Windows and macOS filesystems ignore case by default, and a path seen
+/// with different casing (renamed folder, drive letter typed differently) must
+/// still hit the cache. The item keeps its path as found for display/opening.
+#[cfg(any(windows, target_os = "macos"))]
+pub fn path_key(path: &str) -> Cow<'_, str> {
+    Cow::Owned(path.to_lowercase())
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn path_key(path: &str) -> Cow<'_, str> {
+    Cow::Borrowed(path)
+}
+
+/// Why `path` is too big to index under `max_bytes` (0 = no limit), if it is.
+pub(super) fn oversized_reason(path: &Path, max_bytes: u64) -> Option<String> {
+    size_limit_reason(file_size_bytes(path)?, max_bytes)
+}
+
+pub(super) fn size_limit_reason(size: u64, max_bytes: u64) -> Option<String> {
+    (max_bytes > 0 && size > max_bytes).then(|| {
+        format!(
+            "{:.1} MB exceeds the {:.1} MB size limit",
+            size as f64 / 1_000_000.0,
+            max_bytes as f64 / 1_000_000.0
+        )
+    })
+}
+
+/// Runs `operation` again after a short pause when it fails with an error that
+/// may clear up by itself (e.g. a network share dropping out for a moment).
+/// Any other error (missing file, no permission, bad data) is returned
+/// straight away.
+pub(super) fn retry_io<T>(mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut delays = IO_RETRY_DELAYS.iter();
+    loop {
+        match operation() {
+            Err(error) if is_transient_io_error(&error) => match delays.next() {
+                Some(delay) => {
+                    println!("⟳ IO error, retrying in {:?}: {}", delay, error);
+                    thread::sleep(*delay);
+                }
+                None => return Err(error),
+            },
+            result => return result,
+        }
+    }
+}
+
+fn is_transient_io_error(error: &io::Error) -> bool {
+    // Windows reports a deck that PowerPoint has open as a sharing or lock
+    // violation, which std does not map to `ResourceBusy`.
+    #[cfg(windows)]
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    #[cfg(windows)]
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    #[cfg(windows)]
+    if matches!(error.raw_os_error(), Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)) {
+        return true;
+    }
+    matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ResourceBusy
+    )
+}
+
+pub(super) fn calculate_file_checksum(path: &Path) -> Result<String> {
+    // A retry re-reads the whole file; a hash of a partial read is useless.
+    let io_path = long_path(path);
+    let checksum = retry_io(|| {
+        let mut file = File::open(&io_path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = [0u8; 8192]; // 8KB buffer for streaming
+
+        loop {
+            let bytes_read = file.read(&mut buffer)?;
+            if bytes_read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..bytes_read]);
+        }
+
+        Ok(hex::encode(hasher.finalize()))
+    })?;
+    Ok(checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path of nested folders under `root`, well over Windows' 260-character limit.
+    #[cfg(windows)]
+    fn deep_path(root: &str) -> String {
+        format!("{root}{}deck.pptx", r"lecture-notes-folder\".repeat(15))
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn long_drive_path_gets_verbatim_prefix() {
+        let path = deep_path(r"C:\Slides\");
+        assert!(path.len() > 260);
+        assert_eq!(long_path(Path::new(&path)), Path::new(&format!(r"\\?\{path}")));
+        // Forward slashes aren't separators once prefixed, so they're rewritten.
+        let forward = path.replace('\\', "/");
+        assert_eq!(long_path(Path::new(&forward)), Path::new(&format!(r"\\?\{path}")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn long_unc_path_gets_unc_prefix() {
+        let path = deep_path(r"\\fileserver\lectures\");
+        let expected = format!(r"\\?\UNC\{}", &path[2..]);
+        assert_eq!(long_path(Path::new(&path)), Path::new(&expected));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn prefixed_and_short_paths_are_untouched() {
+        let prefixed = format!(r"\\?\{}", deep_path(r"C:\Slides\"));
+        assert!(matches!(long_path(Path::new(&prefixed)), Cow::Borrowed(_)));
+        assert!(matches!(long_path(Path::new(r"C:\Slides\deck.pptx")), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn permanent_io_errors_are_not_retried() {
+        for kind in [io::ErrorKind::NotFound, io::ErrorKind::PermissionDenied, io::ErrorKind::InvalidData, io::ErrorKind::Other] {
+            let mut attempts = 0;
+            let result: io::Result<()> = retry_io(|| {
+                attempts += 1;
+                Err(io::Error::from(kind))
+            });
+            assert_eq!(result.unwrap_err().kind(), kind);
+            assert_eq!(attempts, 1, "{kind:?} was retried");
+        }
+    }
+
+    #[test]
+    fn transient_io_errors_are_retried() {
+        let mut attempts = 0;
+        let result = retry_io(|| {
+            attempts += 1;
+            if attempts < 2 {
+                Err(io::Error::from(io::ErrorKind::Interrupted))
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 2);
+        for kind in [io::ErrorKind::TimedOut, io::ErrorKind::WouldBlock, io::ErrorKind::ConnectionReset, io::ErrorKind::ConnectionAborted, io::ErrorKind::ResourceBusy] {
+            assert!(is_transient_io_error(&io::Error::from(kind)), "{kind:?}");
+        }
+    }
+}
@@ -0,0 +1,553 @@
+//! Indexing PDFs with the built-in parser, falling back to pdftotext and
+//! OCR when the text it finds isn't usable.
+
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tempfile::{tempdir, TempDir};
+
+use crate::{
+    error::Result,
+    models::{OcrMode, ScanSettings, SlideIndexItem, SlideKind},
+    pdf_fonts::{self, PdfFonts},
+    pdf_streams::{declared_stream_bounds, decode_stream, stream_dictionary_start},
+    text_cache,
+};
+
+use super::{
+    current_timestamp,
+    dedupe_links,
+    files::{long_path, retry_io},
+    find_subsequence,
+    hash_of,
+    pdf_text::{decode_pdf_hex_string, decode_pdf_string, extract_text_from_pdf_stream},
+    pdf_tools::{extract_pdf_with_ocr, extract_pdf_with_pdftotext, tesseract_language},
+    text::{
+        build_previews_from_pages, cleanup_whitespace, count_words, derive_keywords,
+        detect_language, filter_noise_tokens, first_slide_title, has_meaningful_text,
+        meaningful_title, reading_minutes, strip_binary_artifacts, strip_xml_tags, text_coverage,
+        truncate_snippet,
+    },
+    tools::COMMAND_STATUS,
+};
+
+static PAGE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"/Type\s*/Page\b").expect("valid regex"));
+static PAGES_NODE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/Type\s*/Pages\b").expect("valid regex"));
+static PDF_URI_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"/URI\s*(?:\(((?:\\.|[^\\)])*)\)|<([0-9A-Fa-f\s]*)>)").expect("valid regex")
+});
+static PDF_ENCRYPT_REGEX: Lazy<regex::bytes::Regex> = Lazy::new(|| {
+    regex::bytes::Regex::new(r"/Encrypt\s*(?:\d+\s+\d+\s+R|<<)").expect("valid regex")
+});
+static PAGE_COUNT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/Count\s+(\d+)").expect("valid regex"));
+
+pub(super) fn index_pdf(
+    path: &Path, 
+    modified_at: Option<u64>, 
+    checksum: Option<String>,
+    settings: &ScanSettings,
+    progress: &mut dyn FnMut(&str, &str, Option<&str>),
+    initial_scan_details: Option<String>,
+    previous_language: Option<&str>, // from the last index of this file, picks the OCR language
+) -> Result<SlideIndexItem> {
+    let buffer = retry_io(|| fs::read(long_path(path)))?;
+    index_pdf_bytes(
+        &buffer,
+        path,
+        Some(path),
+        modified_at,
+        checksum,
+        settings,
+        progress,
+        initial_scan_details,
+        previous_language,
+    )
+}
+
+/// Indexes the PDF in `buffer` as the deck at `path`. pdftotext and OCR read
+/// `on_disk`, the same PDF as a file; without one (a deck inside a zip
+/// archive) the buffer is written to a temporary file if they are needed.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn index_pdf_bytes(
+    buffer: &[u8],
+    path: &Path,
+    on_disk: Option<&Path>,
+    modified_at: Option<u64>,
+    checksum: Option<String>,
+    settings: &ScanSettings,
+    progress: &mut dyn FnMut(&str, &str, Option<&str>),
+    initial_scan_details: Option<String>,
+    previous_language: Option<&str>,
+) -> Result<SlideIndexItem> {
+    let mut staged: Option<StagedDeck> = None;
+    let mut tool_input = || -> Result<PathBuf> {
+        if let Some(on_disk) = on_disk {
+            return Ok(on_disk.to_path_buf());
+        }
+        if staged.is_none() {
+            staged = Some(stage_bytes(buffer, path)?);
+        }
+        Ok(staged.as_ref().expect("staged above").path.clone())
+    };
+
+    let mut contents = extract_pdf_contents(buffer);
+    let stripped = strip_xml_tags(&contents.text);
+    let sanitized = strip_binary_artifacts(&stripped);
+    let filtered = filter_noise_tokens(&sanitized);
+    let cleaned = cleanup_whitespace(&filtered);
+
+    let mut page_count = contents.page_count;
+    let (mut previews, combined_from_pages, mut text_pages) = build_previews_from_pages(&contents.pages);
+    let mut snippet_source = if has_meaningful_text(&cleaned) {
+        cleaned.clone()
+    } else {
+        String::new()
+    };
+    let mut keyword_source = if has_meaningful_text(&combined_from_pages) {
+        combined_from_pages.clone()
+    } else {
+        String::new()
+    };
+
+    if !has_meaningful_text(&keyword_source) && has_meaningful_text(&snippet_source) {
+        keyword_source = snippet_source.clone();
+    }
+    if !has_meaningful_text(&snippet_source) && has_meaningful_text(&keyword_source) {
+        snippet_source = keyword_source.clone();
+    }
+
+    // Image-only scans have nothing for pdftotext to find; go straight to OCR.
+    let image_only = contents.is_image_only();
+    if image_only {
+        println!("🖼️  Image-only PDF (no fonts): {}", path.file_name().unwrap_or_default().to_string_lossy());
+        // Anything the native parser found came from image bytes, not text.
+        previews.clear();
+        text_pages = 0;
+        snippet_source.clear();
+        keyword_source.clear();
+    }
+
+    // Strings and streams of an encrypted PDF are ciphertext to our parser.
+    // poppler opens files whose user password is empty (owner-restricted ones
+    // that anyone may read), so the tools below decide whether it's readable.
+    let encrypted = pdf_is_encrypted(buffer);
+    let mut decrypted = false;
+    let mut password_rejected = false;
+    if encrypted {
+        println!("🔒 Encrypted PDF: {}", path.file_name().unwrap_or_default().to_string_lossy());
+        previews.clear();
+        text_pages = 0;
+        snippet_source.clear();
+        keyword_source.clear();
+        contents.title = None;
+    }
+
+    if !image_only
+        && (settings.prefer_pdftotext
+            || !has_meaningful_text(&snippet_source)
+            || previews.is_empty())
+        && COMMAND_STATUS.paths.pdftotext.is_some()
+    {
+        match tool_input().and_then(|file| extract_pdf_with_pdftotext(&file)) {
+            Ok(pdftotext_pages) => {
+                decrypted = true;
+                if page_count.is_none() && !pdftotext_pages.is_empty() {
+                    page_count = Some(pdftotext_pages.len());
+                }
+                let (text_previews, combined, pages_with_text) = build_previews_from_pages(&pdftotext_pages);
+                if !text_previews.is_empty() {
+                    previews = text_previews;
+                    text_pages = pages_with_text;
+                }
+                if settings.prefer_pdftotext && has_meaningful_text(&combined) {
+                    keyword_source = combined.clone();
+                    snippet_source = combined;
+                } else if has_meaningful_text(&combined) {
+                    if !has_meaningful_text(&keyword_source) {
+                        keyword_source = combined.clone();
+                    }
+                    if !has_meaningful_text(&snippet_source) {
+                        snippet_source = combined.clone();
+                    }
+                }
+            }
+            Err(error) => {
+                password_rejected = encrypted;
+                println!(
+                    "⚠ pdftotext failed for {}: {}",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    error
+                );
+            }
+        }
+    }
+
+    // pdftoppm would be refused the same password, so don't bother with OCR.
+    let needs_ocr = !password_rejected
+        && match settings.ocr_mode {
+            OcrMode::Off => false,
+            OcrMode::Auto => !has_meaningful_text(&snippet_source) || previews.is_empty(),
+            OcrMode::Force => true,
+        };
+
+    if needs_ocr {
+        println!("⟳ Running OCR on PDF: {}", path.file_name().unwrap_or_default().to_string_lossy());
+        let path_string = path.to_string_lossy().to_string();
+        
+        // Combine initial scan details with OCR status
+        let mut combined_msg = initial_scan_details.unwrap_or_default();
+        if !combined_msg.is_empty() {
+            combined_msg.push_str("\n\n━━━━━━━━━━━━━━━━━━━━━━\n\n");
+        }
+        if image_only {
+            combined_msg.push_str("🖼️ Image-only PDF detected (no fonts)\nSkipped text extraction\n\n");
+        }
+        combined_msg.push_str("🔍 OCR Processing:\nExtracting text from images...\nThis may take a few moments");
+        
+        progress(&path_string, "ocr", Some(&combined_msg));
+        
+        // Prefer the language of any text found so far, then what the last scan detected.
+        let language_hint = detect_language(&snippet_source);
+        let ocr_language = tesseract_language(language_hint.as_deref().or(previous_language));
+        let mut on_page_done = |done: usize, total: usize| {
+            let message = format!("{combined_msg}\n\nOCR page {done}/{total}");
+            progress(&path_string, "ocr", Some(&message));
+        };
+        match tool_input().and_then(|file| extract_pdf_with_ocr(&file, checksum.as_deref(), ocr_language, &mut on_page_done)) {
+            Ok(ocr_pages) => {
+                decrypted |= ocr_pages.iter().any(|page| !page.trim().is_empty());
+                let (ocr_previews, combined, pages_with_text) = build_previews_from_pages(&ocr_pages);
+                if !ocr_previews.is_empty() {
+                    previews = ocr_previews;
+                    text_pages = pages_with_text;
+                }
+                if settings.ocr_mode == OcrMode::Force && has_meaningful_text(&combined) {
+                    keyword_source = combined.clone();
+                    snippet_source = combined;
+                } else if has_meaningful_text(&combined) {
+                    if !has_meaningful_text(&keyword_source) {
+                        keyword_source = combined.clone();
+                    }
+                    if !has_meaningful_text(&snippet_source) {
+                        snippet_source = combined.clone();
+                    }
+                }
+            }
+            Err(error) => println!(
+                "⚠ OCR failed for {}: {}",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                error
+            ),
+        }
+    }
+
+    if !has_meaningful_text(&keyword_source) && has_meaningful_text(&snippet_source) {
+        keyword_source = snippet_source.clone();
+    }
+
+    let keywords = if has_meaningful_text(&keyword_source) {
+        derive_keywords(&keyword_source, &previews, settings)
+    } else {
+        Vec::new()
+    };
+    let word_count = if has_meaningful_text(&keyword_source) {
+        count_words(&keyword_source, settings.min_token_chars)
+    } else {
+        0
+    };
+    let language = detect_language(&keyword_source);
+    let snippet = truncate_snippet(&snippet_source, settings.snippet_chars);
+    if let Some(checksum) = &checksum {
+        let full_text = if has_meaningful_text(&keyword_source) { keyword_source.as_str() } else { "" };
+        text_cache::put(checksum, full_text);
+    }
+    let presentation_title = contents.title.or_else(|| first_slide_title(&previews));
+
+    // Determine document type based on page orientation
+    let document_type = contents.is_landscape.map(|is_landscape| {
+        if is_landscape {
+            crate::models::DocumentType::Presentation
+        } else {
+            crate::models::DocumentType::Book
+        }
+    });
+    
+    let text_coverage = text_coverage(text_pages, page_count);
+    Ok(SlideIndexItem {
+        id: hash_of(path.to_string_lossy()),
+        path: path.to_string_lossy().to_string(),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string()),
+        kind: SlideKind::Pdf,
+        slide_count: page_count.map(|value| value as u32),
+        snippet,
+        keywords,
+        updated_at: modified_at.unwrap_or_else(current_timestamp),
+        slides: previews,
+        checksum,
+        document_type,
+        file_size: Some(buffer.len() as u64),
+        links: dedupe_links(contents.links),
+        word_count,
+        reading_minutes: reading_minutes(word_count),
+        language,
+        presentation_title,
+        encrypted: encrypted && !decrypted,
+        text_coverage,
+        thumbnail_path: None,
+        last_opened_at: None,
+        open_count: 0,
+        tags: Vec::new(),
+        favorite: false,
+        display_name: None,
+    })
+}
+
+/// Temporary copy of a deck read from memory, for the external tools;
+/// deleted when dropped.
+struct StagedDeck {
+    _dir: TempDir,
+    path: PathBuf,
+}
+
+/// Writes `bytes` to a temporary file called `file_name`.
+fn stage_bytes(bytes: &[u8], file_name: &Path) -> Result<StagedDeck> {
+    let dir = tempdir()?;
+    let path = dir.path().join(file_name.file_name().unwrap_or_else(|| "deck".as_ref()));
+    fs::write(&path, bytes)?;
+    Ok(StagedDeck { _dir: dir, path })
+}
+
+/// True when the trailer (or cross-reference stream) names an `/Encrypt`
+/// dictionary, i.e. the PDF's strings and streams are encrypted.
+fn pdf_is_encrypted(buffer: &[u8]) -> bool {
+    PDF_ENCRYPT_REGEX.is_match(buffer)
+}
+
+pub(super) struct PdfContents {
+    pub(super) text: String,
+    page_count: Option<usize>,
+    pages: Vec<String>,
+    is_landscape: Option<bool>,
+    has_fonts: bool,
+    has_images: bool,
+    links: Vec<String>,
+    title: Option<String>, // `/Title` of the document information dictionary
+}
+
+impl PdfContents {
+    /// Text can only be drawn through a font resource, so a PDF with images but
+    /// no `/Font` anywhere (raw or decoded streams) is a scan that needs OCR.
+    fn is_image_only(&self) -> bool {
+        !self.has_fonts && self.has_images
+    }
+}
+
+pub(super) fn extract_pdf_contents(buffer: &[u8]) -> PdfContents {
+    let content = String::from_utf8_lossy(buffer);
+    let mut tree_page_count = page_tree_count(&content);
+
+    // Text of each stream, plus its bytes when it selects fonts: with
+    // `/ToUnicode` CMaps in the file, those are re-read through them at the end.
+    let mut stream_texts: Vec<(String, Option<Vec<u8>>)> = Vec::new();
+    let mut cmaps = HashMap::new();
+    let mut packed_objects = HashMap::new();
+    let mut cursor = 0usize;
+    let mut has_fonts = find_subsequence(buffer, b"/Font").is_some();
+    let has_images = find_subsequence(buffer, b"/Image").is_some();
+    let mut links = extract_pdf_links(&content);
+
+    while let Some(stream_pos) = find_subsequence(&buffer[cursor..], b"stream") {
+        let absolute_stream_pos = cursor + stream_pos;
+        let data_offset = absolute_stream_pos + "stream".len();
+        let dictionary_start = stream_dictionary_start(buffer, absolute_stream_pos);
+
+        // A direct `/Length` bounds the data exactly; searching for `endstream`
+        // is the fallback, as binary data can contain those bytes.
+        let declared = dictionary_start.and_then(|start| {
+            declared_stream_bounds(buffer, &buffer[start..absolute_stream_pos], data_offset)
+        });
+        let bounds = declared.or_else(|| {
+            let mut data_start = data_offset;
+            while data_start < buffer.len()
+                && (buffer[data_start] == b'\r' || buffer[data_start] == b'\n')
+            {
+                data_start += 1;
+            }
+            let end_pos = find_subsequence(buffer.get(data_start..)?, b"endstream")?;
+            let data_end = data_start + end_pos;
+            Some((data_start, data_end, data_end + "endstream".len()))
+        });
+
+        if let Some((data_start, data_end, resume_at)) = bounds {
+            let raw = &buffer[data_start..data_end];
+
+            // The stream's dictionary plus a little before it (for `N 0 obj`);
+            // a fixed window when the dictionary can't be delimited.
+            let header_start = dictionary_start
+                .map_or(absolute_stream_pos.saturating_sub(256), |start| start.saturating_sub(32));
+            let header_slice = &buffer[header_start..absolute_stream_pos];
+            let header = String::from_utf8_lossy(header_slice);
+
+            let decoded = decode_stream(raw, &header);
+
+            // Font dictionaries and the page tree may sit inside compressed object streams.
+            if !has_fonts && find_subsequence(&decoded, b"/Font").is_some() {
+                has_fonts = true;
+            }
+            if find_subsequence(&decoded, b"/URI").is_some() {
+                links.extend(extract_pdf_links(&String::from_utf8_lossy(&decoded)));
+            }
+            if find_subsequence(&decoded, b"/Pages").is_some() {
+                let stream_count = page_tree_count(&String::from_utf8_lossy(&decoded));
+                tree_page_count = tree_page_count.max(stream_count);
+            }
+
+            if header.contains("/ObjStm") {
+                packed_objects.extend(pdf_fonts::object_stream_members(&header, &decoded));
+            }
+            if find_subsequence(&decoded, b"begincmap").is_some() {
+                // A CMap's hex codes aren't text; keep it for decoding the rest.
+                let object_number = last_object_number(&header);
+                if let (Some(number), Some(cmap)) = (object_number, pdf_fonts::parse_cmap(&decoded)) {
+                    cmaps.insert(number, cmap);
+                }
+            } else {
+                let extracted = extract_text_from_pdf_stream(&decoded);
+                let selects_fonts = find_subsequence(&decoded, b"Tf").is_some();
+                stream_texts.push((extracted, selects_fonts.then_some(decoded)));
+            }
+
+            cursor = resume_at;
+        } else {
+            break;
+        }
+    }
+
+    // Prefer the page tree's declared total; counting `/Type /Page` objects is
+    // the fallback for files whose tree we couldn't read.
+    let page_count = tree_page_count.or_else(|| {
+        let count = PAGE_REGEX.find_iter(&content).count();
+        if count == 0 {
+            None
+        } else {
+            Some(count)
+        }
+    });
+
+    let fonts = PdfFonts::build(&content, &packed_objects, cmaps);
+    let segments: Vec<String> = stream_texts
+        .into_iter()
+        .map(|(text, content_stream)| match content_stream {
+            Some(stream) if !fonts.is_empty() => pdf_fonts::extract_text(&stream, &fonts),
+            _ => text,
+        })
+        .filter(|text| !text.is_empty())
+        .collect();
+
+    let is_landscape = detect_pdf_orientation(&content);
+    
+    PdfContents {
+        text: segments.join(" "),
+        page_count,
+        pages: segments,
+        is_landscape,
+        has_fonts,
+        has_images,
+        links,
+        title: pdf_info_title(&content),
+    }
+}
+
+/// Number of the object whose dictionary ends `header` (`12 0 obj << ... >>`).
+fn last_object_number(header: &str) -> Option<u32> {
+    static OBJECT_NUMBER_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(\d+)\s+\d+\s+obj\b").expect("valid regex"));
+    OBJECT_NUMBER_REGEX
+        .captures_iter(header)
+        .last()
+        .and_then(|caps| caps[1].parse().ok())
+}
+
+/// `/Title` of the document information dictionary. Outline (bookmark) items
+/// carry a `/Title` too, so only an object with other info keys and no
+/// `/Parent` counts.
+fn pdf_info_title(content: &str) -> Option<String> {
+    static INFO_KEY_REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"/(?:Producer|Creator|CreationDate|ModDate|Author)\b").expect("valid regex")
+    });
+    static INFO_TITLE_REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"/Title\s*(?:\(((?:\\.|[^\\)])*)\)|<([0-9A-Fa-f\s]*)>)").expect("valid regex")
+    });
+
+    content
+        .split("endobj")
+        .filter(|object| !object.contains("/Parent") && INFO_KEY_REGEX.is_match(object))
+        .filter_map(|object| INFO_TITLE_REGEX.captures(object))
+        .filter_map(|caps| {
+            let title = match (caps.get(1), caps.get(2)) {
+                (Some(literal), _) => decode_pdf_string(literal.as_str()),
+                (None, Some(hex)) => decode_pdf_hex_string(hex.as_str()),
+                (None, None) => return None,
+            };
+            meaningful_title(&title)
+        })
+        .next()
+}
+
+/// Targets of `/URI` link actions (literal or hex strings).
+fn extract_pdf_links(content: &str) -> Vec<String> {
+    PDF_URI_REGEX
+        .captures_iter(content)
+        .filter_map(|caps| {
+            if let Some(literal) = caps.get(1) {
+                Some(decode_pdf_string(literal.as_str()))
+            } else {
+                caps.get(2).map(|hex| decode_pdf_hex_string(hex.as_str()))
+            }
+        })
+        .collect()
+}
+
+/// Largest `/Count` declared by a `/Type /Pages` node. The page tree root
+/// carries the document total, every intermediate node a smaller subtotal.
+fn page_tree_count(content: &str) -> Option<usize> {
+    content
+        .split("endobj")
+        .filter(|object| PAGES_NODE_REGEX.is_match(object))
+        .filter_map(|object| {
+            PAGE_COUNT_REGEX
+                .captures_iter(object)
+                .filter_map(|caps| caps.get(1)?.as_str().parse::<usize>().ok())
+                .max()
+        })
+        .filter(|count| *count > 0)
+        .max()
+}
+
+fn detect_pdf_orientation(content: &str) -> Option<bool> {
+    // Look for MediaBox in PDF structure: /MediaBox [x1 y1 x2 y2]
+    // x2-x1 = width, y2-y1 = height
+    // landscape = width > height
+    
+    let mediabox_regex = regex::Regex::new(r"/MediaBox\s*\[\s*(-?[\d.]+)\s+(-?[\d.]+)\s+(-?[\d.]+)\s+(-?[\d.]+)\s*\]").ok()?;
+    
+    if let Some(caps) = mediabox_regex.captures(content) {
+        let x1: f64 = caps.get(1)?.as_str().parse().ok()?;
+        let y1: f64 = caps.get(2)?.as_str().parse().ok()?;
+        let x2: f64 = caps.get(3)?.as_str().parse().ok()?;
+        let y2: f64 = caps.get(4)?.as_str().parse().ok()?;
+        
+        let width = (x2 - x1).abs();
+        let height = (y2 - y1).abs();
+        
+        Some(width > height)
+    } else {
+        None
+    }
+}
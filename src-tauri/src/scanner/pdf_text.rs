@@ -0,0 +1,171 @@
+//! Text shown by a PDF content stream: the strings passed to its `Tj`/`TJ`
+//! operators, decoded from literal or hex form.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static PDF_TEXT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\((?:\\.|[^\\)])*\)").expect("valid regex"));
+static PDF_HEX_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<([0-9A-Fa-f\s]+)>").expect("valid regex"));
+pub(super) fn extract_text_from_pdf_stream(stream: &[u8]) -> String {
+    let content = String::from_utf8_lossy(stream);
+    let mut segments: Vec<String> = Vec::new();
+
+    for mat in PDF_TEXT_REGEX.find_iter(&content) {
+        let raw = mat.as_str();
+        if raw.len() < 2 {
+            continue;
+        }
+        let decoded = decode_pdf_string(&raw[1..raw.len() - 1]);
+        if !decoded.is_empty() {
+            segments.push(decoded);
+        }
+    }
+
+    for caps in PDF_HEX_REGEX.captures_iter(&content) {
+        if let Some(segment) = caps.get(1) {
+            let decoded = decode_pdf_hex_string(segment.as_str());
+            if !decoded.is_empty() {
+                segments.push(decoded);
+            }
+        }
+    }
+
+    segments.join(" ")
+}
+
+pub(super) fn decode_pdf_string(input: &str) -> String {
+    let mut chars = input.chars().peekable();
+    let mut result = String::new();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+        match chars.peek() {
+            Some('n') => {
+                result.push('\n');
+                chars.next();
+            }
+            Some('r') => {
+                result.push('\r');
+                chars.next();
+            }
+            Some('t') => {
+                result.push('\t');
+                chars.next();
+            }
+            Some('b') => {
+                result.push('\u{0008}');
+                chars.next();
+            }
+            Some('f') => {
+                result.push('\u{000C}');
+                chars.next();
+            }
+            Some('(') => {
+                result.push('(');
+                chars.next();
+            }
+            Some(')') => {
+                result.push(')');
+                chars.next();
+            }
+            Some('\\') => {
+                result.push('\\');
+                chars.next();
+            }
+            Some(oct @ '0'..='7') => {
+                let mut octal = String::new();
+                octal.push(*oct);
+                chars.next();
+                for _ in 0..2 {
+                    if let Some(next) = chars.peek() {
+                        if next.is_ascii_digit() && *next < '8' {
+                            octal.push(*next);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                if let Ok(value) = u8::from_str_radix(&octal, 8) {
+                    result.push(value as char);
+                }
+            }
+            Some(other) => {
+                result.push(*other);
+                chars.next();
+            }
+            None => {}
+        }
+    }
+
+    result
+}
+
+pub(super) fn decode_pdf_hex_string(input: &str) -> String {
+    let sanitized: String = input.chars().filter(|ch| !ch.is_whitespace()).collect();
+    if sanitized.is_empty() {
+        return String::new();
+    }
+
+    let mut bytes = Vec::new();
+    let mut chars = sanitized.chars().peekable();
+    while let Some(first) = chars.next() {
+        let second = chars.peek().copied().unwrap_or('0');
+        let pair = format!("{first}{second}");
+        if let Ok(value) = u8::from_str_radix(&pair, 16) {
+            bytes.push(value);
+        }
+        if chars.peek().is_some() {
+            chars.next();
+        }
+    }
+
+    decode_pdf_encoded_bytes(&bytes)
+}
+
+fn decode_pdf_encoded_bytes(bytes: &[u8]) -> String {
+    if bytes.len() >= 2 {
+        match (bytes[0], bytes[1]) {
+            (0xFE, 0xFF) => {
+                let units: Vec<u16> = bytes[2..]
+                    .chunks(2)
+                    .filter_map(|chunk| {
+                        if chunk.len() == 2 {
+                            Some(u16::from_be_bytes([chunk[0], chunk[1]]))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                if let Ok(value) = String::from_utf16(&units) {
+                    return value;
+                }
+            }
+            (0xFF, 0xFE) => {
+                let units: Vec<u16> = bytes[2..]
+                    .chunks(2)
+                    .filter_map(|chunk| {
+                        if chunk.len() == 2 {
+                            Some(u16::from_le_bytes([chunk[0], chunk[1]]))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                if let Ok(value) = String::from_utf16(&units) {
+                    return value;
+                }
+            }
+            _ => {}
+        }
+    }
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(value) => value,
+        Err(_) => bytes.iter().map(|&byte| byte as char).collect::<String>(),
+    }
+}
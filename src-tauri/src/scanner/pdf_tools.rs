@@ -0,0 +1,250 @@
+//! Text of PDFs the built-in parser can't read: pdftotext for PDFs with
+//! fonts, and OCR (pdftoppm pages through tesseract) for scanned ones.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{atomic::{AtomicUsize, Ordering}, mpsc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use tempfile::tempdir;
+
+use crate::{error::{AppError, Result}, ocr_cache};
+
+use super::{files::long_path, numbered_name_order, tools::{run_tool, COMMAND_STATUS}};
+
+const MAX_OCR_PAGES: usize = 40;
+const MIN_OCR_DPI: &str = "120";
+const PDFTOTEXT_TIMEOUT: Duration = Duration::from_secs(120);
+const PDFTOPPM_TIMEOUT: Duration = Duration::from_secs(300);
+const TESSERACT_PAGE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Tesseract language used when nothing better is known or installed.
+const DEFAULT_OCR_LANGUAGE: &str = "eng";
+
+/// Language packs tesseract reports via `--list-langs` (empty when it isn't installed).
+static TESSERACT_LANGUAGES: Lazy<Vec<String>> = Lazy::new(list_tesseract_languages);
+
+pub(super) fn extract_pdf_with_pdftotext(path: &Path) -> Result<Vec<String>> {
+    let Some(pdftotext) = COMMAND_STATUS.paths.pdftotext.as_ref() else {
+        return Ok(Vec::new());
+    };
+
+    let stdout = run_tool(
+        "pdftotext",
+        Command::new(pdftotext)
+            .arg("-layout")
+            .arg("-enc")
+            .arg("UTF-8")
+            .arg(long_path(path).as_os_str())
+            .arg("-"),
+        PDFTOTEXT_TIMEOUT,
+    )?;
+
+    // pdftotext ends every page with a form feed. Blank pages are kept so the
+    // position of each entry stays its page number.
+    let raw = String::from_utf8_lossy(&stdout);
+    let mut pages = raw
+        .split('\u{c}')
+        .map(|segment| segment.trim().to_string())
+        .collect::<Vec<String>>();
+    if pages.last().is_some_and(|page| page.is_empty()) {
+        pages.pop();
+    }
+    Ok(pages)
+}
+
+/// OCRs the pages of `path`. With a `checksum`, page text is read from and
+/// written to `ocr_cache`, so only pages missing there go through tesseract.
+///
+/// `on_page_done(done, total)` is called on this thread as each page finishes.
+pub(super) fn extract_pdf_with_ocr(
+    path: &Path,
+    checksum: Option<&str>,
+    language: &str,
+    on_page_done: &mut dyn FnMut(usize, usize),
+) -> Result<Vec<String>> {
+    let commands = &COMMAND_STATUS.paths;
+    let (Some(pdftoppm), Some(tesseract)) = (&commands.pdftoppm, &commands.tesseract) else {
+        return Ok(Vec::new());
+    };
+
+    let temp_dir = tempdir().map_err(|error| AppError::Message(error.to_string()))?;
+    let prefix = temp_dir.path().join("page");
+
+    run_tool(
+        "pdftoppm",
+        Command::new(pdftoppm)
+            .arg("-png")
+            .arg("-r")
+            .arg(MIN_OCR_DPI)
+            .arg(long_path(path).as_os_str())
+            .arg(prefix.as_os_str()),
+        PDFTOPPM_TIMEOUT,
+    )?;
+
+    let images = ocr_page_images(temp_dir.path())?;
+
+    // Pages are OCRed by a few workers in parallel; `run_tool` still holds a
+    // `tool_permits` slot per tesseract run, so the global cap applies.
+    let next_page = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<Result<Vec<u8>>>>> =
+        images.iter().map(|_| Mutex::new(None)).collect();
+    let workers = thread::available_parallelism()
+        .map_or(1, |count| count.get())
+        .min(images.len());
+    let (page_done, pages_done) = mpsc::channel::<()>();
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let page_done = page_done.clone();
+            let (next_page, images, results) = (&next_page, &images, &results);
+            scope.spawn(move || loop {
+                let index = next_page.fetch_add(1, Ordering::SeqCst);
+                let Some(image_path) = images.get(index) else {
+                    break;
+                };
+                let cached = checksum
+                    .and_then(|checksum| ocr_cache::get(checksum, index + 1, MIN_OCR_DPI, language));
+                if let Some(text) = cached {
+                    *results[index].lock().expect("OCR result poisoned") = Some(Ok(text.into_bytes()));
+                    let _ = page_done.send(());
+                    continue;
+                }
+                let result = run_tool(
+                    "tesseract",
+                    Command::new(tesseract)
+                        .arg(image_path)
+                        .arg("stdout")
+                        .arg("-l")
+                        .arg(language)
+                        .arg("--psm")
+                        .arg("6"),
+                    TESSERACT_PAGE_TIMEOUT,
+                );
+                if let (Some(checksum), Ok(stdout)) = (checksum, &result) {
+                    let text = String::from_utf8_lossy(stdout);
+                    ocr_cache::put(checksum, index + 1, MIN_OCR_DPI, language, &text);
+                }
+                *results[index].lock().expect("OCR result poisoned") = Some(result);
+                let _ = page_done.send(());
+            });
+        }
+        // With only the workers' senders left, `recv` fails instead of hanging if one dies.
+        drop(page_done);
+        for done in 1..=images.len() {
+            if pages_done.recv().is_err() {
+                break;
+            }
+            on_page_done(done, images.len());
+        }
+    });
+
+    // Collected by page index, so the page order is preserved. Blank and
+    // skipped pages stay as empty entries so later pages keep their numbers.
+    let mut pages = Vec::new();
+    for (index, result) in results.into_iter().enumerate() {
+        let Some(result) = result.into_inner().expect("OCR result poisoned") else {
+            pages.push(String::new());
+            continue;
+        };
+        let stdout = match result {
+            Ok(stdout) => stdout,
+            // A page that crashes or hangs tesseract is skipped; failing to start it at all is fatal.
+            Err(error @ AppError::CommandSpawn { .. }) => return Err(error),
+            Err(error) => {
+                println!("⚠ OCR skipped page {}: {}", index + 1, error);
+                pages.push(String::new());
+                continue;
+            }
+        };
+        pages.push(String::from_utf8_lossy(&stdout).to_string());
+    }
+    Ok(pages)
+}
+
+/// `ppt/slides/slide3.xml` -> `ppt/slides/_rels/slide3.xml.rels`
+/// The number just before a name's extension: 12 for `slide12.xml`, 7 for
+/// `page-07.png`.
+/// The page images pdftoppm wrote to `dir`, in page order and capped at
+/// `MAX_OCR_PAGES`. Their numbers aren't always zero-padded, so name order
+/// would put page-10 before page-2.
+fn ocr_page_images(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut images: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|error| AppError::Message(error.to_string()))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.eq_ignore_ascii_case("png"))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    images.sort_by(|a, b| numbered_name_order(&a.to_string_lossy(), &b.to_string_lossy()));
+    images.truncate(MAX_OCR_PAGES);
+    Ok(images)
+}
+
+/// Tesseract `-l` value for a detected language. whatlang's ISO 639-3 codes
+/// match tesseract's pack names for most languages; anything not installed
+/// falls back to English.
+pub(super) fn tesseract_language(language: Option<&str>) -> &str {
+    match language {
+        Some(code) if TESSERACT_LANGUAGES.iter().any(|installed| installed == code) => code,
+        _ => DEFAULT_OCR_LANGUAGE,
+    }
+}
+
+fn list_tesseract_languages() -> Vec<String> {
+    let Some(tesseract) = &COMMAND_STATUS.paths.tesseract else {
+        return Vec::new();
+    };
+    let output = match run_tool(
+        "tesseract",
+        Command::new(tesseract).arg("--list-langs"),
+        TESSERACT_PAGE_TIMEOUT,
+    ) {
+        Ok(output) => output,
+        Err(error) => {
+            println!("⚠ Could not list tesseract languages: {}", error);
+            return Vec::new();
+        }
+    };
+    // First line is a "List of available languages ..." header.
+    String::from_utf8_lossy(&output)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.ends_with(':'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn twelve_ocr_pages_stay_in_page_order() {
+        let dir = tempdir().unwrap();
+        // Written out of order, unpadded as some pdftoppm builds name them.
+        for page in [10, 3, 12, 1, 7, 11, 2, 9, 5, 4, 8, 6] {
+            fs::write(dir.path().join(format!("page-{page}.png")), b"").unwrap();
+        }
+        fs::write(dir.path().join("page-1.txt"), b"").unwrap();
+
+        let names: Vec<String> = ocr_page_images(dir.path())
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+
+        let expected: Vec<String> = (1..=12).map(|page| format!("page-{page}.png")).collect();
+        assert_eq!(names, expected);
+        assert_eq!(&names[8..], ["page-9.png", "page-10.png", "page-11.png", "page-12.png"]);
+    }
+}
@@ -0,0 +1,237 @@
+//! Deciding which files a scan re-indexes, and the dry-run plan of it.
+
+use std::{borrow::Cow, collections::{HashMap, HashSet}, path::Path};
+
+use crate::{
+    error::Result,
+    models::{PlannedAction, PlannedFile, ScanPlan, ScanSettings, SlideIndexItem},
+};
+
+use super::{
+    archive::deck_sources,
+    deck_kind,
+    files::path_key,
+    is_excluded,
+    is_temporary_deck,
+};
+
+/// How a scan treats one file it found, given the item indexed for it last time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanAction {
+    /// Modification time unchanged; reused without reading the file.
+    CachedByTime,
+    /// Modification time changed but the contents didn't.
+    CachedByChecksum,
+    Rescan { reason: String },
+    New,
+}
+
+/// Decides whether a file can reuse its `existing` index entry. Pass `None` as
+/// the checksum for the cheap mtime-only check; anything other than
+/// `CachedByTime` then needs a second call with the real checksum.
+pub fn decide_scan_action(
+    existing: Option<&SlideIndexItem>,
+    modified_at: Option<u64>,
+    checksum: &Option<String>,
+) -> ScanAction {
+    let Some(existing) = existing else {
+        return ScanAction::New;
+    };
+    if modified_at == Some(existing.updated_at) {
+        return ScanAction::CachedByTime;
+    }
+    let reason = match (&existing.checksum, checksum) {
+        (Some(old), Some(new)) if old == new => return ScanAction::CachedByChecksum,
+        (None, None) => "both checksums missing".to_string(),
+        (None, Some(_)) => "existing has no checksum".to_string(),
+        (Some(_), None) => "new checksum failed to calculate".to_string(),
+        (Some(old), Some(new)) => {
+            format!("checksum changed: {}.. -> {}..", short_checksum(old), short_checksum(new))
+        }
+    };
+    ScanAction::Rescan { reason }
+}
+
+/// `decide_scan_action` for a catch-up scan: an indexed file last modified
+/// before `since` is kept as-is without a checksum. Files the index has never
+/// seen are still indexed, whatever their age, since there's nothing to keep.
+pub fn decide_scan_action_since(
+    existing: Option<&SlideIndexItem>,
+    modified_at: Option<u64>,
+    since: Option<u64>,
+) -> ScanAction {
+    match (existing, modified_at, since) {
+        (Some(_), Some(modified_at), Some(since)) if modified_at < since => ScanAction::CachedByTime,
+        _ => decide_scan_action(existing, modified_at, &None),
+    }
+}
+
+/// First 8 characters of a checksum for log output (all of it if shorter).
+pub(super) fn short_checksum(checksum: &str) -> &str {
+    checksum.get(..8).unwrap_or(checksum)
+}
+
+/// Most files listed in `ScanPlan::samples`.
+const MAX_PLAN_SAMPLES: usize = 50;
+/// Works out what `scan_directories` would do with `existing`, without indexing
+/// anything. Changed files are still checksummed to tell them from touched ones.
+pub fn plan_scan(
+    directories: &[String],
+    existing: &[SlideIndexItem],
+    settings: &ScanSettings,
+) -> Result<ScanPlan> {
+    let existing_map: HashMap<Cow<str>, &SlideIndexItem> =
+        existing.iter().map(|item| (path_key(&item.path), item)).collect();
+    let mut found_files: HashSet<String> = HashSet::new();
+    let mut plan = ScanPlan::default();
+    let sample = |plan: &mut ScanPlan, path: String, action, reason| {
+        if plan.samples.len() < MAX_PLAN_SAMPLES {
+            plan.samples.push(PlannedFile { path, action, reason });
+        }
+    };
+
+    for directory in directories {
+        let path = Path::new(directory);
+        if !path.exists() {
+            continue;
+        }
+        for source in deck_sources(path, settings, &mut Vec::new())? {
+            let item_path = source.item_path();
+            let file_path = item_path.as_path();
+            if deck_kind(file_path).is_none() || is_temporary_deck(file_path) {
+                continue;
+            }
+            let path_string = file_path.to_string_lossy().to_string();
+            if !found_files.insert(path_key(&path_string).into_owned()) || is_excluded(&path_string, settings) {
+                continue;
+            }
+            let previous = existing_map.get(&path_key(&path_string)).copied();
+            let modified_at = source.modified_ms();
+
+            let mut action = decide_scan_action(previous, modified_at, &None);
+            if action == ScanAction::CachedByTime {
+                plan.cached += 1;
+                continue;
+            }
+            if let Some(reason) = source.oversized_reason(settings.max_file_bytes) {
+                plan.skipped += 1;
+                sample(&mut plan, path_string, PlannedAction::Skipped, Some(reason));
+                continue;
+            }
+            if previous.is_some() {
+                let checksum = source.checksum().ok();
+                action = decide_scan_action(previous, modified_at, &checksum);
+            }
+            match action {
+                ScanAction::CachedByTime | ScanAction::CachedByChecksum => plan.cached += 1,
+                ScanAction::Rescan { reason } => {
+                    plan.changed += 1;
+                    sample(&mut plan, path_string, PlannedAction::Changed, Some(reason));
+                }
+                ScanAction::New => {
+                    plan.new += 1;
+                    sample(&mut plan, path_string, PlannedAction::New, None);
+                }
+            }
+        }
+    }
+
+    for item in existing
+        .iter()
+        .filter(|item| !found_files.contains(path_key(&item.path).as_ref()))
+    {
+        plan.deleted += 1;
+        sample(&mut plan, item.path.clone(), PlannedAction::Deleted, None);
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::tests::item;
+
+    fn rescan_reason(action: ScanAction) -> String {
+        match action {
+            ScanAction::Rescan { reason } => reason,
+            other => panic!("expected a rescan, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unknown_file_is_new() {
+        assert_eq!(decide_scan_action(None, Some(5), &None), ScanAction::New);
+        assert_eq!(decide_scan_action(None, Some(5), &Some("abc".into())), ScanAction::New);
+    }
+
+    #[test]
+    fn unchanged_mtime_is_cached_by_time() {
+        let existing = item("/decks/a.pptx", 5, Some("abc"));
+        assert_eq!(decide_scan_action(Some(&existing), Some(5), &None), ScanAction::CachedByTime);
+    }
+
+    #[test]
+    fn same_checksum_is_cached_by_checksum() {
+        let existing = item("/decks/a.pptx", 5, Some("abc"));
+        assert_eq!(
+            decide_scan_action(Some(&existing), Some(6), &Some("abc".into())),
+            ScanAction::CachedByChecksum
+        );
+    }
+
+    #[test]
+    fn missing_checksums_force_a_rescan() {
+        let without = item("/decks/a.pptx", 5, None);
+        let with = item("/decks/a.pptx", 5, Some("abc"));
+        assert_eq!(rescan_reason(decide_scan_action(Some(&without), Some(6), &None)), "both checksums missing");
+        assert_eq!(
+            rescan_reason(decide_scan_action(Some(&without), Some(6), &Some("abc".into()))),
+            "existing has no checksum"
+        );
+        assert_eq!(
+            rescan_reason(decide_scan_action(Some(&with), Some(6), &None)),
+            "new checksum failed to calculate"
+        );
+        // Unknown mtime never counts as unchanged.
+        assert_eq!(rescan_reason(decide_scan_action(Some(&with), None, &None)), "new checksum failed to calculate");
+    }
+
+    #[test]
+    fn changed_checksum_is_logged_shortened() {
+        let existing = item("/decks/a.pptx", 5, Some("0123456789abcdef"));
+        assert_eq!(
+            rescan_reason(decide_scan_action(Some(&existing), Some(6), &Some("fedcba9876543210".into()))),
+            "checksum changed: 01234567.. -> fedcba98.."
+        );
+    }
+
+    #[test]
+    fn short_checksums_are_logged_whole() {
+        let existing = item("/decks/a.pptx", 5, Some("abc"));
+        assert_eq!(
+            rescan_reason(decide_scan_action(Some(&existing), Some(6), &Some("de".into()))),
+            "checksum changed: abc.. -> de.."
+        );
+        // Cutting inside a multi-byte character falls back to the whole string.
+        assert_eq!(short_checksum("abcdefgé12"), "abcdefgé12");
+    }
+
+    #[test]
+    fn catch_up_scan_keeps_old_files_without_a_checksum() {
+        let existing = item("/decks/a.pptx", 5, Some("abc"));
+        assert_eq!(decide_scan_action_since(Some(&existing), Some(6), Some(10)), ScanAction::CachedByTime);
+        // Modified after the cutoff: falls through to the mtime-only check.
+        assert_eq!(
+            rescan_reason(decide_scan_action_since(Some(&existing), Some(12), Some(10))),
+            "new checksum failed to calculate"
+        );
+        assert_eq!(decide_scan_action_since(Some(&existing), Some(5), Some(10)), ScanAction::CachedByTime);
+        // Never-seen files are indexed however old they are.
+        assert_eq!(decide_scan_action_since(None, Some(6), Some(10)), ScanAction::New);
+        // No cutoff behaves like the plain check.
+        assert_eq!(
+            rescan_reason(decide_scan_action_since(Some(&existing), Some(6), None)),
+            "new checksum failed to calculate"
+        );
+    }
+}
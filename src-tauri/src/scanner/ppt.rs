@@ -0,0 +1,111 @@
+//! Indexing legacy binary PowerPoint (`.ppt`) files by scraping readable
+//! text out of the file.
+
+use std::{fs, path::Path};
+
+use crate::{
+    error::{AppError, Result},
+    models::{ScanSettings, SlideIndexItem, SlideKind, SlidePreview},
+    text_cache,
+};
+
+use super::{
+    current_timestamp,
+    files::{long_path, retry_io},
+    has_ole_stream,
+    hash_of,
+    text::{
+        cleanup_whitespace, count_words, derive_keywords, detect_language, filter_noise_tokens,
+        is_gibberish, reading_minutes, strip_binary_artifacts, strip_xml_tags,
+        truncate_snippet,
+    },
+    OLE_MAGIC,
+};
+
+pub(super) fn index_ppt(
+    path: &Path,
+    modified_at: Option<u64>,
+    checksum: Option<String>,
+    settings: &ScanSettings,
+) -> Result<SlideIndexItem> {
+    let buffer = retry_io(|| fs::read(long_path(path)))?;
+    index_ppt_bytes(&buffer, path, modified_at, checksum, settings)
+}
+
+/// Indexes the legacy PowerPoint file in `buffer` as the deck at `path`.
+pub(super) fn index_ppt_bytes(
+    buffer: &[u8],
+    path: &Path,
+    modified_at: Option<u64>,
+    checksum: Option<String>,
+    settings: &ScanSettings,
+) -> Result<SlideIndexItem> {
+    // PowerPoint stores an encrypted .ppt's document properties in this stream;
+    // the slide text itself is unreadable ciphertext.
+    if buffer.starts_with(&OLE_MAGIC) && has_ole_stream(buffer, "EncryptedSummary") {
+        return Err(AppError::PasswordProtected);
+    }
+    let ascii: String = buffer
+        .iter()
+        .map(|byte| match byte {
+            0x09 | 0x0A | 0x0D => *byte as char,
+            0x20..=0x7E => *byte as char,
+            _ => ' ',
+        })
+        .collect();
+    let cleaned = cleanup_whitespace(&filter_noise_tokens(&strip_binary_artifacts(
+        &strip_xml_tags(&ascii),
+    )));
+    let previews = if cleaned.is_empty() || is_gibberish(&cleaned) {
+        Vec::new()
+    } else {
+        vec![SlidePreview {
+            index: 1,
+            title: None,
+            text: cleaned.clone(),
+            outline: None,
+        }]
+    };
+    let effective_snippet = if previews.is_empty() {
+        String::new()
+    } else {
+        cleaned.clone()
+    };
+    if let Some(checksum) = &checksum {
+        text_cache::put(checksum, &effective_snippet);
+    }
+    let keywords = derive_keywords(&effective_snippet, &previews, settings);
+    let word_count = count_words(&effective_snippet, settings.min_token_chars);
+    let language = detect_language(&effective_snippet);
+
+    Ok(SlideIndexItem {
+        id: hash_of(path.to_string_lossy()),
+        path: path.to_string_lossy().to_string(),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string()),
+        kind: SlideKind::Ppt,
+        slide_count: None,
+        snippet: truncate_snippet(&effective_snippet, settings.snippet_chars),
+        keywords,
+        updated_at: modified_at.unwrap_or_else(current_timestamp),
+        slides: previews,
+        checksum,
+        document_type: Some(crate::models::DocumentType::Presentation),  // PPT files are always presentations
+        file_size: Some(buffer.len() as u64),
+        links: Vec::new(),
+        word_count,
+        reading_minutes: reading_minutes(word_count),
+        language,
+        presentation_title: None,
+        encrypted: false,
+        text_coverage: None, // the slide count of a .ppt isn't known
+        thumbnail_path: None,
+        last_opened_at: None,
+        open_count: 0,
+        tags: Vec::new(),
+        favorite: false,
+        display_name: None,
+    })
+}
@@ -0,0 +1,503 @@
+//! Indexing PowerPoint Open XML (`.pptx`) decks: slide text, tables, charts,
+//! speaker-facing outlines, links and embedded documents.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{Cursor, Read},
+    path::Path,
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    error::{AppError, Result},
+    models::{ScanSettings, SlideIndexItem, SlideKind, SlidePreview},
+    text_cache,
+};
+
+use super::{
+    archive::zip_archive,
+    current_timestamp,
+    dedupe_links,
+    embedded::extract_embedded_text,
+    files::{long_path, retry_io},
+    has_ole_stream,
+    hash_of,
+    numbered_name_order,
+    text::{
+        cleanup_whitespace, count_words, derive_keywords, detect_language, filter_noise_tokens,
+        first_slide_title, meaningful_title, reading_minutes, repeats_previous_preview,
+        strip_binary_artifacts, strip_xml_tags, text_coverage, truncate_snippet,
+    },
+    OLE_MAGIC,
+};
+
+/// Embedded documents `index_pptx` reads per deck, the largest it opens, and
+/// the most text it keeps from each.
+const MAX_EMBEDDINGS: usize = 10;
+const MAX_EMBEDDING_BYTES: u64 = 20 * 1024 * 1024;
+const MAX_EMBEDDED_TEXT_CHARS: usize = 10_000;
+
+static TEXT_RUN_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<a:t[^>]*>(.*?)</a:t>").expect("valid regex"));
+static CORE_TITLE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<dc:title>(.*?)</dc:title>").expect("valid regex"));
+static TABLE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<a:tbl\b[^>]*>.*?</a:tbl>").expect("valid regex"));
+static TABLE_ROW_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<a:tr\b[^>]*>.*?</a:tr>").expect("valid regex"));
+static TABLE_CELL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<a:tc\b[^>]*>.*?</a:tc>").expect("valid regex"));
+
+/// Placed between table cells (and rows) so a phrase can't match across them.
+const TABLE_CELL_SEPARATOR: &str = " | ";
+
+/// Parts of a PPTX package `index_pptx` reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PptxPart {
+    Slide,
+    SlideRels,
+    DiagramData,
+    Chart,
+    CoreProperties,
+    Embedding,
+}
+
+impl PptxPart {
+    fn of(name: &str) -> Option<Self> {
+        if name.starts_with("ppt/slides/slide") && name.ends_with(".xml") {
+            Some(Self::Slide)
+        } else if name.starts_with("ppt/slides/_rels/") && name.ends_with(".xml.rels") {
+            Some(Self::SlideRels)
+        } else if name.starts_with("ppt/diagrams/data") && name.ends_with(".xml") {
+            Some(Self::DiagramData)
+        } else if name.starts_with("ppt/charts/chart") && name.ends_with(".xml") {
+            Some(Self::Chart)
+        } else if name == "docProps/core.xml" {
+            Some(Self::CoreProperties)
+        } else if name.starts_with("ppt/embeddings/") && !name.ends_with('/') {
+            Some(Self::Embedding)
+        } else {
+            None
+        }
+    }
+}
+
+pub(super) fn index_pptx(
+    path: &Path,
+    modified_at: Option<u64>,
+    checksum: Option<String>,
+    settings: &ScanSettings,
+) -> Result<SlideIndexItem> {
+    let buffer = retry_io(|| fs::read(long_path(path)))?;
+    index_pptx_bytes(&buffer, path, modified_at, checksum, settings)
+}
+
+/// Indexes the PPTX in `buffer` as the deck at `path`, which needn't exist on
+/// disk (say a deck inside a zip archive).
+pub(super) fn index_pptx_bytes(
+    buffer: &[u8],
+    path: &Path,
+    modified_at: Option<u64>,
+    checksum: Option<String>,
+    settings: &ScanSettings,
+) -> Result<SlideIndexItem> {
+    // An encrypted PPTX isn't a ZIP at all but an OLE container wrapping the
+    // encrypted package, which the ZIP reader would only call "invalid".
+    if buffer.starts_with(&OLE_MAGIC) && has_ole_stream(buffer, "EncryptionInfo") {
+        return Err(AppError::PasswordProtected);
+    }
+    // Without a readable central directory there's no list of parts to salvage.
+    let mut archive = zip_archive(Cursor::new(buffer))?;
+    let mut slide_entries = Vec::new();
+    let mut slide_rels: HashMap<String, String> = HashMap::new();
+    // Text of SmartArt diagrams, charts and embedded documents, keyed by part name.
+    let mut linked_part_texts: HashMap<String, String> = HashMap::new();
+    let mut core_title = None;
+    let mut embeddings_read = 0;
+
+    // A damaged part only costs that part: the rest of the deck is still indexed.
+    for i in 0..archive.len() {
+        let mut file = match archive.by_index(i) {
+            Ok(file) => file,
+            Err(error) => {
+                println!("⚠ Skipping unreadable entry {} of {}: {}", i, path.display(), error);
+                continue;
+            }
+        };
+        let name = file.name().to_string();
+        let Some(part) = PptxPart::of(&name) else {
+            continue;
+        };
+        if part == PptxPart::Embedding {
+            if embeddings_read >= MAX_EMBEDDINGS || file.size() > MAX_EMBEDDING_BYTES {
+                continue;
+            }
+            embeddings_read += 1;
+            let mut bytes = Vec::new();
+            match file.read_to_end(&mut bytes) {
+                Ok(_) => {
+                    if let Some(text) = extract_embedded_text(&bytes) {
+                        linked_part_texts.insert(name, truncate_snippet(&text, MAX_EMBEDDED_TEXT_CHARS));
+                    }
+                }
+                Err(error) => {
+                    println!("⚠ Skipping unreadable embedding {} of {}: {}", name, path.display(), error);
+                }
+            }
+            continue;
+        }
+        let mut xml = String::new();
+        if let Err(error) = file.read_to_string(&mut xml) {
+            println!("⚠ Skipping unreadable part {} of {}: {}", name, path.display(), error);
+            // An empty slide keeps the numbering of the slides after it.
+            if part == PptxPart::Slide {
+                slide_entries.push((name, String::new()));
+            }
+            continue;
+        }
+        match part {
+            PptxPart::Slide => slide_entries.push((name, xml)),
+            PptxPart::SlideRels => {
+                slide_rels.insert(name, xml);
+            }
+            // SmartArt text lives in the diagram data part, not in the slide XML.
+            PptxPart::DiagramData => {
+                linked_part_texts.insert(name, clean_run_text(&extract_text_runs(&xml)));
+            }
+            PptxPart::Chart => {
+                linked_part_texts.insert(name, extract_chart_text(&xml));
+            }
+            PptxPart::CoreProperties => {
+                core_title = CORE_TITLE_REGEX
+                    .captures(&xml)
+                    .and_then(|caps| meaningful_title(&decode_xml(&caps[1])));
+            }
+            PptxPart::Embedding => unreachable!("embeddings are read as bytes above"),
+        }
+    }
+
+    // Archive order is whatever the writer chose; slide2.xml belongs before slide10.xml.
+    slide_entries.sort_by(|(a, _), (b, _)| numbered_name_order(a, b));
+
+    let mut previews = Vec::new();
+    let mut combined_text = String::new();
+    let mut text_slide_count = 0u32;
+    let total_slides = slide_entries.len();
+    let mut referenced_parts: HashSet<String> = HashSet::new();
+    let mut links = Vec::new();
+    for (index, (name, xml)) in slide_entries.into_iter().enumerate() {
+        let mut text = extract_slide_text(&xml);
+        let mut outline = extract_slide_outline(&xml);
+        let relationships = slide_rels
+            .get(&slide_rels_path(&name))
+            .map(|rels| parse_relationships(rels))
+            .unwrap_or_default();
+        links.extend(
+            relationships
+                .iter()
+                .filter(|rel| rel.external && rel.rel_type.ends_with("/hyperlink"))
+                .map(|rel| rel.target.clone()),
+        );
+        for relationship in relationships.iter().filter(|rel| {
+            !rel.external
+                && ["/diagramData", "/chart", "/oleObject", "/package"]
+                    .iter()
+                    .any(|kind| rel.rel_type.ends_with(kind))
+        }) {
+            let target = resolve_part_target("ppt/slides", &relationship.target);
+            if let Some(part_text) = linked_part_texts.get(&target) {
+                referenced_parts.insert(target);
+                if !part_text.is_empty() {
+                    if !text.is_empty() {
+                        text.push(' ');
+                        outline.push('\n');
+                    }
+                    text.push_str(part_text);
+                    outline.push_str(part_text);
+                }
+            }
+        }
+        if !text.is_empty() {
+            text_slide_count += 1;
+            if repeats_previous_preview(&previews, &text) {
+                continue;
+            }
+            previews.push(SlidePreview {
+                index: index as u32 + 1,
+                title: extract_slide_title(&xml),
+                text: text.clone(),
+                outline: outline.contains('\n').then_some(outline),
+            });
+            if !combined_text.is_empty() {
+                combined_text.push(' ');
+            }
+            combined_text.push_str(&text);
+        }
+    }
+
+    // Diagrams, charts and embeddings no slide points at still count towards the deck's text.
+    for (name, part_text) in &linked_part_texts {
+        if !referenced_parts.contains(name) && !part_text.is_empty() {
+            combined_text.push(' ');
+            combined_text.push_str(part_text);
+        }
+    }
+
+    let cleaned_text = cleanup_whitespace(&combined_text);
+    if let Some(checksum) = &checksum {
+        text_cache::put(checksum, &cleaned_text);
+    }
+    let keywords = derive_keywords(&cleaned_text, &previews, settings);
+    let word_count = count_words(&cleaned_text, settings.min_token_chars);
+    let language = detect_language(&cleaned_text);
+    let presentation_title = core_title.or_else(|| first_slide_title(&previews));
+
+    Ok(SlideIndexItem {
+        id: hash_of(path.to_string_lossy()),
+        path: path.to_string_lossy().to_string(),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string()),
+        kind: SlideKind::Pptx,
+        slide_count: if text_slide_count == 0 {
+            None
+        } else {
+            Some(text_slide_count)
+        },
+        snippet: truncate_snippet(&cleaned_text, settings.snippet_chars),
+        keywords,
+        updated_at: modified_at.unwrap_or_else(current_timestamp),
+        slides: previews,
+        checksum,
+        document_type: Some(crate::models::DocumentType::Presentation),  // PPTX files are always presentations
+        file_size: Some(buffer.len() as u64),
+        links: dedupe_links(links),
+        word_count,
+        reading_minutes: reading_minutes(word_count),
+        language,
+        presentation_title,
+        encrypted: false,
+        text_coverage: text_coverage(text_slide_count as usize, Some(total_slides)),
+        thumbnail_path: None,
+        last_opened_at: None,
+        open_count: 0,
+        tags: Vec::new(),
+        favorite: false,
+        display_name: None,
+    })
+}
+
+/// One `<Relationship>` from an OOXML `.rels` part.
+struct Relationship {
+    rel_type: String,
+    target: String,
+    external: bool, // TargetMode="External", e.g. a hyperlink URL
+}
+
+fn parse_relationships(xml: &str) -> Vec<Relationship> {
+    static RELATIONSHIP_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"<Relationship\b([^>]*)/?>").expect("valid regex"));
+    static ATTRIBUTE_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"(\w+)\s*=\s*"([^"]*)""#).expect("valid regex"));
+
+    RELATIONSHIP_REGEX
+        .captures_iter(xml)
+        .filter_map(|caps| {
+            let mut rel_type = String::new();
+            let mut target = None;
+            let mut external = false;
+            for attribute in ATTRIBUTE_REGEX.captures_iter(caps.get(1)?.as_str()) {
+                let value = decode_xml(&attribute[2]);
+                match &attribute[1] {
+                    "Type" => rel_type = value,
+                    "Target" => target = Some(value),
+                    "TargetMode" => external = value.eq_ignore_ascii_case("External"),
+                    _ => {}
+                }
+            }
+            Some(Relationship {
+                rel_type,
+                target: target?,
+                external,
+            })
+        })
+        .collect()
+}
+
+fn slide_rels_path(slide_part: &str) -> String {
+    match slide_part.rsplit_once('/') {
+        Some((dir, file)) => format!("{dir}/_rels/{file}.rels"),
+        None => format!("_rels/{slide_part}.rels"),
+    }
+}
+
+/// Resolves a relationship target such as `../diagrams/data1.xml` against the
+/// directory of the part that owns the `.rels` file.
+fn resolve_part_target(base_dir: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut segments: Vec<&str> = base_dir.split('/').filter(|part| !part.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            other => segments.push(other),
+        }
+    }
+    segments.join("/")
+}
+
+/// Cleaned text of one slide: free text first, then each table with its
+/// cells and rows kept apart by `TABLE_CELL_SEPARATOR`.
+fn extract_slide_text(xml: &str) -> String {
+    let body_xml = TABLE_REGEX.replace_all(xml, " ");
+    let mut parts = vec![clean_run_text(&extract_text_runs(&body_xml))];
+
+    for table in TABLE_REGEX.find_iter(xml) {
+        let cells: Vec<String> = TABLE_ROW_REGEX
+            .find_iter(table.as_str())
+            .flat_map(|row| TABLE_CELL_REGEX.find_iter(row.as_str()))
+            .map(|cell| clean_run_text(&extract_text_runs(cell.as_str())))
+            .filter(|cell| !cell.is_empty())
+            .collect();
+        parts.push(cells.join(TABLE_CELL_SEPARATOR));
+    }
+
+    parts.retain(|part| !part.is_empty());
+    parts.join(" ")
+}
+
+/// Text of one slide for display: one line per paragraph, indented two spaces
+/// per list level and prefixed with "• " when the paragraph has its own bullet
+/// or is nested; each table row goes on its own line.
+fn extract_slide_outline(xml: &str) -> String {
+    static PARAGRAPH_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?s)<a:p(?:\s[^>]*)?>(.*?)</a:p>").expect("valid regex"));
+    static LEVEL_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"<a:pPr\b[^>]*\blvl="(\d+)""#).expect("valid regex"));
+    static BULLET_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"<a:bu(?:Char|AutoNum)\b").expect("valid regex"));
+
+    let body_xml = TABLE_REGEX.replace_all(xml, " ");
+    let mut lines = Vec::new();
+    for paragraph in PARAGRAPH_REGEX.captures_iter(&body_xml) {
+        let paragraph = &paragraph[1];
+        let text = clean_run_text(&extract_text_runs(paragraph));
+        if text.is_empty() {
+            continue;
+        }
+        let level: usize = LEVEL_REGEX
+            .captures(paragraph)
+            .and_then(|caps| caps[1].parse().ok())
+            .unwrap_or(0);
+        let bullet = if level > 0 || BULLET_REGEX.is_match(paragraph) { "• " } else { "" };
+        lines.push(format!("{}{}{}", "  ".repeat(level), bullet, text));
+    }
+
+    for table in TABLE_REGEX.find_iter(xml) {
+        for row in TABLE_ROW_REGEX.find_iter(table.as_str()) {
+            let cells: Vec<String> = TABLE_CELL_REGEX
+                .find_iter(row.as_str())
+                .map(|cell| clean_run_text(&extract_text_runs(cell.as_str())))
+                .filter(|cell| !cell.is_empty())
+                .collect();
+            if !cells.is_empty() {
+                lines.push(cells.join(TABLE_CELL_SEPARATOR));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// Chart title/axis runs plus the cached category and series labels
+/// (`<c:pt><c:v>`), each label once, through the usual noise filtering.
+fn extract_chart_text(xml: &str) -> String {
+    static CHART_VALUE_REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?s)<c:pt\b[^>]*>\s*<c:v>(.*?)</c:v>").expect("valid regex")
+    });
+
+    let mut seen = HashSet::new();
+    let mut parts = vec![extract_text_runs(xml)];
+    for caps in CHART_VALUE_REGEX.captures_iter(xml) {
+        let value = decode_xml(&caps[1]).trim().to_string();
+        if !value.is_empty() && seen.insert(value.clone()) {
+            parts.push(value);
+        }
+    }
+    clean_run_text(&parts.join(" "))
+}
+
+fn clean_run_text(runs: &str) -> String {
+    let stripped = strip_xml_tags(runs);
+    let sanitized = strip_binary_artifacts(&stripped);
+    let filtered = filter_noise_tokens(&sanitized);
+    cleanup_whitespace(&filtered)
+}
+
+fn extract_text_runs(xml: &str) -> String {
+    TEXT_RUN_REGEX
+        .captures_iter(xml)
+        .filter_map(|capture| capture.get(1))
+        .map(|segment| decode_xml(segment.as_str()))
+        .filter(|segment| !segment.trim().is_empty())
+        .map(|segment| segment.trim().to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+pub(super) fn decode_xml(input: &str) -> String {
+    let mut output = input
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'");
+
+    static DECIMAL_ENTITY: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"&#(\d+);").expect("valid regex"));
+    static HEX_ENTITY: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"&#x([0-9a-fA-F]+);").expect("valid regex"));
+
+    output = DECIMAL_ENTITY
+        .replace_all(&output, |caps: &regex::Captures| {
+            caps.get(1)
+                .and_then(|m| m.as_str().parse::<u32>().ok())
+                .and_then(char::from_u32)
+                .map(|c| c.to_string())
+                .unwrap_or_default()
+        })
+        .into_owned();
+
+    output = HEX_ENTITY
+        .replace_all(&output, |caps: &regex::Captures| {
+            caps.get(1)
+                .and_then(|m| u32::from_str_radix(m.as_str(), 16).ok())
+                .and_then(char::from_u32)
+                .map(|c| c.to_string())
+                .unwrap_or_default()
+        })
+        .into_owned();
+
+    output
+}
+
+/// Text of the slide's title placeholder (`title` or `ctrTitle`), if it has one.
+fn extract_slide_title(xml: &str) -> Option<String> {
+    static SHAPE_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?s)<p:sp\b[^>]*>.*?</p:sp>").expect("valid regex"));
+    static TITLE_PLACEHOLDER_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"<p:ph\b[^>]*\btype="(?:title|ctrTitle)""#).expect("valid regex"));
+
+    SHAPE_REGEX
+        .find_iter(xml)
+        .filter(|shape| TITLE_PLACEHOLDER_REGEX.is_match(shape.as_str()))
+        .map(|shape| clean_run_text(&extract_text_runs(shape.as_str())))
+        .find(|title| !title.is_empty())
+}
@@ -0,0 +1,516 @@
+//! Cleaning extracted text and deriving what's shown and searched from it:
+//! snippets, keywords, titles, language and word counts.
+
+use std::{collections::{HashMap, HashSet}};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use whatlang::detect;
+
+use crate::{models::{ScanSettings, SlideIndexItem, SlidePreview}};
+
+static TOKEN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[a-z0-9]+").expect("valid regex"));
+/// `ScanSettings::min_token_chars` is raised to this; single characters would
+/// swamp the keywords.
+const MIN_TOKEN_CHARS_FLOOR: usize = 2;
+
+/// Language detection only looks at this many leading characters.
+const LANGUAGE_SAMPLE_CHARS: usize = 10_000;
+/// Average silent reading speed used for `reading_minutes`.
+const WORDS_PER_MINUTE: u32 = 200;
+/// Decks shorter than this keep tokens found on every slide as keywords.
+const MIN_SLIDES_FOR_BOILERPLATE: usize = 4;
+
+static NOISE_WORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+    HashSet::from([
+        "rectangle",
+        "title",
+        "subtitle",
+        "body",
+        "outline",
+        "placeholder",
+        "arial",
+        "calibri",
+        "bold",
+        "italic",
+        "regular",
+    ])
+});
+
+/// Common English words that would otherwise top every deck's keywords once
+/// slide text counts towards them.
+static KEYWORD_STOP_WORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+    HashSet::from([
+        "the", "and", "for", "are", "but", "not", "you", "all", "any", "can", "had", "her",
+        "was", "one", "our", "out", "has", "him", "his", "how", "its", "may", "new", "now",
+        "see", "two", "who", "did", "get", "use", "she", "too", "way", "what", "when", "with",
+        "this", "that", "from", "have", "they", "will", "your", "been", "were", "which",
+        "their", "there", "them", "then", "than", "these", "those", "into", "more", "most",
+        "some", "such", "only", "also", "each", "other", "about", "over", "after", "where",
+        "while", "would", "could", "should", "here", "just", "very", "does", "being",
+        // Only reachable with `min_token_chars` set to 2.
+        "an", "as", "at", "be", "by", "do", "he", "if", "in", "is", "it", "me", "my", "no",
+        "of", "on", "or", "so", "to", "up", "us", "we",
+    ])
+});
+static NOISE_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    vec![
+        Regex::new(r"^[a-zA-Z]{2}-[a-zA-Z]{2}$").expect("valid regex"),
+        Regex::new(r"^latin-\d+$").expect("valid regex"),
+        Regex::new(r"^slide\d*$").expect("valid regex"),
+        Regex::new(r"^text\d*$").expect("valid regex"),
+    ]
+});
+
+pub(super) fn strip_binary_artifacts(input: &str) -> String {
+    input
+        .chars()
+        .map(|ch| {
+            if ch == '\u{FFFD}' || (ch.is_control() && !matches!(ch, '\n' | '\r' | '\t')) {
+                ' '
+            } else {
+                ch
+            }
+        })
+        .collect()
+}
+
+pub(super) fn strip_xml_tags(input: &str) -> String {
+    static TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").expect("valid regex"));
+    TAG_REGEX.replace_all(input, " ").to_string()
+}
+
+pub(super) fn cleanup_whitespace(input: &str) -> String {
+    input.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+pub(super) fn filter_noise_tokens(input: &str) -> String {
+    input
+        .split_whitespace()
+        .filter(|token| !is_noise_token(token))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+pub(crate) fn is_noise_token(token: &str) -> bool {
+    let stripped = token.replace(['(', ')'], "");
+    if !stripped.chars().any(|ch| ch.is_ascii_alphabetic()) {
+        return true;
+    }
+    let lowered = stripped.to_lowercase();
+    if NOISE_WORDS.contains(lowered.as_str()) {
+        return true;
+    }
+    NOISE_PATTERNS
+        .iter()
+        .any(|pattern| pattern.is_match(&lowered))
+}
+
+/// ISO 639-3 code (e.g. "eng", "deu") of the dominant language in `text`,
+/// or `None` when the text is empty or the detection isn't reliable.
+pub(super) fn detect_language(text: &str) -> Option<String> {
+    let sample: String = text.chars().take(LANGUAGE_SAMPLE_CHARS).collect();
+    detect(&sample)
+        .filter(|info| info.is_reliable())
+        .map(|info| info.lang().code().to_string())
+}
+
+/// Words of at least `min_chars` characters in the already lowercased `text`.
+fn tokens(text: &str, min_chars: usize) -> impl Iterator<Item = &str> {
+    let min_chars = min_chars.max(MIN_TOKEN_CHARS_FLOOR);
+    TOKEN_REGEX
+        .find_iter(text)
+        .map(|found| found.as_str())
+        .filter(move |token| token.len() >= min_chars)
+}
+
+/// Share of `total` slides or pages that gave usable text, capped at 1.0.
+pub(super) fn text_coverage(with_text: usize, total: Option<usize>) -> Option<f32> {
+    let total = total.filter(|&total| total > 0)?;
+    Some((with_text as f32 / total as f32).min(1.0))
+}
+
+/// Number of words in `text`, counted with the keyword tokenizer so both agree.
+pub(super) fn count_words(text: &str, min_chars: usize) -> u32 {
+    tokens(&text.to_lowercase(), min_chars).count() as u32
+}
+
+/// Estimated reading time, rounded up; 0 only for documents without words.
+pub(super) fn reading_minutes(word_count: u32) -> u32 {
+    word_count.div_ceil(WORDS_PER_MINUTE)
+}
+
+/// The `max_keywords` most characteristic tokens of `text`, scored TF-IDF
+/// style with each slide as a document: frequent tokens rank high, tokens
+/// spread over many slides lower. Tokens on every slide of a deck with at
+/// least `MIN_SLIDES_FOR_BOILERPLATE` slides (footers, the company name) are
+/// dropped outright.
+pub(super) fn derive_keywords(text: &str, slides: &[SlidePreview], settings: &ScanSettings) -> Vec<String> {
+    let mut frequencies: HashMap<String, usize> = HashMap::new();
+    for token in tokens(&text.to_lowercase(), settings.min_token_chars) {
+        *frequencies.entry(token.to_string()).or_insert(0) += 1;
+    }
+    fold_plurals(&mut frequencies);
+
+    // Slide counts per token, under the same plural folding as `frequencies`.
+    let mut slide_frequencies: HashMap<&str, usize> = HashMap::new();
+    for slide in slides {
+        let lowered = slide.text.to_lowercase();
+        let mut seen: HashSet<&str> = HashSet::new();
+        for token in tokens(&lowered, settings.min_token_chars) {
+            let folded = if let Some((known, _)) = frequencies.get_key_value(token) {
+                Some(known.as_str())
+            } else {
+                singular_forms(token)
+                    .into_iter()
+                    .find_map(|singular| frequencies.get_key_value(&singular))
+                    .map(|(known, _)| known.as_str())
+            };
+            if let Some(folded) = folded {
+                seen.insert(folded);
+            }
+        }
+        for token in seen {
+            *slide_frequencies.entry(token).or_insert(0) += 1;
+        }
+    }
+
+    let slide_count = slides.len();
+    let mut items: Vec<(&str, f64)> = frequencies
+        .iter()
+        .filter(|(token, _)| !KEYWORD_STOP_WORDS.contains(token.as_str()))
+        .filter_map(|(token, &count)| {
+            let on_slides = slide_frequencies.get(token.as_str()).copied().unwrap_or(0);
+            if slide_count >= MIN_SLIDES_FOR_BOILERPLATE && on_slides == slide_count {
+                return None;
+            }
+            let idf = 1.0 + ((1 + slide_count) as f64 / (1 + on_slides) as f64).ln();
+            Some((token.as_str(), count as f64 * idf))
+        })
+        .collect();
+    items.sort_by(|(a_token, a_score), (b_token, b_score)| {
+        b_score.total_cmp(a_score).then_with(|| a_token.cmp(b_token))
+    });
+    items
+        .into_iter()
+        .take(settings.max_keywords)
+        .map(|(token, _)| token.to_string())
+        .collect()
+}
+
+/// Title of the first slide/page that has one.
+pub(super) fn first_slide_title(previews: &[SlidePreview]) -> Option<String> {
+    previews.iter().find_map(|preview| preview.title.clone())
+}
+
+/// `title` cleaned up, or `None` for the placeholders tools fill in when the
+/// author never set one ("PowerPoint Presentation", "Microsoft Word - x.docx").
+pub(super) fn meaningful_title(title: &str) -> Option<String> {
+    const PLACEHOLDER_TITLES: [&str; 4] =
+        ["powerpoint presentation", "presentation", "untitled", "slide 1"];
+
+    let title = cleanup_whitespace(&strip_binary_artifacts(title));
+    if title.chars().filter(|ch| ch.is_alphabetic()).count() < 3 {
+        return None;
+    }
+    let lowered = title.to_lowercase();
+    if PLACEHOLDER_TITLES.contains(&lowered.as_str()) || lowered.starts_with("microsoft ") {
+        return None;
+    }
+    Some(title)
+}
+
+/// Longest line `guess_page_title` accepts; a longer first line is body text
+/// (or a page whose text has no line breaks at all).
+const MAX_PAGE_TITLE_CHARS: usize = 120;
+
+/// First line of a PDF page with some real words on it, taken as the page title.
+fn guess_page_title(raw_page: &str) -> Option<String> {
+    raw_page
+        .lines()
+        .map(|line| {
+            cleanup_whitespace(&filter_noise_tokens(&strip_binary_artifacts(&strip_xml_tags(line))))
+        })
+        .find(|line| line.chars().filter(|ch| ch.is_alphabetic()).count() >= 3)
+        .filter(|line| line.chars().count() <= MAX_PAGE_TITLE_CHARS)
+}
+
+/// Merges the count of each plural token into its singular when the text uses
+/// both ("networks" into "network"). A plural whose singular never appears is
+/// left alone, which keeps words like "news" or "series" intact. Possessives
+/// need no folding: the apostrophe already ends the token.
+fn fold_plurals(frequencies: &mut HashMap<String, usize>) {
+    let plurals: Vec<(String, String)> = frequencies
+        .keys()
+        .filter_map(|token| {
+            singular_forms(token)
+                .into_iter()
+                .find(|singular| frequencies.contains_key(singular))
+                .map(|singular| (token.clone(), singular))
+        })
+        .collect();
+    for (plural, singular) in plurals {
+        if let Some(count) = frequencies.remove(&plural) {
+            *frequencies.entry(singular).or_insert(0) += count;
+        }
+    }
+}
+
+/// Possible singulars of a regular English plural, most likely first
+/// ("classes" -> "class"; "caches" -> "cach", "cache"). Empty for short words
+/// and endings that usually aren't plurals.
+fn singular_forms(token: &str) -> Vec<String> {
+    if token.len() <= 4
+        || !token.ends_with('s')
+        || ["ss", "us", "is"].iter().any(|ending| token.ends_with(ending))
+    {
+        return Vec::new();
+    }
+    if let Some(stem) = token.strip_suffix("ies") {
+        return vec![format!("{stem}y")];
+    }
+    let mut forms = Vec::new();
+    if let Some(stem) = token.strip_suffix("es") {
+        if ["s", "x", "z", "ch", "sh"].iter().any(|ending| stem.ends_with(ending)) {
+            forms.push(stem.to_string());
+        }
+    }
+    forms.push(token[..token.len() - 1].to_string());
+    forms
+}
+
+/// Previews and combined text of `raw_pages`, plus how many pages had text.
+/// The count is taken before repeated pages collapse, for `text_coverage`.
+pub(super) fn build_previews_from_pages(raw_pages: &[String]) -> (Vec<SlidePreview>, String, usize) {
+    let mut previews = Vec::new();
+    let mut combined = String::new();
+    let mut text_pages = 0;
+
+    for (index, raw_page) in raw_pages.iter().enumerate() {
+        let stripped_page = strip_xml_tags(raw_page);
+        let sanitized = strip_binary_artifacts(&stripped_page);
+        let filtered = filter_noise_tokens(&sanitized);
+        let cleaned = cleanup_whitespace(&filtered);
+        if !has_meaningful_text(&cleaned) {
+            continue;
+        }
+        text_pages += 1;
+        if repeats_previous_preview(&previews, &cleaned) {
+            continue;
+        }
+        if !combined.is_empty() {
+            combined.push(' ');
+        }
+        combined.push_str(&cleaned);
+        previews.push(SlidePreview {
+            index: index as u32 + 1,
+            title: guess_page_title(raw_page),
+            text: cleaned,
+            outline: None,
+        });
+    }
+
+    (previews, combined, text_pages)
+}
+
+/// True when `text` matches the last kept preview, so runs of identical
+/// slides (section dividers, repeated boilerplate) collapse to their first slide.
+pub(super) fn repeats_previous_preview(previews: &[SlidePreview], text: &str) -> bool {
+    previews
+        .last()
+        .is_some_and(|previous| previous.text.to_lowercase() == text.to_lowercase())
+}
+
+/// True when indexing got usable text out of the file: a meaningful snippet
+/// or at least one keyword.
+pub fn has_extracted_text(item: &SlideIndexItem) -> bool {
+    has_meaningful_text(&item.snippet) || !item.keywords.is_empty()
+}
+
+pub(super) fn has_meaningful_text(text: &str) -> bool {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return false;
+    }
+    if trimmed.len() < 12 {
+        return trimmed.chars().any(|ch| ch.is_ascii_alphanumeric());
+    }
+    if is_gibberish(trimmed) {
+        return false;
+    }
+    true
+}
+
+pub(super) fn is_gibberish(text: &str) -> bool {
+    let compact: String = text.chars().filter(|ch| !ch.is_whitespace()).collect();
+    if compact.len() < 40 {
+        return false;
+    }
+    let alpha = compact
+        .chars()
+        .filter(|ch| ch.is_ascii_alphabetic())
+        .count();
+    if alpha == 0 {
+        return true;
+    }
+    let alpha_ratio = alpha as f64 / compact.len() as f64;
+    if alpha_ratio < 0.35 {
+        return true;
+    }
+    // All caps alone isn't enough: title slides and acronym-heavy content are
+    // legitimately uppercase, but they still read as words.
+    let upper = compact.chars().filter(|ch| ch.is_ascii_uppercase()).count();
+    if alpha > 80 && (upper as f64 / alpha as f64) > 0.9 && !has_word_structure(text) {
+        return true;
+    }
+    let long_tokens = text
+        .split_whitespace()
+        .filter(|token| token.chars().count() > 40)
+        .count();
+    long_tokens > 2
+}
+
+/// True when `text` is made of words: most tokens are short and mostly
+/// letters, and most all-letter tokens have a vowel (acronyms are the
+/// exception in real text, the rule in letter runs decoded from binary data).
+fn has_word_structure(text: &str) -> bool {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    if tokens.len() < 5 {
+        return false;
+    }
+    let wordlike = tokens
+        .iter()
+        .filter(|token| {
+            let length = token.chars().count();
+            let letters = token.chars().filter(|ch| ch.is_alphabetic()).count();
+            length <= 20 && letters * 2 >= length
+        })
+        .count();
+    let letter_tokens: Vec<&&str> = tokens
+        .iter()
+        .filter(|token| token.chars().all(char::is_alphabetic))
+        .collect();
+    let voweled = letter_tokens
+        .iter()
+        .filter(|token| token.chars().any(|ch| "aeiouyAEIOUY".contains(ch)))
+        .count();
+    wordlike * 10 >= tokens.len() * 8 && voweled * 2 >= letter_tokens.len()
+}
+
+/// `text` cut to at most `max_chars` characters. A cut ends at the last whole
+/// word, plus an ellipsis; a single word longer than that (or unspaced
+/// scripts such as Chinese) is cut mid-word.
+pub(super) fn truncate_snippet(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    // One char of the budget goes to the ellipsis.
+    let end = text
+        .char_indices()
+        .nth(max_chars.saturating_sub(1))
+        .map_or(text.len(), |(index, _)| index);
+    let head = &text[..end];
+    let ends_mid_word = !head.ends_with(char::is_whitespace)
+        && !text[end..].starts_with(char::is_whitespace);
+    let kept = match head.rfind(char::is_whitespace) {
+        Some(boundary) if ends_mid_word && boundary > 0 => &head[..boundary],
+        _ => head,
+    };
+    format!("{}…", kept.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippet_of_unspaced_cjk_is_cut_on_a_char_boundary() {
+        let text = "光合作用是植物利用光能的过程".repeat(10);
+        let snippet = truncate_snippet(&text, 10);
+        assert_eq!(snippet, "光合作用是植物利用…");
+        assert_eq!(snippet.chars().count(), 10);
+    }
+
+    #[test]
+    fn snippet_backs_up_to_a_word_boundary() {
+        assert_eq!(truncate_snippet("the quick brown fox jumps", 12), "the quick…");
+        // A cut that falls right after a word keeps it.
+        assert_eq!(truncate_snippet("the quick brown fox jumps", 10), "the quick…");
+        // Multi-byte words are handled the same way.
+        assert_eq!(truncate_snippet("écoute ça résumé détaillé", 14), "écoute ça…");
+    }
+
+    #[test]
+    fn snippet_at_exactly_max_chars_is_kept_whole() {
+        assert_eq!(truncate_snippet("the quick brown", 15), "the quick brown");
+        assert_eq!(truncate_snippet("光合作用", 4), "光合作用");
+    }
+
+    #[test]
+    fn all_caps_slide_is_kept() {
+        let slide = "INTRODUCTION TO NETWORK SECURITY: TCP/IP, DNS AND TLS HANDSHAKES. \
+                     FIREWALL RULES, VPN TUNNELS AND IDS ALERTS FOR THE SOC TEAM";
+        assert!(!is_gibberish(slide));
+        assert!(has_meaningful_text(slide));
+    }
+
+    #[test]
+    fn mojibake_is_rejected() {
+        // Letter runs decoded from binary data: capitals without vowels.
+        let letter_runs = "QXZRT BKPLM WXCVB NMZXC TRWQP LKJHG FDSZX CVBNM QWRTP ZXCVB \
+                           PLKMN BVCXZ HGFDS TRWQX MNBVC XZLKJ GHTRW PQZXM";
+        assert!(is_gibberish(letter_runs));
+        assert!(!has_meaningful_text(letter_runs));
+
+        // UTF-8 read as Latin-1 and binary punctuation: too few letters.
+        let symbols = "Ã©Ã¨Ã¢ Ã´Ã» â€™â€œ Ã¯Ã§ Â¤Â§Â¶ Ã¼Ã¶ Ã±Ã¡ Ã®Ã« â€\u{9d}Â° Ã˜Ã¦ Ã…Ã¸ Â©Â® Â±Âµ";
+        assert!(is_gibberish(symbols));
+
+        // Unbroken runs of characters, as from a compressed stream read as text.
+        let run = "kQ9zPq2LmX8vR4tYwB7nC1sD5fG3hJ6kL0pO9iU8yT7rE6wQ5aS4dF3gH2jK1lZ";
+        let runs = [run; 3].join(" ");
+        assert!(is_gibberish(&runs));
+    }
+
+    #[test]
+    fn normal_deck_yields_keywords() {
+        let texts = [
+            "Photosynthesis overview. Plants turn sunlight into glucose. Biology 101 footer",
+            "Chlorophyll absorbs sunlight in the thylakoid. Biology 101 footer",
+            "The Calvin cycle fixes carbon dioxide into glucose. Biology 101 footer",
+            "Stomata control how much carbon dioxide enters the leaf. Biology 101 footer",
+            "Photosynthesis feeds almost every food chain. Biology 101 footer",
+        ];
+        let slides: Vec<SlidePreview> = texts
+            .iter()
+            .enumerate()
+            .map(|(index, text)| SlidePreview {
+                index: index as u32 + 1,
+                title: None,
+                text: text.to_string(),
+                outline: None,
+            })
+            .collect();
+        // As for a PPTX, the keyword text is exactly the slides' text.
+        let combined = texts.join(" ");
+
+        let keywords = derive_keywords(&combined, &slides, &ScanSettings::default());
+
+        for expected in ["photosynthesis", "glucose", "sunlight", "carbon", "dioxide", "chlorophyll"] {
+            assert!(keywords.iter().any(|keyword| keyword == expected), "{expected} missing from {keywords:?}");
+        }
+        // The footer on every slide is boilerplate; stop words never count.
+        for dropped in ["biology", "footer", "the", "into"] {
+            assert!(!keywords.iter().any(|keyword| keyword == dropped), "{dropped} in {keywords:?}");
+        }
+    }
+
+    #[test]
+    fn repeated_pages_still_count_toward_coverage() {
+        let pages = vec!["Section divider slide".to_string(); 4];
+        let (previews, _, text_pages) = build_previews_from_pages(&pages);
+        assert_eq!(previews.len(), 1);
+        assert_eq!(text_pages, 4);
+        assert_eq!(text_coverage(text_pages, Some(4)), Some(1.0));
+    }
+}
@@ -0,0 +1,259 @@
+//! The external tools the scanner shells out to (pdftotext, pdftoppm,
+//! tesseract): finding them on the PATH or in the usual install folders,
+//! reporting which are missing, and running them with a timeout.
+
+use std::{
+    env,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+
+use crate::{error::{AppError, Result}, models::{ToolStatus, ToolWarning}, tool_permits};
+
+pub(super) struct CommandPaths {
+    pub(super) pdftoppm: Option<PathBuf>,
+    pub(super) tesseract: Option<PathBuf>,
+    pub(super) pdftotext: Option<PathBuf>,
+}
+
+pub(super) struct CommandStatus {
+    pub(super) paths: CommandPaths,
+    missing: Vec<&'static str>,
+}
+
+pub(super) static COMMAND_STATUS: Lazy<CommandStatus> = Lazy::new(resolve_command_status);
+
+/// Resolved `pdftoppm` binary, shared with thumbnail rendering.
+pub(crate) fn pdftoppm_path() -> Option<&'static Path> {
+    COMMAND_STATUS.paths.pdftoppm.as_deref()
+}
+
+/// Runs an external tool, returning its stdout.
+///
+/// Spawn failures, exceeding `timeout` (the process is killed) and non-zero
+/// exits map to the matching `AppError::Command*` variant. Waits for a
+/// `tool_permits` slot first, so the timeout only counts the tool's own run.
+pub(crate) fn run_tool(tool: &'static str, command: &mut Command, timeout: Duration) -> Result<Vec<u8>> {
+    let _permit = tool_permits::acquire();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|source| AppError::CommandSpawn { tool, source })?;
+
+    // Drain stdout on a separate thread so a chatty tool can't block on a full pipe.
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = stdout.read_to_end(&mut buffer);
+        buffer
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = reader.join();
+            return Err(AppError::CommandTimeout { tool });
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    let output = reader.join().unwrap_or_default();
+    if !status.success() {
+        return Err(AppError::CommandFailed { tool, status });
+    }
+    Ok(output)
+}
+
+fn resolve_command_status() -> CommandStatus {
+    let pdftoppm = resolve_command("pdftoppm");
+    let tesseract = resolve_command("tesseract");
+    let pdftotext = resolve_command("pdftotext");
+
+    let mut missing: Vec<&'static str> = Vec::new();
+    if pdftoppm.is_none() {
+        missing.push("pdftoppm");
+    }
+    if tesseract.is_none() {
+        missing.push("tesseract");
+    }
+    if pdftotext.is_none() {
+        missing.push("pdftotext");
+    }
+
+    CommandStatus {
+        paths: CommandPaths {
+            pdftoppm,
+            tesseract,
+            pdftotext,
+        },
+        missing,
+    }
+}
+
+pub(crate) fn resolve_command(command: &str) -> Option<PathBuf> {
+    let mut search_dirs: Vec<PathBuf> = Vec::new();
+    if let Some(path_var) = env::var_os("PATH") {
+        search_dirs.extend(env::split_paths(&path_var));
+    }
+
+    search_dirs.extend(default_command_dirs());
+
+    #[allow(unused_mut)]
+    let mut candidate_names = vec![command.to_string()];
+    #[cfg(target_os = "windows")]
+    {
+        if !command.ends_with(".exe") {
+            candidate_names.push(format!("{command}.exe"));
+        }
+    }
+
+    for dir in search_dirs {
+        for name in &candidate_names {
+            let candidate = dir.join(name);
+            if is_executable_path(&candidate) {
+                return Some(candidate);
+            }
+        }
+    }
+
+    None
+}
+
+fn default_command_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+
+    #[cfg(target_os = "macos")]
+    {
+        dirs.extend(
+            [
+                "/opt/homebrew/bin",
+                "/usr/local/bin",
+                "/usr/bin",
+                "/bin",
+                "/opt/local/bin",
+            ]
+            .into_iter()
+            .map(PathBuf::from),
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        dirs.extend(
+            ["/usr/local/bin", "/usr/bin", "/bin", "/snap/bin"]
+                .into_iter()
+                .map(PathBuf::from),
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        dirs.extend(
+            [
+                r"C:\\Program Files\\Tesseract-OCR",
+                r"C:\\Program Files (x86)\\Tesseract-OCR",
+                r"C:\\Program Files\\poppler\\bin",
+                r"C:\\Program Files (x86)\\poppler\\bin",
+            ]
+            .into_iter()
+            .map(PathBuf::from),
+        );
+    }
+
+    dirs
+}
+
+fn is_executable_path(path: &Path) -> bool {
+    match fs::metadata(path) {
+        Ok(metadata) => {
+            if !metadata.is_file() {
+                return false;
+            }
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                metadata.permissions().mode() & 0o111 != 0
+            }
+            #[cfg(not(unix))]
+            {
+                true
+            }
+        }
+        Err(_) => false,
+    }
+}
+
+#[allow(dead_code)]
+pub fn is_ocr_available() -> bool {
+    COMMAND_STATUS.paths.pdftoppm.is_some() && COMMAND_STATUS.paths.tesseract.is_some()
+}
+
+/// Human-readable form of the missing-tools warning, for the log.
+pub fn ocr_status_message() -> Option<String> {
+    if COMMAND_STATUS.missing.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "PDF extraction tools missing: {}. Install them to enable full PDF scanning ({}).",
+            COMMAND_STATUS.missing.join(", "),
+            install_hint(&COMMAND_STATUS.missing)
+        ))
+    }
+}
+
+pub fn tool_status() -> ToolStatus {
+    let paths = &COMMAND_STATUS.paths;
+    let mut warnings = Vec::new();
+    if !COMMAND_STATUS.missing.is_empty() {
+        warnings.push(ToolWarning {
+            code: "tools_missing".to_string(),
+            tools: COMMAND_STATUS.missing.iter().map(|tool| tool.to_string()).collect(),
+            hint: install_hint(&COMMAND_STATUS.missing),
+        });
+    }
+    ToolStatus {
+        pdftoppm: paths.pdftoppm.is_some(),
+        pdftotext: paths.pdftotext.is_some(),
+        tesseract: paths.tesseract.is_some(),
+        ocr_available: is_ocr_available(),
+        warnings,
+    }
+}
+
+/// How to install the packages providing `missing` on this platform.
+fn install_hint(missing: &[&str]) -> String {
+    let needs_poppler = missing.iter().any(|tool| *tool == "pdftoppm" || *tool == "pdftotext");
+    let needs_tesseract = missing.contains(&"tesseract");
+    let packages = |poppler: &str, tesseract: &str| {
+        [(needs_poppler, poppler), (needs_tesseract, tesseract)]
+            .iter()
+            .filter(|(needed, _)| *needed)
+            .map(|(_, package)| *package)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    if cfg!(target_os = "macos") {
+        format!("brew install {}", packages("poppler", "tesseract"))
+    } else if cfg!(windows) {
+        format!(
+            "install {} and add {} to PATH",
+            packages("Poppler", "Tesseract").replace(' ', " and "),
+            if missing.len() == 1 { "it" } else { "them" }
+        )
+    } else {
+        format!("sudo apt install {}", packages("poppler-utils", "tesseract-ocr"))
+    }
+}
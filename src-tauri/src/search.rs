@@ -0,0 +1,575 @@
+//! Query parsing and matching: turns what the user typed into a
+//! `SearchPattern` and checks items, slides and corpora against it. Ranking,
+//! match snippets and "why did this match" explanations live here too.
+
+use std::{cmp::Reverse, path::Path};
+
+use once_cell::sync::Lazy;
+use regex::{escape, Regex, RegexBuilder};
+
+use crate::models::{MatchSnippet, SearchFilters, SlideIndexItem, TermMatch};
+
+/// Shortest search term that fuzzy mode tolerates typos in.
+const MIN_FUZZY_TERM_CHARS: usize = 4;
+
+static SEARCH_TOKEN_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""([^"]+)"|([^\s]+)"#).expect("valid regex"));
+
+/// Checks one part of a `SearchPattern` against a lowercased text.
+pub type QueryPartMatcher<'a> = Box<dyn Fn(&str) -> bool + 'a>;
+
+#[derive(Debug)]
+pub struct SearchPattern {
+    terms: Vec<String>,
+    phrases: Vec<String>,
+    wildcards: Vec<(String, Regex)>, // (query token, compiled form)
+    whole_word_terms: Option<Vec<Regex>>, // replaces substring checks for `terms` when set
+    fuzzy: bool, // terms also match corpus tokens a few typos away
+    extensions: Vec<String>, // from `ext:pdf`, `kind:pdf` or `*.pdf`; any one must match
+    min_slide_hits: Option<usize>, // from `min_slide_hits:3`; slides that must match on their own
+    is_empty: bool,
+    filters: SearchFilters,
+}
+
+impl SearchPattern {
+    pub fn new(raw: &str) -> Self {
+        let mut terms = Vec::new();
+        let mut phrases = Vec::new();
+        let mut wildcards = Vec::new();
+        let mut extensions = Vec::new();
+        let mut min_slide_hits = None;
+
+        for capture in SEARCH_TOKEN_REGEX.captures_iter(raw) {
+            if let Some(phrase) = capture.get(1) {
+                let value = phrase.as_str().trim().to_lowercase();
+                if !value.is_empty() {
+                    phrases.push(value);
+                }
+            } else if let Some(token) = capture.get(2) {
+                let value = token.as_str().trim();
+                if value.is_empty() {
+                    continue;
+                }
+                // Checked before wildcards, so `*.pdf` restricts the file type
+                // instead of matching text.
+                if let Some(extension) = extension_filter(value) {
+                    extensions.push(extension);
+                } else if let Some(hits) = min_slide_hits_filter(value) {
+                    min_slide_hits = Some(hits);
+                } else if value.contains('*') || value.contains('?') {
+                    if let Some(regex) = wildcard_to_regex(value) {
+                        wildcards.push((value.to_string(), regex));
+                    }
+                } else {
+                    terms.push(value.to_lowercase());
+                }
+            }
+        }
+
+        let is_empty = terms.is_empty() && phrases.is_empty() && wildcards.is_empty();
+        Self {
+            terms,
+            phrases,
+            wildcards,
+            whole_word_terms: None,
+            fuzzy: false,
+            extensions,
+            min_slide_hits,
+            is_empty,
+            filters: SearchFilters::default(),
+        }
+    }
+
+    /// Makes plain terms match whole words only, so "cat" no longer matches "concatenate".
+    pub fn with_whole_word(mut self, whole_word: bool) -> Self {
+        self.whole_word_terms = whole_word.then(|| {
+            self.terms
+                .iter()
+                .map(|term| whole_word_regex(term))
+                .collect()
+        });
+        self
+    }
+
+    /// Lets a term also match a word within a small edit distance ("netwrok" → "network").
+    pub fn with_fuzzy(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    pub fn is_fuzzy(&self) -> bool {
+        self.fuzzy
+    }
+
+    /// Plain (non-phrase, non-wildcard) terms, lowercased.
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+
+    pub fn with_filters(mut self, filters: SearchFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Every phrase (quoted), plain term and wildcard as typed, each with a
+    /// check of whether it alone occurs in a lowercased text.
+    pub fn parts(&self) -> Vec<(String, QueryPartMatcher<'_>)> {
+        let mut parts: Vec<(String, QueryPartMatcher<'_>)> = Vec::new();
+        for phrase in &self.phrases {
+            parts.push((format!("\"{phrase}\""), Box::new(|text| text.contains(phrase.as_str()))));
+        }
+        for (index, term) in self.terms.iter().enumerate() {
+            parts.push((term.clone(), Box::new(move |text| term_matches(text, self, index))));
+        }
+        for (token, wildcard) in &self.wildcards {
+            parts.push((token.clone(), Box::new(|text| wildcard.is_match(text))));
+        }
+        parts
+    }
+
+    fn matches_extension(&self, item: &SlideIndexItem) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+        Path::new(&item.path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|wanted| extension.eq_ignore_ascii_case(wanted))
+            })
+    }
+}
+
+/// The slide count a `min_slide_hits:N` query token asks for.
+fn min_slide_hits_filter(token: &str) -> Option<usize> {
+    let (key, value) = token.split_once(':')?;
+    key.eq_ignore_ascii_case("min_slide_hits")
+        .then(|| value.parse().ok())
+        .flatten()
+}
+
+/// The extension a query token restricts results to: `ext:pptx`, `kind:pdf`
+/// or `*.pdf` (lowercased, without the dot).
+fn extension_filter(token: &str) -> Option<String> {
+    let lowered = token.to_lowercase();
+    let extension = ["ext:", "kind:", "*."]
+        .iter()
+        .find_map(|prefix| lowered.strip_prefix(prefix))?
+        .trim_start_matches('.');
+    (!extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric()))
+        .then(|| extension.to_string())
+}
+
+/// `\bterm\b`, with the boundary left off a side that starts/ends with a
+/// non-word character (e.g. "c++"), where `\b` could never match.
+fn whole_word_regex(term: &str) -> Regex {
+    let is_word_char = |ch: char| ch.is_alphanumeric() || ch == '_';
+    let start = if term.starts_with(is_word_char) { r"\b" } else { "" };
+    let end = if term.ends_with(is_word_char) { r"\b" } else { "" };
+    Regex::new(&format!("{start}{}{end}", escape(term))).expect("escaped term is a valid regex")
+}
+
+/// Typos tolerated for a fuzzy term: none below `MIN_FUZZY_TERM_CHARS`
+/// (too many short words are one edit apart), 1 for short terms, 2 otherwise.
+fn max_typos(term: &str) -> usize {
+    match term.chars().count() {
+        count if count < MIN_FUZZY_TERM_CHARS => 0,
+        count if count <= 5 => 1,
+        _ => 2,
+    }
+}
+
+/// True when `token` contains `term` or is within `max_typos(term)` edits of it.
+pub fn is_similar_token(token: &str, term: &str) -> bool {
+    if token.contains(term) {
+        return true;
+    }
+    let max = max_typos(term);
+    max > 0 && within_edit_distance(token, term, max)
+}
+
+fn has_similar_token(text: &str, term: &str) -> bool {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .any(|token| !token.is_empty() && is_similar_token(token, term))
+}
+
+/// Levenshtein distance(a, b) <= max, computed over chars with an early exit
+/// once every cell in a row exceeds `max`.
+fn within_edit_distance(a: &str, b: &str, max: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return false;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().all(|&distance| distance > max) {
+            return false;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()] <= max
+}
+
+fn wildcard_to_regex(pattern: &str) -> Option<Regex> {
+    let mut converted = String::new();
+    for ch in pattern.chars() {
+        match ch {
+            '*' => converted.push_str(".*"),
+            '?' => converted.push('.'),
+            _ => converted.push_str(&escape(&ch.to_string())),
+        }
+    }
+    if converted.is_empty() {
+        return None;
+    }
+    let final_pattern = format!(".*{}.*", converted);
+    RegexBuilder::new(&final_pattern)
+        .case_insensitive(true)
+        .dot_matches_new_line(true)
+        .build()
+        .ok()
+}
+
+pub fn build_search_corpus(item: &SlideIndexItem) -> String {
+    let parts: Vec<String> = search_fields(item).into_iter().map(|(_, text)| text).collect();
+    parts.join(" ")
+}
+
+/// The lowercased searchable text of `item`, labelled by where it came from
+/// (`name`, `slide:4`, `tags`...). Joined, these make up the search corpus.
+fn search_fields(item: &SlideIndexItem) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    fields.push(("name".to_string(), item.name.to_lowercase()));
+    if let Some(display_name) = &item.display_name {
+        fields.push(("displayName".to_string(), display_name.to_lowercase()));
+    }
+    if let Some(presentation_title) = &item.presentation_title {
+        fields.push(("title".to_string(), presentation_title.to_lowercase()));
+    }
+    fields.push(("path".to_string(), item.path.to_lowercase()));
+    if !item.snippet.is_empty() {
+        fields.push(("snippet".to_string(), item.snippet.to_lowercase()));
+    }
+    fields.extend(
+        item.slides
+            .iter()
+            .map(|slide| (format!("slide:{}", slide.index), slide.text.to_lowercase())),
+    );
+    if !item.keywords.is_empty() {
+        fields.push(("keywords".to_string(), item.keywords.join(" ").to_lowercase()));
+    }
+    if !item.links.is_empty() {
+        fields.push(("links".to_string(), item.links.join(" ").to_lowercase()));
+    }
+    // Tags are indexed both bare and as `tag:<name>` so either form can be searched.
+    for tag in &item.tags {
+        fields.push(("tags".to_string(), format!("{tag} tag:{tag}")));
+    }
+    fields
+}
+
+/// For each phrase, term and wildcard of `pattern`, the fields of `item` it
+/// matched in. Answers "why did this match"; only computed on request.
+pub fn explain_match(item: &SlideIndexItem, pattern: &SearchPattern) -> Vec<TermMatch> {
+    let fields = search_fields(item);
+    let matched_in = |matches: &dyn Fn(&str) -> bool| {
+        let mut names: Vec<String> = Vec::new();
+        for (name, text) in &fields {
+            // Several tags share one label.
+            if matches(text) && !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    };
+
+    pattern
+        .parts()
+        .into_iter()
+        .map(|(term, matches)| TermMatch {
+            term,
+            fields: matched_in(&*matches),
+        })
+        .collect()
+}
+
+pub fn matches_query(item: &SlideIndexItem, pattern: &SearchPattern) -> bool {
+    if !matches_filters(item, &pattern.filters) || !pattern.matches_extension(item) {
+        return false;
+    }
+    if pattern.is_empty {
+        return true;
+    }
+    let corpus = build_search_corpus(item);
+    text_matches(&corpus, pattern) && has_min_slide_hits(item, pattern)
+}
+
+/// `matches_query` against a corpus the caller already built with `build_search_corpus`.
+pub fn matches_query_in(item: &SlideIndexItem, corpus: &str, pattern: &SearchPattern) -> bool {
+    if !matches_filters(item, &pattern.filters) || !pattern.matches_extension(item) {
+        return false;
+    }
+    pattern.is_empty || (text_matches(corpus, pattern) && has_min_slide_hits(item, pattern))
+}
+
+/// True unless the query has `min_slide_hits:N` and fewer than N slides match
+/// the whole pattern on their own, i.e. the deck only mentions it in passing.
+fn has_min_slide_hits(item: &SlideIndexItem, pattern: &SearchPattern) -> bool {
+    let Some(required) = pattern.min_slide_hits else {
+        return true;
+    };
+    item.slides
+        .iter()
+        .filter(|slide| text_matches(&slide.text.to_lowercase(), pattern))
+        .take(required)
+        .count()
+        >= required
+}
+
+fn matches_filters(item: &SlideIndexItem, filters: &SearchFilters) -> bool {
+    if filters.updated_after.is_some_and(|after| item.updated_at < after) {
+        return false;
+    }
+    if filters.updated_before.is_some_and(|before| item.updated_at >= before) {
+        return false;
+    }
+    if let Some(max) = filters.max_text_coverage {
+        if !item.text_coverage.is_some_and(|coverage| coverage <= max) {
+            return false;
+        }
+    }
+    // A cleared language field comes through as "" and means no filter.
+    if let Some(language) = filters.language.as_deref().map(str::trim).filter(|language| !language.is_empty()) {
+        if !item
+            .language
+            .as_deref()
+            .is_some_and(|detected| detected.eq_ignore_ascii_case(language))
+        {
+            return false;
+        }
+    }
+    match item.slide_count {
+        Some(count) => {
+            if filters.min_slides.is_some_and(|min| count < min) {
+                return false;
+            }
+            if filters.max_slides.is_some_and(|max| count > max) {
+                return false;
+            }
+        }
+        None => {
+            if filters.min_slides.unwrap_or(0) > 0 {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// At most this many `match_snippets` per hit...
+const MAX_MATCH_SNIPPETS: usize = 3;
+/// ...holding at most this many characters of text between them.
+const MAX_MATCH_SNIPPET_TOTAL_CHARS: usize = 600;
+/// Text kept on each side of the occurrence a snippet is centred on.
+const MATCH_SNIPPET_CONTEXT_BYTES: usize = 70;
+
+/// Up to `MAX_MATCH_SNIPPETS` excerpts of the matched slides, each centred on a
+/// different occurrence of a query term or phrase, with every occurrence inside
+/// it highlighted. Wildcard and fuzzy matches aren't located, so a slide matched
+/// only through those gets no snippet.
+pub fn match_snippets(
+    item: &SlideIndexItem,
+    matched_slides: &[u32],
+    pattern: &SearchPattern,
+) -> Vec<MatchSnippet> {
+    let mut needles: Vec<&str> =
+        pattern.phrases.iter().chain(&pattern.terms).map(String::as_str).collect();
+    if needles.is_empty() {
+        return Vec::new();
+    }
+    // Longest first, so a phrase wins over a term inside it.
+    needles.sort_by_key(|needle| Reverse(needle.len()));
+    let alternation: Vec<String> = needles.iter().map(|needle| escape(needle)).collect();
+    let Ok(occurrence_regex) = RegexBuilder::new(&alternation.join("|"))
+        .case_insensitive(true)
+        .build()
+    else {
+        return Vec::new();
+    };
+
+    let mut snippets = Vec::new();
+    let mut total_chars = 0;
+    for slide in item.slides.iter().filter(|slide| matched_slides.contains(&slide.index)) {
+        let text = slide.text.as_str();
+        let mut covered_until = 0;
+        for occurrence in occurrence_regex.find_iter(text) {
+            if occurrence.start() < covered_until {
+                continue;
+            }
+            if snippets.len() == MAX_MATCH_SNIPPETS || total_chars >= MAX_MATCH_SNIPPET_TOTAL_CHARS {
+                return snippets;
+            }
+            let (start, end) = snippet_window(text, occurrence.start(), occurrence.end());
+            covered_until = end;
+
+            let prefix = if start > 0 { "…" } else { "" };
+            let suffix = if end < text.len() { "…" } else { "" };
+            let offset = prefix.chars().count();
+            let highlights = occurrence_regex
+                .find_iter(&text[start..end])
+                .map(|found| {
+                    let from = offset + text[start..start + found.start()].chars().count();
+                    (from, from + found.as_str().chars().count())
+                })
+                .collect();
+            let excerpt = format!("{prefix}{}{suffix}", &text[start..end]);
+            total_chars += excerpt.chars().count();
+            snippets.push(MatchSnippet {
+                slide_index: slide.index,
+                text: excerpt,
+                highlights,
+            });
+        }
+    }
+    snippets
+}
+
+/// Byte range of `text` around `[start, end)`, widened by the snippet context
+/// and then pulled in to whole words (slide text is single-spaced).
+fn snippet_window(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let mut from = start.saturating_sub(MATCH_SNIPPET_CONTEXT_BYTES);
+    while !text.is_char_boundary(from) {
+        from -= 1;
+    }
+    let mut to = (end + MATCH_SNIPPET_CONTEXT_BYTES).min(text.len());
+    while !text.is_char_boundary(to) {
+        to += 1;
+    }
+    if from > 0 {
+        if let Some(space) = text[from..start].find(' ') {
+            from += space + 1;
+        }
+    }
+    if to < text.len() {
+        if let Some(space) = text[end..to].rfind(' ') {
+            to = end + space;
+        }
+    }
+    (from, to)
+}
+
+/// Matched slides whose title also matches count this much towards `relevance`.
+const TITLE_MATCH_WEIGHT: u32 = 3;
+
+/// Ranking score for `SortBy::Relevance`: one point per matched slide, or
+/// `TITLE_MATCH_WEIGHT` when the slide's title matches the query by itself.
+pub fn relevance(item: &SlideIndexItem, matched_slides: &[u32], pattern: &SearchPattern) -> u32 {
+    item.slides
+        .iter()
+        .filter(|slide| matched_slides.contains(&slide.index))
+        .map(|slide| {
+            let title_matches = slide
+                .title
+                .as_ref()
+                .is_some_and(|title| text_matches(&title.to_lowercase(), pattern));
+            if title_matches {
+                TITLE_MATCH_WEIGHT
+            } else {
+                1
+            }
+        })
+        .sum()
+}
+
+/// Returns the indices of the slides whose own text satisfies the whole pattern.
+pub fn matched_slide_indices(item: &SlideIndexItem, pattern: &SearchPattern) -> Vec<u32> {
+    if pattern.is_empty {
+        return Vec::new();
+    }
+    item.slides
+        .iter()
+        .filter(|slide| text_matches(&slide.text.to_lowercase(), pattern))
+        .map(|slide| slide.index)
+        .collect()
+}
+
+// `text` must already be lowercased.
+fn text_matches(text: &str, pattern: &SearchPattern) -> bool {
+    for phrase in &pattern.phrases {
+        if !text.contains(phrase) {
+            return false;
+        }
+    }
+    for index in 0..pattern.terms.len() {
+        if !term_matches(text, pattern, index) {
+            return false;
+        }
+    }
+    for (_, wildcard) in &pattern.wildcards {
+        if !wildcard.is_match(text) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Whether plain term `index` of `pattern` occurs in the lowercased `text`.
+fn term_matches(text: &str, pattern: &SearchPattern, index: usize) -> bool {
+    let term = &pattern.terms[index];
+    let exact = match &pattern.whole_word_terms {
+        Some(regexes) => regexes[index].is_match(text),
+        None => text.contains(term.as_str()),
+    };
+    exact || (pattern.fuzzy && has_similar_token(text, term))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(language: Option<&str>) -> SlideIndexItem {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "path": "/decks/biology.pptx",
+            "name": "biology.pptx",
+            "kind": "pptx",
+            "slideCount": 3,
+            "snippet": "photosynthesis",
+            "updatedAt": 1,
+            "language": language,
+        }))
+        .unwrap()
+    }
+
+    fn language_filter(language: &str) -> SearchFilters {
+        SearchFilters {
+            language: Some(language.to_string()),
+            ..SearchFilters::default()
+        }
+    }
+
+    #[test]
+    fn blank_language_filter_is_ignored() {
+        for blank in ["", "   "] {
+            assert!(matches_filters(&item(Some("eng")), &language_filter(blank)));
+            assert!(matches_filters(&item(None), &language_filter(blank)));
+        }
+    }
+
+    #[test]
+    fn language_filter_is_trimmed_and_case_insensitive() {
+        assert!(matches_filters(&item(Some("eng")), &language_filter(" ENG ")));
+        assert!(!matches_filters(&item(Some("deu")), &language_filter("eng")));
+        assert!(!matches_filters(&item(None), &language_filter("eng")));
+    }
+}
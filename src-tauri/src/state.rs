@@ -1,24 +1,97 @@
 use std::{
     fs,
-    path::{Path, PathBuf},
-    sync::Mutex,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use tauri::{AppHandle, Manager};
+use tauri::AppHandle;
 
 use crate::{
     error::{AppError, Result},
-    models::{AppState, ScanProgressPayload, ScanSummary, SearchResponse, SlideIndexItem},
-    scanner::{
-        current_timestamp, matches_query, ocr_status_message, scan_directories, ScanOutcome,
-        SearchPattern,
-    },
+    index::{check_index_health, load_state, path_within, persist_state, CorpusCache},
+    inverted_index::InvertedIndex,
+    keyword_index::KeywordIndex,
+    models::{AppState, OcrMode, ScanSettings, SlideIndexItem, SlideKind},
+    ocr_cache,
+    scanner::{current_timestamp, deck_exists, index_path_with_language, launchable_path},
+    text_cache,
 };
 
+mod data_dir;
+mod events;
+mod library;
+mod previews;
+mod scans;
+mod searches;
+mod user_fields;
+
+pub use data_dir::DATA_DIR_ENV;
+
+use data_dir::resolve_data_dir;
+use events::ProgressThrottle;
+
+/// Flag file in the data directory that opens the index read-only, for a
+/// shared index that an admin maintains and everyone else only searches.
+const READ_ONLY_FLAG: &str = "read-only";
+
+/// How long `shutdown` waits for a stopped scan to wrap up before saving anyway.
+const SHUTDOWN_SCAN_WAIT: Duration = Duration::from_secs(10);
+
+/// Quiet time after the last small edit (tag, favorite, open count, recent
+/// search) before the index is written, so a burst of edits is saved once.
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(750);
+
+/// Starts the thread behind `StateManager::schedule_save`: it sleeps until a
+/// save is requested, waits for `SAVE_DEBOUNCE` without further requests, then
+/// writes the index if nothing (like `flush`) has written it meanwhile.
+fn spawn_debounced_saver(state: Arc<Mutex<AppState>>, storage_path: PathBuf, pending: Arc<AtomicBool>) -> Sender<()> {
+    let (requests, received) = mpsc::channel::<()>();
+    thread::spawn(move || {
+        while received.recv().is_ok() {
+            // Each further request restarts the wait; a closed channel saves straight away.
+            while received.recv_timeout(SAVE_DEBOUNCE).is_ok() {}
+            if pending.swap(false, Ordering::SeqCst) {
+                let state = state.lock().expect("state poisoned");
+                if let Err(error) = persist_state(&storage_path, &state) {
+                    println!("⚠️  Failed to save index: {}", error);
+                }
+            }
+        }
+    });
+    requests
+}
+
 pub struct StateManager {
-    state: Mutex<AppState>,
+    state: Arc<Mutex<AppState>>,
     storage_path: PathBuf,
-    app_handle: AppHandle,
+    default_data_dir: PathBuf, // where `DATA_DIR_POINTER` lives
+    // Set by `schedule_save`, cleared by whichever save writes the edit out.
+    save_pending: Arc<AtomicBool>,
+    save_requests: Sender<()>,
+    thumbnail_dir: PathBuf,
+    render_dir: PathBuf,
+    app_handle: Option<AppHandle>, // None only in unit tests, where events go nowhere
+    // Lazily rebuilt after any change to `state.items`; lock after `state`.
+    keyword_index: Mutex<Option<KeywordIndex>>,
+    // Same rules as `keyword_index`; lock order: corpus_cache, then inverted_index.
+    corpus_cache: Mutex<Option<CorpusCache>>,
+    inverted_index: Mutex<Option<InvertedIndex>>,
+    progress_throttle: Mutex<ProgressThrottle>,
+    // Scans release `state` while walking, so two at once would interleave their writes.
+    scan_running: AtomicBool,
+    // Asks the running scan to stop before its next file; cleared when a scan starts.
+    stop_requested: AtomicBool,
+    // Fixed at startup; every change to the index is refused with `AppError::ReadOnly`.
+    read_only: bool,
+    // Set once `set_data_dir` has moved the index elsewhere: edits until the
+    // restart would only reach the old folder, so they're refused.
+    data_dir_moved: AtomicBool,
 }
 
 impl StateManager {
@@ -29,306 +102,285 @@ impl StateManager {
             .or_else(|| resolver.app_config_dir())
             .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
 
-        let data_dir = base_dir.join("slides-indexer");
-        fs::create_dir_all(&data_dir)?;
+        let default_data_dir = base_dir.join("slides-indexer");
+        fs::create_dir_all(&default_data_dir)?;
+        let data_dir = resolve_data_dir(&default_data_dir)?;
 
         let storage_path = data_dir.join("index.json");
+        ocr_cache::set_dir(data_dir.join("ocr"));
+        text_cache::set_dir(data_dir.join("text"));
+        let read_only = data_dir.join(READ_ONLY_FLAG).exists();
         let state = if storage_path.exists() {
-            load_state(&storage_path)?
+            let mut loaded = load_state(&storage_path)?;
+            // Read-only, broken items are still dropped for this session; the file is left alone.
+            let health = check_index_health(&mut loaded, true);
+            if !health.issues.is_empty() {
+                println!(
+                    "🩺 Index health: {} issues in {} items ({} repaired)",
+                    health.issues.len(),
+                    health.checked,
+                    health.repaired
+                );
+            }
+            if health.repaired > 0 && !read_only {
+                persist_state(&storage_path, &loaded)?;
+            }
+            loaded
         } else {
             let initial = AppState::default();
-            persist_state(&storage_path, &initial)?;
+            if !read_only {
+                persist_state(&storage_path, &initial)?;
+            }
             initial
         };
+        // Rendered images can't go next to a read-only index.
+        let image_dir = if read_only {
+            println!("🔒 Index at {} is read-only", storage_path.display());
+            std::env::temp_dir().join("slides-indexer")
+        } else {
+            data_dir
+        };
+
+        let state = Arc::new(Mutex::new(state));
+        let save_pending = Arc::new(AtomicBool::new(false));
+        let save_requests = spawn_debounced_saver(Arc::clone(&state), storage_path.clone(), Arc::clone(&save_pending));
 
         Ok(Self {
-            state: Mutex::new(state),
+            state,
             storage_path,
-            app_handle: handle.clone(),
+            default_data_dir,
+            save_pending,
+            save_requests,
+            thumbnail_dir: image_dir.join("thumbnails"),
+            render_dir: image_dir.join("renders"),
+            app_handle: Some(handle.clone()),
+            keyword_index: Mutex::new(None),
+            corpus_cache: Mutex::new(None),
+            inverted_index: Mutex::new(None),
+            progress_throttle: Mutex::new(ProgressThrottle::default()),
+            scan_running: AtomicBool::new(false),
+            stop_requested: AtomicBool::new(false),
+            read_only,
+            data_dir_moved: AtomicBool::new(false),
         })
     }
 
     pub fn get_state(&self) -> AppState {
-        let mut state = self.state.lock().expect("state poisoned").clone();
+        let state = self.state.lock().expect("state poisoned").clone();
         println!("get_state returning directories: {:?}", state.directories);
-        if let Some(message) = ocr_status_message() {
-            if !state.warnings.iter().any(|existing| existing == &message) {
-                state.warnings.push(message);
-            }
-        }
         state
     }
 
-    pub fn rescan(&self) -> Result<ScanSummary> {
-        let (directories, existing_snapshot) = {
-            let state = self.state.lock().expect("state poisoned");
-            (state.directories.clone(), state.items.clone())
-        };
-        if directories.is_empty() {
-            let mut state = self.state.lock().expect("state poisoned");
-            state.items.clear();
-            state.last_indexed_at = Some(current_timestamp());
-            let mut summary = ScanSummary {
-                indexed: 0,
-                scanned: None,
-                cached: None,
-                errors: Vec::new(),
-                last_indexed_at: state.last_indexed_at,
-            };
-            if let Some(message) = ocr_status_message() {
-                summary.errors.push(message);
-            }
-            state.warnings = summary.errors.clone();
-            let persist_result = persist_state(&self.storage_path, &state);
-            self.emit_scan_progress(None, None, None);
-            persist_result?;
-            return Ok(summary);
-        }
-
-        // Create callback that saves state after each indexed file
-        let storage_path = self.storage_path.clone();
-        let state_mutex = &self.state;
-        
-        let mut progress_cb = |path: &str, status: &str, debug: Option<&str>| self.emit_scan_progress(Some(path), Some(status), debug);
-        
-        let mut on_item_indexed = |item: crate::models::SlideIndexItem| {
-            let mut state = state_mutex.lock().expect("state poisoned");
-            // Add or update the item
-            if let Some(pos) = state.items.iter().position(|i| i.path == item.path) {
-                state.items[pos] = item;
-            } else {
-                state.items.push(item);
-            }
-            state.last_indexed_at = Some(current_timestamp());
-            // Save immediately after each file
-            if let Err(e) = persist_state(&storage_path, &state) {
-                println!("⚠️  Failed to save cache after indexing file: {}", e);
-            } else {
-                println!("💾 Cache saved (items: {})", state.items.len());
-            }
-        };
-        
-        let outcome = scan_directories(&directories, &existing_snapshot, &mut progress_cb, &mut on_item_indexed);
-        let ScanOutcome { items, errors, scanned_count, cached_count } = match outcome {
-            Ok(result) => result,
-            Err(error) => {
-                self.emit_scan_progress(None, None, None);
-                return Err(error);
-            }
-        };
+    pub fn get_settings(&self) -> ScanSettings {
+        self.state.lock().expect("state poisoned").settings.clone()
+    }
 
+    pub fn update_settings(&self, settings: ScanSettings) -> Result<ScanSettings> {
+        self.ensure_writable()?;
         let mut state = self.state.lock().expect("state poisoned");
-        state.items = items;
-        state.items.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-        state.last_indexed_at = Some(current_timestamp());
-
-        let mut summary = ScanSummary {
-            indexed: state.items.len(),
-            scanned: Some(scanned_count),
-            cached: Some(cached_count),
-            errors,
-            last_indexed_at: state.last_indexed_at,
-        };
-        if let Some(message) = ocr_status_message() {
-            if !summary.errors.iter().any(|existing| existing == &message) {
-                summary.errors.push(message);
-            }
-        }
-
-        state.warnings = summary.errors.clone();
-        let persist_result = persist_state(&self.storage_path, &state);
-        self.emit_scan_progress(None, None, None);
-        persist_result?;
+        state.settings = settings;
+        persist_state(&self.storage_path, &state)?;
+        Ok(state.settings.clone())
+    }
 
-        Ok(summary)
+    pub fn find_item(&self, id: &str) -> Option<SlideIndexItem> {
+        let state = self.state.lock().expect("state poisoned");
+        state.items.iter().find(|item| item.id == id).cloned()
     }
 
-    pub fn update_directories(&self, directories: Vec<String>) -> Result<ScanSummary> {
-        println!("update_directories called with: {:?}", directories);
-        
-        let mut seen = std::collections::HashSet::new();
-        let mut sanitised: Vec<String> = Vec::new();
-        for dir in directories {
-            let trimmed = dir.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-            let normalised = trimmed.to_string();
-            if seen.insert(normalised.clone()) {
-                sanitised.push(normalised);
-            }
+    /// Runs OCR on the PDF `id` again whatever text it already has (say after
+    /// installing tesseract or changing language), replacing the item in place.
+    pub fn reocr_item(&self, id: &str) -> Result<SlideIndexItem> {
+        let _scan = self.begin_scan()?;
+        let item = self
+            .find_item(id)
+            .ok_or_else(|| AppError::Message(format!("No indexed item with id {id}")))?;
+        if !matches!(item.kind, SlideKind::Pdf) {
+            return Err(AppError::Message(format!("{} is not a PDF", item.name)));
         }
-
-        println!("Sanitised directories: {:?}", sanitised);
-
-        let (last_indexed_at, item_count) = {
-            let mut state = self.state.lock().expect("state poisoned");
-            state.directories = sanitised.clone();
-            println!("Saving directories to state: {:?}", state.directories);
-            persist_state(&self.storage_path, &state)?;
-            println!("Directories persisted successfully (no scan triggered)");
-            (state.last_indexed_at, state.items.len())
-        };
-
-        // Return summary without scanning
-        let mut summary = ScanSummary {
-            indexed: item_count,
-            scanned: None,
-            cached: None,
-            errors: Vec::new(),
-            last_indexed_at,
-        };
-        
-        if let Some(message) = ocr_status_message() {
-            summary.errors.push(message);
+        if !deck_exists(&item.path) {
+            return Err(AppError::Message("Slide deck path no longer exists".to_string()));
         }
-        
-        Ok(summary)
-    }
-
-    pub fn rescan_directory(&self, directory: String) -> Result<ScanSummary> {
-        let (target, existing_subset) = {
-            let state = self.state.lock().expect("state poisoned");
-            if let Some(target) = state
-                .directories
-                .iter()
-                .find(|existing| *existing == &directory)
-                .cloned()
-            {
-                let subset = state
-                    .items
-                    .iter()
-                    .filter(|item| path_within(&item.path, &target))
-                    .cloned()
-                    .collect::<Vec<_>>();
-                (Some(target), subset)
-            } else {
-                (None, Vec::new())
-            }
-        };
+        println!("⟳ Re-running OCR on {}", item.name);
+        let mut settings = self.state.lock().expect("state poisoned").settings.clone();
+        settings.ocr_mode = OcrMode::Force;
+        // Pages OCR'd before would otherwise be served from the cache untouched.
+        if let Some(checksum) = &item.checksum {
+            ocr_cache::evict(checksum);
+        }
+        let mut fresh = index_path_with_language(&launchable_path(&item.path)?, &settings, item.language.as_deref())?;
+        // Archived decks are indexed from a temporary copy; file them where they were.
+        fresh.id = item.id.clone();
+        fresh.path = item.path.clone();
+        fresh.thumbnail_path = item.thumbnail_path.clone();
 
-        let target = target
-            .ok_or_else(|| AppError::Message(format!("Directory not linked: {directory}")))?;
-
-        // Create callback that saves state after each indexed file
-        let storage_path = self.storage_path.clone();
-        let state_mutex = &self.state;
-        
-        let mut progress_cb = |path: &str, status: &str, debug: Option<&str>| self.emit_scan_progress(Some(path), Some(status), debug);
-        
-        let mut on_item_indexed = |item: crate::models::SlideIndexItem| {
-            let mut state = state_mutex.lock().expect("state poisoned");
-            // Add or update the item
-            if let Some(pos) = state.items.iter().position(|i| i.path == item.path) {
-                state.items[pos] = item;
-            } else {
-                state.items.push(item);
-            }
-            state.last_indexed_at = Some(current_timestamp());
-            // Save immediately after each file
-            if let Err(e) = persist_state(&storage_path, &state) {
-                println!("⚠️  Failed to save cache after indexing file: {}", e);
-            } else {
-                println!("💾 Cache saved (items: {})", state.items.len());
-            }
-        };
-        
-        let outcome = scan_directories(&[target.clone()], &existing_subset, &mut progress_cb, &mut on_item_indexed);
-        let ScanOutcome {
-            items: new_items,
-            errors,
-            scanned_count,
-            cached_count,
-        } = match outcome {
-            Ok(result) => result,
-            Err(error) => {
-                self.emit_scan_progress(None, None, None);
-                return Err(error);
-            }
-        };
+        let mut state = self.state.lock().expect("state poisoned");
+        let slot = state
+            .items
+            .iter_mut()
+            .find(|existing| existing.id == item.id)
+            .ok_or_else(|| AppError::Message(format!("No indexed item with id {id}")))?;
+        fresh.carry_user_fields_from(slot);
+        *slot = fresh.clone();
+        self.invalidate_search_caches();
+        ocr_cache::evict_stale(&state.items);
+        text_cache::evict_stale(&state.items);
+        persist_state(&self.storage_path, &state)?;
+        Ok(fresh)
+    }
 
+    /// Drops every item and cached page. Refused while a scan is running, as
+    /// it would put the items straight back.
+    pub fn clear_cache(&self) -> Result<()> {
+        let _scan = self.begin_scan()?;
         let mut state = self.state.lock().expect("state poisoned");
-        state.items.retain(|item| !path_within(&item.path, &target));
-        state.items.extend(new_items);
-        state.items.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        state.items.clear();
+        self.invalidate_search_caches();
+        // With no items left this drops every cached OCR page too.
+        ocr_cache::evict_stale(&state.items);
+        text_cache::evict_stale(&state.items);
         state.last_indexed_at = Some(current_timestamp());
+        state.warnings.clear();
+        state.errors.clear();
+        state.scan_errors.clear();
+        persist_state(&self.storage_path, &state)?;
+        Ok(())
+    }
 
-        let mut summary = ScanSummary {
-            indexed: state.items.len(),
-            scanned: Some(scanned_count),
-            cached: Some(cached_count),
-            errors,
-            last_indexed_at: state.last_indexed_at,
-        };
+    /// Drops the items under `directory` (and their cached OCR pages) so the
+    /// next scan indexes that tree from scratch; the directory stays linked.
+    /// Returns how many items are left. Refused while a scan is running, as it
+    /// would put the items straight back.
+    pub fn clear_directory(&self, directory: &str) -> Result<usize> {
+        let _scan = self.begin_scan()?;
+        let mut state = self.state.lock().expect("state poisoned");
+        let before = state.items.len();
+        state.items.retain(|item| !path_within(&item.path, directory));
+        println!("🧹 Cleared {} items under {}", before - state.items.len(), directory);
+        self.invalidate_search_caches();
+        ocr_cache::evict_stale(&state.items);
+        text_cache::evict_stale(&state.items);
+        persist_state(&self.storage_path, &state)?;
+        Ok(state.items.len())
+    }
 
-        if let Some(message) = ocr_status_message() {
-            if !summary.errors.iter().any(|existing| existing == &message) {
-                summary.errors.push(message);
-            }
+    /// Called on exit: stops a running scan, gives it up to `SHUTDOWN_SCAN_WAIT`
+    /// to finish its current file and commit, then writes the index whatever
+    /// state it is in, so at most that one file's work is lost.
+    pub fn shutdown(&self) -> Result<()> {
+        if self.read_only {
+            return Ok(());
         }
-
-        state.warnings = summary.errors.clone();
-        let persist_result = persist_state(&self.storage_path, &state);
-        self.emit_scan_progress(None, None, None);
-        persist_result?;
-
-        Ok(summary)
+        self.stop_scan();
+        let deadline = Instant::now() + SHUTDOWN_SCAN_WAIT;
+        while self.scan_running.load(Ordering::SeqCst) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+        self.save_pending.store(false, Ordering::SeqCst);
+        let state = self.state.lock().expect("state poisoned");
+        persist_state(&self.storage_path, &state)
     }
 
-    pub fn search(&self, query: &str) -> SearchResponse {
-        let state = self.state.lock().expect("state poisoned");
-        let pattern = SearchPattern::new(query);
-        let items = state
-            .items
-            .iter()
-            .cloned()
-            .filter(|item| matches_query(item, &pattern))
-            .collect::<Vec<SlideIndexItem>>();
-        SearchResponse {
-            total: items.len(),
-            items,
-            last_indexed_at: state.last_indexed_at,
+    /// Writes any edit still waiting for its debounced save. Called on exit;
+    /// the frontend can also call it before anything that must not lose edits.
+    pub fn flush(&self) -> Result<()> {
+        if self.read_only || !self.save_pending.swap(false, Ordering::SeqCst) {
+            return Ok(());
         }
+        let state = self.state.lock().expect("state poisoned");
+        persist_state(&self.storage_path, &state)
     }
 
-    pub fn find_item(&self, id: &str) -> Option<SlideIndexItem> {
-        let state = self.state.lock().expect("state poisoned");
-        state.items.iter().find(|item| item.id == id).cloned()
+    /// Marks the index as changed; it is saved once no edit has come in for `SAVE_DEBOUNCE`.
+    fn schedule_save(&self) {
+        self.save_pending.store(true, Ordering::SeqCst);
+        let _ = self.save_requests.send(());
     }
 
-    pub fn clear_cache(&self) -> Result<()> {
-        let mut state = self.state.lock().expect("state poisoned");
-        state.items.clear();
-        state.last_indexed_at = Some(current_timestamp());
-        state.warnings.clear();
-        persist_state(&self.storage_path, &state)?;
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(AppError::ReadOnly);
+        }
+        if self.data_dir_moved.load(Ordering::SeqCst) {
+            return Err(AppError::RestartRequired);
+        }
         Ok(())
     }
 
-    fn emit_scan_progress(&self, path: Option<&str>, status: Option<&str>, debug_info: Option<&str>) {
-        let payload = ScanProgressPayload {
-            path: path.map(|value| value.to_string()),
-            status: status.map(|value| value.to_string()),
-            debug_info: debug_info.map(|value| value.to_string()),
-        };
-        let _ = self.app_handle.emit_all("scan-progress", payload);
+    /// Whether edits are refused: a read-only index, or one `set_data_dir` moved.
+    pub fn is_read_only(&self) -> bool {
+        self.ensure_writable().is_err()
     }
-}
 
-fn load_state(path: &Path) -> Result<AppState> {
-    let raw = fs::read_to_string(path)?;
-    let parsed: AppState = serde_json::from_str(&raw)?;
-    Ok(parsed)
-}
+    /// Drops the derived search structures; call after any change to `state.items`.
+    fn invalidate_search_caches(&self) {
+        *self.keyword_index.lock().expect("keyword index poisoned") = None;
+        *self.corpus_cache.lock().expect("corpus cache poisoned") = None;
+        *self.inverted_index.lock().expect("inverted index poisoned") = None;
+    }
 
-fn persist_state(path: &Path, state: &AppState) -> Result<()> {
-    let payload = serde_json::to_string_pretty(state)?;
-    fs::write(path, payload)?;
-    Ok(())
 }
 
-fn path_within(path: &str, directory: &str) -> bool {
-    let file_path = Path::new(path);
-    let dir_path = Path::new(directory);
-    file_path == dir_path || file_path.starts_with(dir_path)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Held by tests that clear items: that evicts from the process-wide text
+    /// cache, which another test may be filling at the same time.
+    pub(super) static TEXT_CACHE: Mutex<()> = Mutex::new(());
+
+    /// A manager over `state` stored in `dir`, without an app to send events to.
+    pub(super) fn manager(dir: &Path, state: AppState) -> StateManager {
+        let storage_path = dir.join("index.json");
+        let state = Arc::new(Mutex::new(state));
+        let save_pending = Arc::new(AtomicBool::new(false));
+        let save_requests = spawn_debounced_saver(Arc::clone(&state), storage_path.clone(), Arc::clone(&save_pending));
+        StateManager {
+            state,
+            storage_path,
+            default_data_dir: dir.to_path_buf(),
+            save_pending,
+            save_requests,
+            thumbnail_dir: dir.join("thumbnails"),
+            render_dir: dir.join("renders"),
+            app_handle: None,
+            keyword_index: Mutex::new(None),
+            corpus_cache: Mutex::new(None),
+            inverted_index: Mutex::new(None),
+            progress_throttle: Mutex::new(ProgressThrottle::default()),
+            scan_running: AtomicBool::new(false),
+            stop_requested: AtomicBool::new(false),
+            read_only: false,
+            data_dir_moved: AtomicBool::new(false),
+        }
+    }
+
+    pub(super) fn item(path: &str, updated_at: u64) -> SlideIndexItem {
+        serde_json::from_value(serde_json::json!({
+            "id": path,
+            "path": path,
+            "name": Path::new(path).file_name().unwrap().to_string_lossy(),
+            "kind": "pptx",
+            "slideCount": 1,
+            "snippet": "",
+            "updatedAt": updated_at,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn clear_cache_is_refused_during_a_scan() {
+        let _text_cache = TEXT_CACHE.lock().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager(dir.path(), AppState { items: vec![item("/d/a.pptx", 1)], ..AppState::default() });
+
+        let scan = manager.begin_scan().unwrap();
+        assert!(matches!(manager.clear_cache(), Err(AppError::ScanInProgress)));
+        assert_eq!(manager.get_state().items.len(), 1);
+
+        drop(scan);
+        manager.clear_cache().unwrap();
+        assert!(manager.get_state().items.is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,198 @@
+//! Where the index and its caches live: the app-data folder, a directory
+//! from `DATA_DIR_ENV`, or one chosen with `set_data_dir`.
+
+use std::{fs, path::{Path, PathBuf}, sync::atomic::Ordering};
+
+use tauri::Manager;
+
+use crate::{error::{AppError, Result}, index::persist_state, models::DataDirProgressPayload};
+
+use super::StateManager;
+
+/// Environment variable naming a directory to keep the index and its caches in,
+/// instead of the app-data folder. Wins over a location set with `set_data_dir`.
+pub const DATA_DIR_ENV: &str = "SLIDE_INDEXER_DATA_DIR";
+
+/// File in the default data directory holding the location set with `set_data_dir`.
+const DATA_DIR_POINTER: &str = "data-dir.txt";
+
+/// The data directory to use: `DATA_DIR_ENV`, else the one saved by
+/// `set_data_dir`, else `default_dir`. A custom directory is created if
+/// missing and, when it has no index yet, seeded from `default_dir`.
+pub(super) fn resolve_data_dir(default_dir: &Path) -> Result<PathBuf> {
+    let custom = std::env::var_os(DATA_DIR_ENV)
+        .map(PathBuf::from)
+        .or_else(|| {
+            fs::read_to_string(default_dir.join(DATA_DIR_POINTER))
+                .ok()
+                .map(|saved| PathBuf::from(saved.trim()))
+        })
+        .filter(|directory| !directory.as_os_str().is_empty());
+    let Some(custom) = custom else {
+        return Ok(default_dir.to_path_buf());
+    };
+    prepare_data_dir(&custom)?;
+    copy_index(default_dir, &custom)?;
+    println!("📁 Using data directory {}", custom.display());
+    Ok(custom)
+}
+
+fn prepare_data_dir(directory: &Path) -> Result<()> {
+    if !directory.is_absolute() {
+        return Err(AppError::Message(format!(
+            "Data directory must be an absolute path: {}",
+            directory.display()
+        )));
+    }
+    fs::create_dir_all(directory)?;
+    Ok(())
+}
+
+/// Copies `index.json` and the OCR and full-text caches from `from` to `to`,
+/// unless `to` already has an index.
+fn copy_index(from: &Path, to: &Path) -> Result<()> {
+    let source = from.join("index.json");
+    if from == to || to.join("index.json").exists() || !source.exists() {
+        return Ok(());
+    }
+    copy_caches(from, to, &mut |_, _| {})?;
+    // Last, so an interrupted copy is simply retried on the next launch.
+    fs::copy(&source, to.join("index.json"))?;
+    println!("📦 Copied index from {} to {}", from.display(), to.display());
+    Ok(())
+}
+
+/// Copies the OCR and full-text caches from `from` to `to`, calling
+/// `progress(copied, total)` after each file. Thumbnails aren't copied; they
+/// are rendered again on demand.
+fn copy_caches(from: &Path, to: &Path, progress: &mut dyn FnMut(usize, usize)) -> Result<()> {
+    let mut files = Vec::new();
+    for cache in ["ocr", "text"] {
+        let Ok(entries) = fs::read_dir(from.join(cache)) else {
+            continue;
+        };
+        fs::create_dir_all(to.join(cache))?;
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if entry.path().is_file() {
+                files.push((entry.path(), to.join(cache).join(entry.file_name())));
+            }
+        }
+    }
+    for (copied, (source, destination)) in files.iter().enumerate() {
+        fs::copy(source, destination)?;
+        progress(copied + 1, files.len());
+    }
+    Ok(())
+}
+
+impl StateManager {
+    /// The app's own data folder, even when the index lives elsewhere.
+    pub fn install_dir(&self) -> &Path {
+        &self.default_data_dir
+    }
+
+    /// Folder holding `index.json` and the caches.
+    pub fn data_dir(&self) -> PathBuf {
+        self.storage_path.parent().map(Path::to_path_buf).unwrap_or_default()
+    }
+
+    /// Moves the index to `directory` (None = back to the app-data folder) from
+    /// the next launch on. Unless it already holds an index, the index as it is
+    /// now and its caches are copied there, with `data-dir-progress` events.
+    /// Edits are refused from then until the restart (`AppError::RestartRequired`),
+    /// so none is left behind in the old folder. Slow for big caches; call it
+    /// off the main thread. Returns the directory that will be used.
+    pub fn set_data_dir(&self, directory: Option<String>) -> Result<PathBuf> {
+        let _scan = self.begin_scan()?;
+        let pointer = self.default_data_dir.join(DATA_DIR_POINTER);
+        let custom = directory.as_deref().map(str::trim).filter(|value| !value.is_empty());
+        let target = custom.map_or_else(|| self.default_data_dir.clone(), PathBuf::from);
+        prepare_data_dir(&target)?;
+
+        let current = self.data_dir();
+        if target != current {
+            self.data_dir_moved.store(true, Ordering::SeqCst);
+            if let Err(error) = self.copy_index_to(&current, &target) {
+                self.data_dir_moved.store(false, Ordering::SeqCst);
+                return Err(error);
+            }
+        }
+        match custom {
+            Some(directory) => fs::write(&pointer, directory)?,
+            None if pointer.exists() => fs::remove_file(&pointer)?,
+            None => {}
+        }
+        if std::env::var_os(DATA_DIR_ENV).is_some() {
+            println!("⚠️  {} is set and still overrides the data directory", DATA_DIR_ENV);
+        }
+        Ok(target)
+    }
+
+    /// `copy_index` from `current` for `set_data_dir`, writing the index as it
+    /// is in memory (pending edits included) rather than the last save.
+    fn copy_index_to(&self, current: &Path, target: &Path) -> Result<()> {
+        if target.join("index.json").exists() {
+            println!("📁 {} already holds an index; it will be used as it is", target.display());
+            return Ok(());
+        }
+        let state = self.get_state();
+        copy_caches(current, target, &mut |copied, total| {
+            if let Some(handle) = &self.app_handle {
+                let _ = handle.emit_all("data-dir-progress", DataDirProgressPayload { copied, total });
+            }
+        })?;
+        // Last, so a copy that fails part-way isn't mistaken for a finished one.
+        persist_state(&target.join("index.json"), &state)?;
+        println!("📦 Copied index from {} to {}", current.display(), target.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        index::load_state,
+        models::AppState,
+        state::tests::{item, manager},
+    };
+
+    #[test]
+    fn moving_the_data_dir_copies_pending_edits_and_refuses_later_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("text")).unwrap();
+        fs::write(dir.path().join("text").join("abc.txt"), "photosynthesis").unwrap();
+        let manager = manager(dir.path(), AppState { items: vec![item("/d/a.pptx", 1)], ..AppState::default() });
+        // Still waiting for its debounced save when the folder changes.
+        manager.add_tag("/d/a.pptx", "biology").unwrap();
+
+        let moved_to = manager.set_data_dir(Some(target.path().to_string_lossy().into_owned())).unwrap();
+
+        assert_eq!(moved_to, target.path());
+        let copied = load_state(&target.path().join("index.json")).unwrap();
+        assert_eq!(copied.items[0].tags, ["biology"]);
+        assert!(target.path().join("text").join("abc.txt").exists());
+        assert_eq!(fs::read_to_string(dir.path().join(DATA_DIR_POINTER)).unwrap(), target.path().to_string_lossy());
+
+        assert!(matches!(manager.add_tag("/d/a.pptx", "plants"), Err(AppError::RestartRequired)));
+        assert!(matches!(manager.rescan(), Err(AppError::RestartRequired)));
+        manager.record_search("plants");
+        assert!(manager.get_recent_searches().is_empty());
+        assert!(manager.is_read_only());
+    }
+
+    #[test]
+    fn moving_to_a_folder_with_an_index_keeps_that_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let existing = AppState { items: vec![item("/shared/b.pptx", 1)], ..AppState::default() };
+        persist_state(&target.path().join("index.json"), &existing).unwrap();
+        let manager = manager(dir.path(), AppState { items: vec![item("/d/a.pptx", 1)], ..AppState::default() });
+
+        manager.set_data_dir(Some(target.path().to_string_lossy().into_owned())).unwrap();
+
+        let kept = load_state(&target.path().join("index.json")).unwrap();
+        assert_eq!(kept.items[0].path, "/shared/b.pptx");
+    }
+}
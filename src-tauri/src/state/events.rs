@@ -0,0 +1,132 @@
+//! Events sent to the frontend while the index changes: throttled scan
+//! progress, indexed items and finished scans.
+
+use std::time::{Duration, Instant};
+
+use tauri::Manager;
+
+use crate::models::{ScanProgressPayload, ScanSummary, SlideIndexItem};
+
+use super::StateManager;
+
+/// Minimum gap between two consecutive `scan-progress` events with the same status.
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Remembers the last emitted progress event so bursts (e.g. thousands of
+/// cached files) are coalesced instead of flooding the IPC channel.
+#[derive(Default)]
+pub(super) struct ProgressThrottle {
+    last_emit: Option<Instant>,
+    last_status: Option<String>,
+    /// The latest event held back by the interval, sent before the next
+    /// status change or the final clear so the last file of a burst is shown.
+    suppressed: Option<ScanProgressPayload>,
+}
+
+impl ProgressThrottle {
+    /// The events to send for `payload`, oldest first.
+    fn admit(&mut self, payload: ScanProgressPayload, now: Instant) -> Vec<ScanProgressPayload> {
+        if payload.status.is_none() {
+            // Final clear: always delivered, and the next scan starts fresh.
+            let suppressed = std::mem::take(self).suppressed;
+            return suppressed.into_iter().chain([payload]).collect();
+        }
+        // First event and status changes (cached -> scanning -> ocr) always go out;
+        // repeats of the same status are limited to one per interval.
+        let is_transition = self.last_status != payload.status;
+        let is_due = self
+            .last_emit
+            .is_none_or(|last| now.duration_since(last) >= PROGRESS_EMIT_INTERVAL);
+        if !is_transition && !is_due {
+            self.suppressed = Some(payload);
+            return Vec::new();
+        }
+        self.last_emit = Some(now);
+        self.last_status = payload.status.clone();
+        // A held-back event of the same status is older than this one; one of
+        // the previous status still has to go out first.
+        let suppressed = self.suppressed.take().filter(|_| is_transition);
+        suppressed.into_iter().chain([payload]).collect()
+    }
+}
+
+impl StateManager {
+    pub(super) fn emit_scan_progress(&self, path: Option<&str>, status: Option<&str>, debug_info: Option<&str>) {
+        let payload = ScanProgressPayload {
+            path: path.map(|value| value.to_string()),
+            status: status.map(|value| value.to_string()),
+            debug_info: debug_info.map(|value| value.to_string()),
+        };
+        let payloads = self
+            .progress_throttle
+            .lock()
+            .expect("progress throttle poisoned")
+            .admit(payload, Instant::now());
+        if let Some(handle) = &self.app_handle {
+            for payload in payloads {
+                let _ = handle.emit_all("scan-progress", payload);
+            }
+        }
+    }
+
+    /// Announces a finished scan to every listener, not just the command that
+    /// started it, so scans nobody awaits (e.g. automatic ones) can be reacted to.
+    pub(super) fn emit_scan_complete(&self, summary: &ScanSummary) {
+        if let Some(handle) = &self.app_handle {
+            let _ = handle.emit_all("scan-complete", summary);
+        }
+    }
+
+    /// Sent for every file a scan (re-)indexes, so the list can fill in live.
+    /// Cached files aren't sent; they're already in the frontend's state.
+    pub(super) fn emit_item_indexed(&self, item: &SlideIndexItem) {
+        if let Some(handle) = &self.app_handle {
+            let _ = handle.emit_all("item-indexed", item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(path: &str, status: Option<&str>) -> ScanProgressPayload {
+        ScanProgressPayload {
+            path: Some(path.to_string()),
+            status: status.map(str::to_string),
+            debug_info: None,
+        }
+    }
+
+    fn paths(payloads: Vec<ScanProgressPayload>) -> Vec<String> {
+        payloads.into_iter().filter_map(|payload| payload.path).collect()
+    }
+
+    #[test]
+    fn throttled_progress_is_flushed_on_status_change_and_final_clear() {
+        let mut throttle = ProgressThrottle::default();
+        let start = Instant::now();
+
+        assert_eq!(paths(throttle.admit(progress("a", Some("cached")), start)), ["a"]);
+        assert!(throttle.admit(progress("b", Some("cached")), start).is_empty());
+        assert!(throttle.admit(progress("c", Some("cached")), start).is_empty());
+        // The latest held-back file goes out before the new status.
+        assert_eq!(paths(throttle.admit(progress("d", Some("scanning")), start)), ["c", "d"]);
+
+        assert!(throttle.admit(progress("e", Some("scanning")), start).is_empty());
+        assert_eq!(paths(throttle.admit(progress("", None), start)), ["e", ""]);
+        assert!(throttle.suppressed.is_none());
+    }
+
+    #[test]
+    fn throttled_progress_is_replaced_once_the_interval_passes() {
+        let mut throttle = ProgressThrottle::default();
+        let start = Instant::now();
+
+        throttle.admit(progress("a", Some("cached")), start);
+        assert!(throttle.admit(progress("b", Some("cached")), start).is_empty());
+        let later = start + PROGRESS_EMIT_INTERVAL;
+        assert_eq!(paths(throttle.admit(progress("c", Some("cached")), later)), ["c"]);
+        assert_eq!(paths(throttle.admit(progress("", None), later)), [""]);
+    }
+}
@@ -0,0 +1,137 @@
+//! Questions about the library as a whole or one item: statistics, health,
+//! unindexed and similar items, diffs and full text.
+
+use crate::{
+    error::{AppError, Result},
+    index::{check_index_health, persist_state},
+    item_diff,
+    keyword_index::keyword_similarity,
+    models::{IndexHealth, IndexStats, ItemDiff, SimilarItem, SlideIndexItem},
+    scanner::{deck_exists, has_extracted_text, index_path, launchable_path},
+    text_cache,
+};
+
+use super::StateManager;
+
+/// `IndexStats::low_coverage_items` counts items with less text coverage than this.
+const LOW_TEXT_COVERAGE: f32 = 0.5;
+
+impl StateManager {
+    pub fn stats(&self) -> IndexStats {
+        let state = self.state.lock().expect("state poisoned");
+        let mut stats = IndexStats {
+            total_items: state.items.len(),
+            last_indexed_at: state.last_indexed_at,
+            ..IndexStats::default()
+        };
+        for item in &state.items {
+            stats.items_by_kind.record(&item.kind);
+            stats.total_slides += u64::from(item.slide_count.unwrap_or(0));
+            stats.total_size += item.file_size.unwrap_or(0);
+            if !has_extracted_text(item) {
+                stats.items_without_text += 1;
+            }
+            if item.encrypted {
+                stats.encrypted_items += 1;
+            }
+            if item.text_coverage.is_some_and(|coverage| coverage < LOW_TEXT_COVERAGE) {
+                stats.low_coverage_items += 1;
+            }
+        }
+        stats
+    }
+
+    /// Re-runs the startup health check, dropping items that can't be repaired
+    /// otherwise (empty id or path, duplicate id). Missing files are only reported.
+    pub fn index_health(&self) -> Result<IndexHealth> {
+        let mut state = self.state.lock().expect("state poisoned");
+        let health = check_index_health(&mut state, !self.is_read_only());
+        if health.repaired > 0 {
+            self.invalidate_search_caches();
+            persist_state(&self.storage_path, &state)?;
+        }
+        Ok(health)
+    }
+
+    /// Items indexing got no usable text from (image-only PDFs without OCR,
+    /// password-protected PDFs, parse failures); candidates for a re-scan once tesseract is installed.
+    pub fn list_unindexed(&self) -> Vec<SlideIndexItem> {
+        let state = self.state.lock().expect("state poisoned");
+        state
+            .items
+            .iter()
+            .filter(|item| !has_extracted_text(item))
+            .cloned()
+            .collect()
+    }
+
+    /// Up to `limit` other items sharing keywords with `id`, most similar first.
+    /// Items without keywords (or with nothing in common) are left out.
+    pub fn similar_items(&self, id: &str, limit: usize) -> Result<Vec<SimilarItem>> {
+        let state = self.state.lock().expect("state poisoned");
+        let target = state
+            .items
+            .iter()
+            .find(|item| item.id == id)
+            .ok_or_else(|| AppError::Message(format!("No indexed item with id {id}")))?;
+
+        let mut similar: Vec<SimilarItem> = state
+            .items
+            .iter()
+            .filter(|item| item.id != target.id && !item.keywords.is_empty())
+            .filter_map(|item| {
+                let score = keyword_similarity(&target.keywords, &item.keywords);
+                (score > 0.0).then(|| SimilarItem {
+                    item: item.clone(),
+                    score,
+                })
+            })
+            .collect();
+        similar.sort_by(|a, b| b.score.total_cmp(&a.score));
+        similar.truncate(limit);
+        Ok(similar)
+    }
+
+    /// Slide-by-slide text differences from item `id_a` to item `id_b`.
+    pub fn diff_items(&self, id_a: &str, id_b: &str) -> Result<ItemDiff> {
+        let state = self.state.lock().expect("state poisoned");
+        let find = |id: &str| {
+            state
+                .items
+                .iter()
+                .find(|item| item.id == id)
+                .ok_or_else(|| AppError::Message(format!("No indexed item with id {id}")))
+        };
+        Ok(item_diff::diff_items(find(id_a)?, find(id_b)?))
+    }
+
+    /// Complete extracted text of `id`: from the full-text cache, else by
+    /// extracting the file again (which also refills the cache).
+    pub fn get_full_text(&self, id: &str) -> Result<String> {
+        let item = self
+            .find_item(id)
+            .ok_or_else(|| AppError::Message("Slide deck not found".to_string()))?;
+        if let Some(text) = item.checksum.as_deref().and_then(text_cache::get) {
+            return Ok(text);
+        }
+        if !deck_exists(&item.path) {
+            return Err(AppError::Message("Slide deck path no longer exists".to_string()));
+        }
+        println!("📄 Full text of {} not cached, extracting again", item.name);
+        let settings = self.state.lock().expect("state poisoned").settings.clone();
+        let fresh = index_path(&launchable_path(&item.path)?, &settings)?;
+        Ok(fresh
+            .checksum
+            .as_deref()
+            .and_then(text_cache::get)
+            .unwrap_or_else(|| {
+                // Cache disabled: the slide texts are the next best thing.
+                fresh
+                    .slides
+                    .iter()
+                    .map(|slide| slide.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }))
+    }
+}
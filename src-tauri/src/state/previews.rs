@@ -0,0 +1,77 @@
+//! Slide images: cached first-slide thumbnails and per-page renders.
+
+use crate::{error::{AppError, Result}, index::persist_state, models::SlideIndexItem, thumbnails};
+
+use super::StateManager;
+
+impl StateManager {
+    /// Returns the cached first-slide thumbnail for `id`, rendering it if missing or stale.
+    pub fn get_thumbnail(&self, id: &str) -> Result<String> {
+        let item = self
+            .find_item(id)
+            .ok_or_else(|| AppError::Message(format!("No indexed item with id {id}")))?;
+        if thumbnails::is_current(&self.thumbnail_dir, &item) {
+            return Ok(item.thumbnail_path.unwrap_or_default());
+        }
+
+        let path = thumbnails::generate_thumbnail(&self.thumbnail_dir, &item)?
+            .to_string_lossy()
+            .to_string();
+        let mut state = self.state.lock().expect("state poisoned");
+        if let Some(existing) = state.items.iter_mut().find(|existing| existing.id == id) {
+            existing.thumbnail_path = Some(path.clone());
+        }
+        if !self.is_read_only() {
+            persist_state(&self.storage_path, &state)?;
+        }
+        Ok(path)
+    }
+
+    /// Path of a PNG render of page `index` (1-based) of a PDF item, cached per resolution.
+    pub fn render_slide(&self, id: &str, index: u32, dpi: u32) -> Result<String> {
+        let item = self
+            .find_item(id)
+            .ok_or_else(|| AppError::Message(format!("No indexed item with id {id}")))?;
+        let path = thumbnails::render_page(&self.render_dir, &item, index, dpi)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// Renders thumbnails for every item whose cached image is missing or outdated.
+    /// Failures are logged and skipped; the item just keeps no thumbnail.
+    pub(super) fn refresh_thumbnails(&self) {
+        let pending: Vec<SlideIndexItem> = {
+            let state = self.state.lock().expect("state poisoned");
+            state
+                .items
+                .iter()
+                .filter(|item| !thumbnails::is_current(&self.thumbnail_dir, item))
+                .cloned()
+                .collect()
+        };
+        if pending.is_empty() {
+            return;
+        }
+
+        // Render without holding the lock; this can take a while for big indexes.
+        let mut rendered: Vec<(String, String)> = Vec::new();
+        for item in &pending {
+            match thumbnails::generate_thumbnail(&self.thumbnail_dir, item) {
+                Ok(path) => rendered.push((item.id.clone(), path.to_string_lossy().to_string())),
+                Err(error) => println!("⚠️  Thumbnail failed for {}: {}", item.path, error),
+            }
+        }
+        if rendered.is_empty() {
+            return;
+        }
+
+        let mut state = self.state.lock().expect("state poisoned");
+        for (id, path) in rendered {
+            if let Some(item) = state.items.iter_mut().find(|item| item.id == id) {
+                item.thumbnail_path = Some(path);
+            }
+        }
+        if let Err(error) = persist_state(&self.storage_path, &state) {
+            println!("⚠️  Failed to save thumbnail paths: {}", error);
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! Scanning the linked directories into the index: full, catch-up and
+//! per-directory rescans, their dry runs, and excluded directories.
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{AppError, Result},
+    index::{carry_over_user_fields, finish_scan, path_within, persist_state},
+    models::{ScanError, ScanSettings, ScanSummary, ScanTiming, SlideIndexItem},
+    scanner::{current_timestamp, path_key, plan_scan, scan_directories, ScanOutcome},
+};
+
+use super::StateManager;
+
+/// Marks a scan as running for as long as it lives; see `StateManager::begin_scan`.
+pub(super) struct ScanGuard<'a>(&'a AtomicBool);
+
+impl Drop for ScanGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Removes directories that lie inside another linked directory (compared by
+/// path component, so `/Slides2` isn't inside `/Slides`); their decks are
+/// already scanned through the parent and would otherwise be indexed twice.
+/// Returns a warning for each one removed.
+fn drop_nested_directories(directories: &mut Vec<String>) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut kept: Vec<String> = Vec::new();
+    for directory in directories.iter() {
+        let path = Path::new(directory);
+        // The same folder spelled twice ("/Slides/" and "/Slides"): keep the first.
+        if kept.iter().any(|other| Path::new(other) == path) {
+            continue;
+        }
+        let parent = directories
+            .iter()
+            .find(|other| Path::new(other.as_str()) != path && path_within(directory, other));
+        match parent {
+            Some(parent) => {
+                println!("⚠️  Skipping {}: already covered by {}", directory, parent);
+                warnings.push(format!("{directory} is inside {parent}, which is already linked"));
+            }
+            None => kept.push(directory.clone()),
+        }
+    }
+    *directories = kept;
+    warnings
+}
+
+/// Decides when `on_item_indexed` should write the index to disk during a scan,
+/// so big scans save in batches instead of after every single file.
+struct PersistBatch {
+    pending: usize,
+    last_save: Instant,
+    every_items: usize,
+    interval: Duration,
+}
+
+impl PersistBatch {
+    fn new(settings: &ScanSettings) -> Self {
+        Self {
+            pending: 0,
+            last_save: Instant::now(),
+            every_items: settings.persist_every_items.max(1),
+            interval: Duration::from_millis(settings.persist_interval_ms),
+        }
+    }
+
+    /// Records one indexed item; returns true when a save is due.
+    fn record(&mut self) -> bool {
+        self.pending += 1;
+        if self.pending >= self.every_items || self.last_save.elapsed() >= self.interval {
+            self.pending = 0;
+            self.last_save = Instant::now();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Where each item sits in `state.items`, by `path_key`, so storing an indexed
+/// file mid-scan doesn't compare (and re-key) every item each time. Built on
+/// first use and rebuilt only if the list changed outside the scan.
+#[derive(Default)]
+struct ItemPositions {
+    by_key: Option<HashMap<String, usize>>,
+    items_len: usize,
+}
+
+impl ItemPositions {
+    fn find(&mut self, items: &[SlideIndexItem], key: &str) -> Option<usize> {
+        if self.by_key.is_none() || self.items_len != items.len() {
+            self.rebuild(items);
+        }
+        let index = *self.by_key.as_ref()?.get(key)?;
+        if items.get(index).is_some_and(|item| path_key(&item.path) == key) {
+            return Some(index);
+        }
+        self.rebuild(items);
+        self.by_key.as_ref()?.get(key).copied()
+    }
+
+    fn rebuild(&mut self, items: &[SlideIndexItem]) {
+        self.by_key = Some(
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| (path_key(&item.path).into_owned(), index))
+                .collect(),
+        );
+        self.items_len = items.len();
+    }
+
+    /// Records an item appended to the list at `index`.
+    fn push(&mut self, key: String, index: usize) {
+        if let Some(by_key) = &mut self.by_key {
+            by_key.insert(key, index);
+            self.items_len = index + 1;
+        }
+    }
+}
+
+impl StateManager {
+    /// Dry run of `rescan`: reports what it would reuse, re-index and drop,
+    /// without indexing, running OCR or saving anything.
+    pub fn plan_rescan(&self) -> Result<ScanSummary> {
+        let (directories, existing_snapshot, settings, last_indexed_at) = {
+            let state = self.state.lock().expect("state poisoned");
+            (
+                state.directories.clone(),
+                state.items.clone(),
+                state.settings.clone(),
+                state.last_indexed_at,
+            )
+        };
+        let plan = plan_scan(&directories, &existing_snapshot, &settings)?;
+        let scan_errors: Vec<ScanError> = directories
+            .iter()
+            .filter(|directory| !Path::new(directory).exists())
+            .map(|directory| ScanError::new(directory.as_str(), "Directory not found"))
+            .collect();
+        Ok(ScanSummary {
+            indexed: plan.cached + plan.changed + plan.new,
+            scanned: Some(plan.changed + plan.new),
+            cached: Some(plan.cached),
+            errors: scan_errors.iter().map(ToString::to_string).collect(),
+            scan_errors,
+            warnings: Vec::new(),
+            last_indexed_at,
+            plan: Some(plan),
+            timing: None,
+        })
+    }
+
+    pub fn rescan(&self) -> Result<ScanSummary> {
+        self.run_rescan(None)
+    }
+
+    /// Catch-up rescan: only files modified at or after `since` (default: the
+    /// last time anything was indexed) are re-indexed; older ones stay cached
+    /// as they are. New and deleted files are handled as in `rescan`.
+    pub fn rescan_modified_since(&self, since: Option<u64>) -> Result<ScanSummary> {
+        let since = since.or_else(|| self.state.lock().expect("state poisoned").last_indexed_at);
+        println!("⏩ Catch-up rescan of files modified since {:?}", since);
+        self.run_rescan(since)
+    }
+
+    fn run_rescan(&self, modified_since: Option<u64>) -> Result<ScanSummary> {
+        let _scan = self.begin_scan()?;
+        let (directories, existing_snapshot, settings) = {
+            let state = self.state.lock().expect("state poisoned");
+            (state.directories.clone(), state.items.clone(), state.settings.clone())
+        };
+        if directories.is_empty() {
+            let mut state = self.state.lock().expect("state poisoned");
+            state.items.clear();
+            self.invalidate_search_caches();
+            // Report it like a scan that found nothing: zero counts rather than
+            // None (which means "not scanned"), and warnings rebuilt the same way.
+            let summary = finish_scan(
+                &mut state,
+                Some(0),
+                Some(0),
+                Vec::new(),
+                Vec::new(),
+                Some(ScanTiming::default()),
+            );
+            let persist_result = persist_state(&self.storage_path, &state);
+            self.emit_scan_progress(None, None, None);
+            persist_result?;
+            self.emit_scan_complete(&summary);
+            return Ok(summary);
+        }
+
+        // Create callback that saves state in batches as files are indexed
+        let mut persist_batch = PersistBatch::new(&settings);
+        let mut positions = ItemPositions::default();
+        
+        let mut progress_cb = |path: &str, status: &str, debug: Option<&str>| self.emit_scan_progress(Some(path), Some(status), debug);
+        
+        let mut on_item_indexed = |item| self.store_indexed_item(item, &mut positions, &mut persist_batch);
+        
+        let outcome = scan_directories(&directories, &existing_snapshot, &settings, modified_since, &self.stop_requested, &mut progress_cb, &mut on_item_indexed);
+        let ScanOutcome { items, errors, warnings, scanned_count, cached_count, timing } = match outcome {
+            Ok(result) => result,
+            Err(error) => {
+                // Keep whatever was indexed since the last batch save.
+                {
+                    let state = self.state.lock().expect("state poisoned");
+                    self.invalidate_search_caches();
+                    let _ = persist_state(&self.storage_path, &state);
+                }
+                self.emit_scan_progress(None, None, None);
+                return Err(error);
+            }
+        };
+
+        let mut state = self.state.lock().expect("state poisoned");
+        let mut items = items;
+        carry_over_user_fields(&state.items, &mut items);
+        state.items = items;
+        self.invalidate_search_caches();
+        let summary = finish_scan(&mut state, Some(scanned_count), Some(cached_count), errors, warnings, Some(timing));
+        let persist_result = persist_state(&self.storage_path, &state);
+        self.emit_scan_progress(None, None, None);
+        persist_result?;
+        drop(state);
+        self.emit_scan_complete(&summary);
+
+        if settings.generate_thumbnails {
+            self.refresh_thumbnails();
+        }
+
+        Ok(summary)
+    }
+
+    /// Adds or replaces a freshly indexed item mid-scan, keeping its user
+    /// fields, and writes the index out whenever `persist_batch` says so.
+    /// The search caches are left alone until the scan ends, so searches
+    /// made meanwhile may still see a file's previous text.
+    fn store_indexed_item(&self, mut item: SlideIndexItem, positions: &mut ItemPositions, persist_batch: &mut PersistBatch) {
+        let mut state = self.state.lock().expect("state poisoned");
+        // Add or update the item
+        let key = path_key(&item.path).into_owned();
+        if let Some(pos) = positions.find(&state.items, &key) {
+            item.carry_user_fields_from(&state.items[pos]);
+            self.emit_item_indexed(&item);
+            state.items[pos] = item;
+        } else {
+            self.emit_item_indexed(&item);
+            state.items.push(item);
+            positions.push(key, state.items.len() - 1);
+        }
+        state.last_indexed_at = Some(current_timestamp());
+        if !persist_batch.record() {
+            return;
+        }
+        if let Err(e) = persist_state(&self.storage_path, &state) {
+            println!("⚠️  Failed to save cache after indexing file: {}", e);
+        } else {
+            println!("💾 Cache saved (items: {})", state.items.len());
+        }
+    }
+
+    pub fn update_directories(&self, directories: Vec<String>) -> Result<ScanSummary> {
+        println!("update_directories called with: {:?}", directories);
+        self.ensure_writable()?;
+        
+        let mut seen = std::collections::HashSet::new();
+        let mut sanitised: Vec<String> = Vec::new();
+        for dir in directories {
+            let trimmed = dir.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let normalised = trimmed.to_string();
+            if seen.insert(normalised.clone()) {
+                sanitised.push(normalised);
+            }
+        }
+        let warnings = drop_nested_directories(&mut sanitised);
+
+        println!("Sanitised directories: {:?}", sanitised);
+
+        let (last_indexed_at, item_count) = {
+            let mut state = self.state.lock().expect("state poisoned");
+            state.directories = sanitised.clone();
+            println!("Saving directories to state: {:?}", state.directories);
+            persist_state(&self.storage_path, &state)?;
+            println!("Directories persisted successfully (no scan triggered)");
+            (state.last_indexed_at, state.items.len())
+        };
+
+        // Return summary without scanning
+        let summary = ScanSummary {
+            indexed: item_count,
+            scanned: None,
+            cached: None,
+            errors: Vec::new(),
+            scan_errors: Vec::new(),
+            warnings,
+            last_indexed_at,
+            plan: None,
+            timing: None,
+        };
+
+        Ok(summary)
+    }
+
+    pub fn rescan_directory(&self, directory: String) -> Result<ScanSummary> {
+        let _scan = self.begin_scan()?;
+        let (target, existing_subset, settings) = {
+            let state = self.state.lock().expect("state poisoned");
+            if let Some(target) = state
+                .directories
+                .iter()
+                .find(|existing| *existing == &directory)
+                .cloned()
+            {
+                let subset = state
+                    .items
+                    .iter()
+                    .filter(|item| path_within(&item.path, &target))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (Some(target), subset, state.settings.clone())
+            } else {
+                (None, Vec::new(), state.settings.clone())
+            }
+        };
+
+        let target = target
+            .ok_or_else(|| AppError::Message(format!("Directory not linked: {directory}")))?;
+
+        // Create callback that saves state in batches as files are indexed
+        let mut persist_batch = PersistBatch::new(&settings);
+        let mut positions = ItemPositions::default();
+        
+        let mut progress_cb = |path: &str, status: &str, debug: Option<&str>| self.emit_scan_progress(Some(path), Some(status), debug);
+        
+        let mut on_item_indexed = |item| self.store_indexed_item(item, &mut positions, &mut persist_batch);
+        
+        let outcome = scan_directories(std::slice::from_ref(&target), &existing_subset, &settings, None, &self.stop_requested, &mut progress_cb, &mut on_item_indexed);
+        let ScanOutcome {
+            items: mut new_items,
+            errors,
+            warnings,
+            scanned_count,
+            cached_count,
+            timing,
+        } = match outcome {
+            Ok(result) => result,
+            Err(error) => {
+                // Keep whatever was indexed since the last batch save.
+                {
+                    let state = self.state.lock().expect("state poisoned");
+                    self.invalidate_search_caches();
+                    let _ = persist_state(&self.storage_path, &state);
+                }
+                self.emit_scan_progress(None, None, None);
+                return Err(error);
+            }
+        };
+
+        let mut state = self.state.lock().expect("state poisoned");
+        carry_over_user_fields(&state.items, &mut new_items);
+        state.items.retain(|item| !path_within(&item.path, &target));
+        state.items.extend(new_items);
+        self.invalidate_search_caches();
+        let summary = finish_scan(&mut state, Some(scanned_count), Some(cached_count), errors, warnings, Some(timing));
+        let persist_result = persist_state(&self.storage_path, &state);
+        self.emit_scan_progress(None, None, None);
+        persist_result?;
+        drop(state);
+        self.emit_scan_complete(&summary);
+
+        if settings.generate_thumbnails {
+            self.refresh_thumbnails();
+        }
+
+        Ok(summary)
+    }
+
+    /// Stops scans from looking inside `directory`; returns the excluded directories.
+    pub fn add_excluded_directory(&self, directory: &str) -> Result<Vec<String>> {
+        let directory = directory.trim();
+        if directory.is_empty() {
+            return Err(AppError::Message("Excluded directory cannot be empty".to_string()));
+        }
+        self.ensure_writable()?;
+        let mut state = self.state.lock().expect("state poisoned");
+        let excluded = &mut state.settings.excluded_directories;
+        if !excluded.iter().any(|existing| existing == directory) {
+            excluded.push(directory.to_string());
+            excluded.sort();
+        }
+        persist_state(&self.storage_path, &state)?;
+        Ok(state.settings.excluded_directories.clone())
+    }
+
+    pub fn remove_excluded_directory(&self, directory: &str) -> Result<Vec<String>> {
+        self.ensure_writable()?;
+        let mut state = self.state.lock().expect("state poisoned");
+        state
+            .settings
+            .excluded_directories
+            .retain(|existing| existing != directory.trim());
+        persist_state(&self.storage_path, &state)?;
+        Ok(state.settings.excluded_directories.clone())
+    }
+
+    /// Asks a running scan to stop once the file it is on is done. What it
+    /// indexed so far is kept; files it didn't reach keep their old entries.
+    pub fn stop_scan(&self) {
+        if self.scan_running.load(Ordering::SeqCst) {
+            println!("⏹ Stopping scan");
+            self.stop_requested.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Claims the single scan slot, failing with `ScanInProgress` while another
+    /// scan holds it. The slot is released when the guard is dropped.
+    pub(super) fn begin_scan(&self) -> Result<ScanGuard<'_>> {
+        self.ensure_writable()?;
+        if self.scan_running.swap(true, Ordering::SeqCst) {
+            return Err(AppError::ScanInProgress);
+        }
+        self.stop_requested.store(false, Ordering::SeqCst);
+        Ok(ScanGuard(&self.scan_running))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{AppState, SearchOptions},
+        state::tests::{item, manager, TEXT_CACHE},
+        text_cache,
+    };
+
+    fn dropped(directories: &[&str]) -> (Vec<String>, usize) {
+        let mut directories: Vec<String> = directories.iter().map(|directory| directory.to_string()).collect();
+        let warnings = drop_nested_directories(&mut directories);
+        (directories, warnings.len())
+    }
+
+    #[test]
+    fn nested_directory_is_dropped_in_either_order() {
+        assert_eq!(dropped(&["/a", "/a/b"]), (vec!["/a".to_string()], 1));
+        assert_eq!(dropped(&["/a/b", "/a"]), (vec!["/a".to_string()], 1));
+        assert_eq!(dropped(&["/a/b/c", "/x", "/a"]), (vec!["/x".to_string(), "/a".to_string()], 1));
+    }
+
+    #[test]
+    fn sibling_with_a_shared_name_prefix_is_kept() {
+        assert_eq!(dropped(&["/a/b", "/a/bc"]), (vec!["/a/b".to_string(), "/a/bc".to_string()], 0));
+        assert_eq!(dropped(&["/a/bc", "/a/b"]), (vec!["/a/bc".to_string(), "/a/b".to_string()], 0));
+    }
+
+    #[test]
+    fn trailing_separators_do_not_hide_nesting_or_duplicates() {
+        assert_eq!(dropped(&["/a/", "/a/b"]), (vec!["/a/".to_string()], 1));
+        assert_eq!(dropped(&["/a/b/", "/a"]), (vec!["/a".to_string()], 1));
+        assert_eq!(dropped(&["/a/", "/a"]), (vec!["/a/".to_string()], 0));
+        assert_eq!(dropped(&["/a/b/", "/a/bc/"]), (vec!["/a/b/".to_string(), "/a/bc/".to_string()], 0));
+    }
+
+    #[test]
+    fn indexed_items_replace_their_previous_entry_by_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut favorite = item("/d/b.pptx", 1);
+        favorite.favorite = true;
+        let manager = manager(dir.path(), AppState { items: vec![item("/d/a.pptx", 1), favorite], ..AppState::default() });
+        let mut positions = ItemPositions::default();
+        let mut batch = PersistBatch::new(&ScanSettings { persist_every_items: 1_000, ..ScanSettings::default() });
+
+        manager.store_indexed_item(item("/d/b.pptx", 2), &mut positions, &mut batch);
+        manager.store_indexed_item(item("/d/c.pptx", 2), &mut positions, &mut batch);
+        manager.store_indexed_item(item("/d/c.pptx", 3), &mut positions, &mut batch);
+        // A change to the list outside the scan isn't trusted to the old positions.
+        manager.state.lock().unwrap().items.remove(0);
+        manager.store_indexed_item(item("/d/b.pptx", 4), &mut positions, &mut batch);
+
+        let items = manager.get_state().items;
+        let stored: Vec<(&str, u64, bool)> = items
+            .iter()
+            .map(|item| (item.path.as_str(), item.updated_at, item.favorite))
+            .collect();
+        assert_eq!(stored, [("/d/b.pptx", 4, true), ("/d/c.pptx", 3, false)]);
+    }
+
+    #[test]
+    fn rescan_with_all_directories_removed_clears_the_index() {
+        let _text_cache = TEXT_CACHE.lock().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        text_cache::set_dir(dir.path().join("text"));
+        text_cache::put("abc", "photosynthesis in plants");
+        let cached_text = dir.path().join("text").join("abc.txt");
+        assert!(cached_text.exists());
+
+        let item: SlideIndexItem = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "path": "/unlinked/biology.pptx",
+            "name": "biology.pptx",
+            "kind": "pptx",
+            "slideCount": 1,
+            "snippet": "photosynthesis in plants",
+            "updatedAt": 1,
+            "checksum": "abc",
+        }))
+        .unwrap();
+        let mut state = AppState {
+            items: vec![item],
+            warnings: vec!["Skipped /unlinked/huge.pdf: too big".to_string()],
+            ..AppState::default()
+        };
+        state.settings.inverted_index_min_items = 1;
+        let manager = manager(dir.path(), state);
+
+        // Builds the corpus cache and inverted index.
+        assert_eq!(manager.search("photosynthesis", &SearchOptions::default()).total, 1);
+        assert!(manager.inverted_index.lock().unwrap().is_some());
+
+        let summary = manager.rescan().unwrap();
+
+        assert_eq!(summary.indexed, 0);
+        assert_eq!(summary.scanned, Some(0));
+        assert_eq!(summary.cached, Some(0));
+        assert!(summary.errors.is_empty());
+        let state = manager.get_state();
+        assert!(state.items.is_empty());
+        assert!(state.warnings.is_empty());
+        assert!(state.last_indexed_at.is_some());
+        assert!(!cached_text.exists());
+        assert!(manager.inverted_index.lock().unwrap().is_none());
+        assert!(manager.corpus_cache.lock().unwrap().is_none());
+        assert_eq!(manager.search("photosynthesis", &SearchOptions::default()).total, 0);
+    }
+}
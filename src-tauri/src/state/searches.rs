@@ -0,0 +1,158 @@
+//! Searching the index, plus the recent and saved searches.
+
+use crate::{
+    error::{AppError, Result},
+    index::{explain_query, path_within, persist_state, search_items, CorpusCache},
+    inverted_index::InvertedIndex,
+    keyword_index::KeywordIndex,
+    models::{
+        AppState, KeywordSuggestion, QueryExplanation, SavedSearch, SearchOptions, SearchResponse,
+    },
+};
+
+use super::StateManager;
+
+/// Number of queries kept in `AppState::recent_searches`.
+const MAX_RECENT_SEARCHES: usize = 20;
+
+impl StateManager {
+    /// Runs a search. The query isn't added to the recent searches; the
+    /// frontend searches as the user types, so it calls `record_search` once
+    /// a search is committed.
+    pub fn search(&self, query: &str, options: &SearchOptions) -> SearchResponse {
+        let state = self.state.lock().expect("state poisoned");
+        self.run_search(&state, query, options)
+    }
+
+    /// Adds `query` to the front of the recent searches.
+    pub fn record_search(&self, query: &str) {
+        if self.is_read_only() {
+            return;
+        }
+        let mut state = self.state.lock().expect("state poisoned");
+        if remember_search(&mut state.recent_searches, query) {
+            self.schedule_save();
+        }
+    }
+
+    /// Searches through the lazily built corpus cache and, for large libraries,
+    /// the inverted index.
+    fn run_search(&self, state: &AppState, query: &str, options: &SearchOptions) -> SearchResponse {
+        let mut corpus_cache = self.corpus_cache.lock().expect("corpus cache poisoned");
+        let corpus = corpus_cache.get_or_insert_with(|| CorpusCache::build(&state.items));
+
+        let mut inverted_cache = self.inverted_index.lock().expect("inverted index poisoned");
+        let inverted = if state.items.len() >= state.settings.inverted_index_min_items {
+            Some(&*inverted_cache.get_or_insert_with(|| InvertedIndex::build(corpus)))
+        } else {
+            None
+        };
+        search_items(state, corpus, inverted, query, options)
+    }
+
+    /// Per-term item counts for `query`; see `index::explain_query`.
+    pub fn explain_query(&self, query: &str) -> QueryExplanation {
+        let state = self.state.lock().expect("state poisoned");
+        let mut corpus_cache = self.corpus_cache.lock().expect("corpus cache poisoned");
+        let corpus = corpus_cache.get_or_insert_with(|| CorpusCache::build(&state.items));
+        explain_query(&state, corpus, query)
+    }
+
+    pub fn get_recent_searches(&self) -> Vec<String> {
+        self.state.lock().expect("state poisoned").recent_searches.clone()
+    }
+
+    pub fn get_saved_searches(&self) -> Vec<SavedSearch> {
+        self.state.lock().expect("state poisoned").saved_searches.clone()
+    }
+
+    /// Saves `query` under `name`, replacing an existing saved search with the same name.
+    pub fn save_search(&self, name: &str, query: &str) -> Result<Vec<SavedSearch>> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::Message("Saved search name cannot be empty".to_string()));
+        }
+        self.ensure_writable()?;
+        let mut state = self.state.lock().expect("state poisoned");
+        let saved = SavedSearch {
+            name: name.to_string(),
+            query: query.trim().to_string(),
+        };
+        match state.saved_searches.iter_mut().find(|existing| existing.name == name) {
+            Some(existing) => *existing = saved,
+            None => state.saved_searches.push(saved),
+        }
+        persist_state(&self.storage_path, &state)?;
+        Ok(state.saved_searches.clone())
+    }
+
+    pub fn delete_saved_search(&self, name: &str) -> Result<Vec<SavedSearch>> {
+        self.ensure_writable()?;
+        let mut state = self.state.lock().expect("state poisoned");
+        state.saved_searches.retain(|existing| existing.name != name);
+        persist_state(&self.storage_path, &state)?;
+        Ok(state.saved_searches.clone())
+    }
+
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<KeywordSuggestion> {
+        let state = self.state.lock().expect("state poisoned");
+        let mut cache = self.keyword_index.lock().expect("keyword index poisoned");
+        cache
+            .get_or_insert_with(|| KeywordIndex::build(&state.items))
+            .suggest(prefix, limit)
+    }
+
+    /// Most widespread keywords across the library, or only items under `within`.
+    pub fn top_keywords(&self, limit: usize, within: Option<&str>) -> Vec<KeywordSuggestion> {
+        let state = self.state.lock().expect("state poisoned");
+        match within.map(str::trim).filter(|directory| !directory.is_empty()) {
+            Some(directory) => KeywordIndex::build(
+                state
+                    .items
+                    .iter()
+                    .filter(|item| path_within(&item.path, directory)),
+            )
+            .top(limit),
+            None => {
+                let mut cache = self.keyword_index.lock().expect("keyword index poisoned");
+                cache
+                    .get_or_insert_with(|| KeywordIndex::build(&state.items))
+                    .top(limit)
+            }
+        }
+    }
+}
+
+/// Moves `query` to the front of the recent list (deduplicated, capped).
+/// Returns false when nothing changed, so callers can skip the save.
+fn remember_search(recent: &mut Vec<String>, query: &str) -> bool {
+    let query = query.trim();
+    if query.is_empty() || recent.first().is_some_and(|latest| latest == query) {
+        return false;
+    }
+    recent.retain(|existing| existing != query);
+    recent.insert(0, query.to_string());
+    recent.truncate(MAX_RECENT_SEARCHES);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::{item, manager};
+
+    #[test]
+    fn only_recorded_searches_are_remembered() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager(dir.path(), AppState { items: vec![item("/d/biology.pptx", 1)], ..AppState::default() });
+
+        for prefix in ["b", "bi", "bio", "biology"] {
+            manager.search(prefix, &SearchOptions::default());
+        }
+        assert!(manager.get_recent_searches().is_empty());
+
+        manager.record_search("biology");
+        manager.record_search("  ");
+        assert_eq!(manager.get_recent_searches(), ["biology"]);
+    }
+}
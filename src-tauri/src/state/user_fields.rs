@@ -0,0 +1,143 @@
+//! Edits to the user fields of an item (favorite, tags, display name, open
+//! count) and the lists built from them.
+
+use std::cmp::Reverse;
+
+use crate::{error::{AppError, Result}, models::SlideIndexItem, scanner::current_timestamp};
+
+use super::StateManager;
+
+impl StateManager {
+    /// Records that the user opened `id`; saved shortly after without touching the scan.
+    pub fn record_open(&self, id: &str) -> Result<()> {
+        // Opening still works on a read-only index; it just isn't remembered.
+        if self.is_read_only() {
+            return Ok(());
+        }
+        let mut state = self.state.lock().expect("state poisoned");
+        let item = state
+            .items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| AppError::Message(format!("No indexed item with id {id}")))?;
+        item.last_opened_at = Some(current_timestamp());
+        item.open_count = item.open_count.saturating_add(1);
+        self.schedule_save();
+        Ok(())
+    }
+
+    /// Adds `tag` (trimmed, lowercased) to the item; returns the updated tag list.
+    pub fn add_tag(&self, id: &str, tag: &str) -> Result<Vec<String>> {
+        let tag = normalise_tag(tag)?;
+        self.update_tags(id, |tags| {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+                tags.sort();
+            }
+        })
+    }
+
+    pub fn remove_tag(&self, id: &str, tag: &str) -> Result<Vec<String>> {
+        let tag = normalise_tag(tag)?;
+        self.update_tags(id, |tags| tags.retain(|existing| existing != &tag))
+    }
+
+    fn update_tags(&self, id: &str, update: impl FnOnce(&mut Vec<String>)) -> Result<Vec<String>> {
+        self.ensure_writable()?;
+        let mut state = self.state.lock().expect("state poisoned");
+        let item = state
+            .items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| AppError::Message(format!("No indexed item with id {id}")))?;
+        update(&mut item.tags);
+        let tags = item.tags.clone();
+        self.invalidate_search_caches();
+        self.schedule_save();
+        Ok(tags)
+    }
+
+    /// Sets a friendly name for `id`; `None` or a blank name reverts to the file name.
+    pub fn set_display_name(&self, id: &str, name: Option<String>) -> Result<SlideIndexItem> {
+        self.ensure_writable()?;
+        let mut state = self.state.lock().expect("state poisoned");
+        let item = state
+            .items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| AppError::Message(format!("No indexed item with id {id}")))?;
+        item.display_name = name
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        let updated = item.clone();
+        self.invalidate_search_caches();
+        self.schedule_save();
+        Ok(updated)
+    }
+
+    /// Flips the favorite flag on `id` and returns the new value.
+    pub fn toggle_favorite(&self, id: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let mut state = self.state.lock().expect("state poisoned");
+        let item = state
+            .items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| AppError::Message(format!("No indexed item with id {id}")))?;
+        item.favorite = !item.favorite;
+        let favorite = item.favorite;
+        self.schedule_save();
+        Ok(favorite)
+    }
+
+    /// Favorite items, newest (most recently modified) first.
+    pub fn list_favorites(&self) -> Vec<SlideIndexItem> {
+        let state = self.state.lock().expect("state poisoned");
+        let mut favorites: Vec<SlideIndexItem> = state.items.iter().filter(|item| item.favorite).cloned().collect();
+        favorites.sort_by_key(|item| Reverse(item.updated_at));
+        favorites
+    }
+
+    /// Most recently opened items first; never-opened items are left out.
+    pub fn recent_items(&self, limit: usize) -> Vec<SlideIndexItem> {
+        let state = self.state.lock().expect("state poisoned");
+        let mut opened: Vec<SlideIndexItem> = state
+            .items
+            .iter()
+            .filter(|item| item.last_opened_at.is_some())
+            .cloned()
+            .collect();
+        opened.sort_by_key(|item| Reverse(item.last_opened_at));
+        opened.truncate(limit);
+        opened
+    }
+}
+
+fn normalise_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err(AppError::Message("Tag cannot be empty".to_string()));
+    }
+    Ok(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::AppState,
+        state::tests::{item, manager},
+    };
+
+    #[test]
+    fn favorites_are_listed_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut items = vec![item("/d/old.pptx", 1), item("/d/plain.pptx", 5), item("/d/new.pptx", 9), item("/d/mid.pptx", 4)];
+        for item in &mut items {
+            item.favorite = !item.path.ends_with("plain.pptx");
+        }
+        let manager = manager(dir.path(), AppState { items, ..AppState::default() });
+
+        let paths: Vec<String> = manager.list_favorites().into_iter().map(|item| item.path).collect();
+        assert_eq!(paths, ["/d/new.pptx", "/d/mid.pptx", "/d/old.pptx"]);
+    }
+}
@@ -0,0 +1,67 @@
+//! On-disk cache of each item's complete extracted text, which `index.json`
+//! only keeps capped previews and a truncated snippet of.
+//!
+//! Entries are keyed by file checksum and written whenever a file is indexed.
+//! The cache is off until `set_dir` is called; the desktop app points it into
+//! its app-data directory.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use once_cell::sync::OnceCell;
+
+use crate::models::SlideIndexItem;
+
+static CACHE_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Enables the cache, storing entries in `dir`. Only the first call has an effect.
+pub fn set_dir(dir: PathBuf) {
+    let _ = CACHE_DIR.set(dir);
+}
+
+fn entry_path(dir: &Path, checksum: &str) -> PathBuf {
+    dir.join(format!("{checksum}.txt"))
+}
+
+pub fn get(checksum: &str) -> Option<String> {
+    let dir = CACHE_DIR.get()?;
+    fs::read_to_string(entry_path(dir, checksum)).ok()
+}
+
+pub fn put(checksum: &str, text: &str) {
+    let Some(dir) = CACHE_DIR.get() else {
+        return;
+    };
+    let written = fs::create_dir_all(dir).and_then(|()| fs::write(entry_path(dir, checksum), text));
+    if let Err(error) = written {
+        println!("⚠ Could not cache full text for {}: {}", checksum, error);
+    }
+}
+
+/// Deletes entries whose checksum no longer belongs to any indexed item.
+pub fn evict_stale(items: &[SlideIndexItem]) {
+    let Some(dir) = CACHE_DIR.get() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let live: HashSet<&str> = items.iter().filter_map(|item| item.checksum.as_deref()).collect();
+    let mut evicted = 0;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let is_stale = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|checksum| !live.contains(checksum));
+        if is_stale && fs::remove_file(&path).is_ok() {
+            evicted += 1;
+        }
+    }
+    if evicted > 0 {
+        println!("🗑️  Evicted {} stale full-text cache entries", evicted);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use tempfile::tempdir;
+
+use crate::{
+    error::{AppError, Result},
+    models::{SlideIndexItem, SlideKind},
+    scanner::{launchable_path, pdftoppm_path, resolve_command, run_tool},
+};
+
+/// Longest edge of a generated thumbnail, in pixels.
+const THUMBNAIL_SIZE: &str = "320";
+const THUMBNAIL_TIMEOUT: Duration = Duration::from_secs(120);
+/// Allowed resolution range for `render_page`; requests outside it are clamped.
+pub const MIN_RENDER_DPI: u32 = 36;
+pub const MAX_RENDER_DPI: u32 = 300;
+pub const DEFAULT_RENDER_DPI: u32 = 110;
+
+/// LibreOffice is optional; without it PPTX/PPT decks simply get no thumbnail.
+static SOFFICE: Lazy<Option<PathBuf>> =
+    Lazy::new(|| resolve_command("soffice").or_else(|| resolve_command("libreoffice")));
+
+/// Cache location for an item's thumbnail. The name includes the checksum, so
+/// a changed file maps to a new path and the old image is never reused.
+pub fn thumbnail_file(cache_dir: &Path, item: &SlideIndexItem) -> PathBuf {
+    cache_dir.join(format!("{}-{}.png", item.id, version_key(item)))
+}
+
+/// Short identifier of the indexed file contents, used in cache file names.
+fn version_key(item: &SlideIndexItem) -> String {
+    match item.checksum.as_deref() {
+        Some(checksum) => checksum.chars().take(16).collect(),
+        None => item.updated_at.to_string(),
+    }
+}
+
+/// True when the item already points at its up-to-date cached thumbnail.
+pub fn is_current(cache_dir: &Path, item: &SlideIndexItem) -> bool {
+    let expected = thumbnail_file(cache_dir, item);
+    item.thumbnail_path.as_deref() == Some(expected.to_string_lossy().as_ref()) && expected.exists()
+}
+
+/// Renders the first page/slide of `item` into the thumbnail cache and returns its path.
+pub fn generate_thumbnail(cache_dir: &Path, item: &SlideIndexItem) -> Result<PathBuf> {
+    fs::create_dir_all(cache_dir)?;
+    let target = thumbnail_file(cache_dir, item);
+    let source = launchable_path(&item.path)?;
+    let work_dir = tempdir()?;
+
+    let rendered = match item.kind {
+        SlideKind::Pdf => render_pdf_first_page(&source, work_dir.path())?,
+        SlideKind::Pptx | SlideKind::Ppt => render_office_first_slide(&source, work_dir.path())?,
+    };
+    fs::copy(&rendered, &target)?;
+    remove_stale_thumbnails(cache_dir, item, &target);
+    Ok(target)
+}
+
+fn render_pdf_first_page(source: &Path, work_dir: &Path) -> Result<PathBuf> {
+    let pdftoppm = pdftoppm_path()
+        .ok_or_else(|| AppError::Message("pdftoppm is not installed".to_string()))?;
+    let prefix = work_dir.join("thumbnail");
+    run_tool(
+        "pdftoppm",
+        Command::new(pdftoppm)
+            .args(["-png", "-f", "1", "-l", "1", "-singlefile", "-scale-to", THUMBNAIL_SIZE])
+            .arg(source)
+            .arg(&prefix),
+        THUMBNAIL_TIMEOUT,
+    )?;
+    Ok(prefix.with_extension("png"))
+}
+
+/// Renders one PDF page (1-based) at `dpi` into `cache_dir`, reusing a previous render
+/// of the same page, resolution and file version.
+pub fn render_page(
+    cache_dir: &Path,
+    item: &SlideIndexItem,
+    page: u32,
+    dpi: u32,
+) -> Result<PathBuf> {
+    if !matches!(item.kind, SlideKind::Pdf) {
+        return Err(AppError::Message(
+            "Slide rendering is only available for PDF files".to_string(),
+        ));
+    }
+    if page == 0 || item.slide_count.is_some_and(|count| page > count) {
+        return Err(AppError::Message(format!("Page {page} is out of range")));
+    }
+    let pdftoppm = pdftoppm_path()
+        .ok_or_else(|| AppError::Message("pdftoppm is not installed".to_string()))?;
+
+    let dpi = dpi.clamp(MIN_RENDER_DPI, MAX_RENDER_DPI);
+    let file_name = format!("{}-{}-p{}-{}dpi.png", item.id, version_key(item), page, dpi);
+    let target = cache_dir.join(file_name);
+    if target.exists() {
+        return Ok(target);
+    }
+
+    fs::create_dir_all(cache_dir)?;
+    let source = launchable_path(&item.path)?;
+    let work_dir = tempdir()?;
+    let prefix = work_dir.path().join("page");
+    let page_arg = page.to_string();
+    run_tool(
+        "pdftoppm",
+        Command::new(pdftoppm)
+            .args(["-png", "-singlefile", "-f", &page_arg, "-l", &page_arg])
+            .args(["-r", &dpi.to_string()])
+            .arg(&source)
+            .arg(&prefix),
+        THUMBNAIL_TIMEOUT,
+    )?;
+    fs::copy(prefix.with_extension("png"), &target)?;
+    remove_outdated_renders(cache_dir, item);
+    Ok(target)
+}
+
+fn render_office_first_slide(source: &Path, work_dir: &Path) -> Result<PathBuf> {
+    let soffice = SOFFICE
+        .as_ref()
+        .ok_or_else(|| AppError::Message("LibreOffice is not installed".to_string()))?;
+    // `--convert-to png` exports the first slide as <stem>.png.
+    run_tool(
+        "soffice",
+        Command::new(soffice)
+            .args(["--headless", "--convert-to", "png", "--outdir"])
+            .arg(work_dir)
+            .arg(source),
+        THUMBNAIL_TIMEOUT,
+    )?;
+    let stem = source.file_stem().unwrap_or_default();
+    Ok(work_dir.join(stem).with_extension("png"))
+}
+
+/// Deletes thumbnails rendered for earlier versions of the same item.
+fn remove_stale_thumbnails(cache_dir: &Path, item: &SlideIndexItem, keep: &Path) {
+    let prefix = format!("{}-", item.id);
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let is_same_item = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(&prefix));
+        if is_same_item && path != keep {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Deletes page renders made from an older version of the item's file.
+fn remove_outdated_renders(cache_dir: &Path, item: &SlideIndexItem) {
+    let prefix = format!("{}-", item.id);
+    let current = format!("{}{}-", prefix, version_key(item));
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let is_outdated = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with(&prefix) && !name.starts_with(&current));
+        if is_outdated {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Process-wide cap on how many external tools (pdftoppm, pdftotext,
+//! tesseract, soffice) run at the same time, so big OCR jobs can't launch
+//! dozens of heavy processes and make the whole machine unresponsive.
+
+use std::{
+    sync::{Condvar, Mutex},
+    thread,
+};
+
+use once_cell::sync::Lazy;
+
+struct Permits {
+    in_use: usize,
+    limit: usize,
+}
+
+static PERMITS: Lazy<(Mutex<Permits>, Condvar)> = Lazy::new(|| {
+    let permits = Permits {
+        in_use: 0,
+        limit: default_limit(),
+    };
+    (Mutex::new(permits), Condvar::new())
+});
+
+/// Held while a tool process runs; the slot is released on drop.
+pub struct ToolPermit(());
+
+impl Drop for ToolPermit {
+    fn drop(&mut self) {
+        let (lock, available) = &*PERMITS;
+        lock.lock().expect("tool permits poisoned").in_use -= 1;
+        available.notify_one();
+    }
+}
+
+/// Blocks until fewer than the configured number of tools are running.
+pub fn acquire() -> ToolPermit {
+    let (lock, available) = &*PERMITS;
+    let mut permits = lock.lock().expect("tool permits poisoned");
+    while permits.in_use >= permits.limit {
+        permits = available.wait(permits).expect("tool permits poisoned");
+    }
+    permits.in_use += 1;
+    ToolPermit(())
+}
+
+/// Sets the number of concurrent tool processes; 0 means one per CPU.
+/// Running processes keep their permits; the new limit applies to the next acquire.
+pub fn set_limit(limit: usize) {
+    let (lock, available) = &*PERMITS;
+    lock.lock().expect("tool permits poisoned").limit =
+        if limit == 0 { default_limit() } else { limit };
+    available.notify_all();
+}
+
+fn default_limit() -> usize {
+    thread::available_parallelism().map_or(1, |count| count.get())
+}
@@ -0,0 +1,62 @@
+//! Indexes the sample decks in `tests/fixtures` end to end through `index_path`.
+//!
+//! The fixtures are deliberately tiny: a three-slide PPTX, a two-page PDF with
+//! Flate-compressed content streams, and a PPT whose OLE header is followed by
+//! plain slide text. OCR is off so the results don't depend on installed tools.
+
+use std::path::{Path, PathBuf};
+
+use slides_indexer::models::{DocumentType, OcrMode, ScanSettings, SlideIndexItem, SlideKind};
+use slides_indexer::scanner::index_path;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+fn index(name: &str) -> SlideIndexItem {
+    let settings = ScanSettings {
+        ocr_mode: OcrMode::Off,
+        ..ScanSettings::default()
+    };
+    index_path(&fixture(name), &settings).unwrap_or_else(|error| panic!("indexing {name} failed: {error}"))
+}
+
+#[test]
+fn indexes_pptx_slides_titles_and_metadata() {
+    let item = index("sample.pptx");
+    assert!(matches!(item.kind, SlideKind::Pptx));
+    assert_eq!(item.slide_count, Some(3));
+    let titles: Vec<_> = item.slides.iter().map(|slide| slide.title.as_deref()).collect();
+    assert_eq!(
+        titles,
+        [Some("Photosynthesis Overview"), Some("Light Reactions"), Some("Calvin Cycle")]
+    );
+    assert_eq!(item.presentation_title.as_deref(), Some("Photosynthesis Lecture"));
+    assert!(item.snippet.starts_with("Photosynthesis Overview Plants convert sunlight"));
+    assert!(item.keywords.iter().any(|keyword| keyword == "chlorophyll"));
+    assert_eq!(item.text_coverage, Some(1.0));
+    assert!(item.checksum.is_some());
+}
+
+#[test]
+fn indexes_pdf_pages_from_compressed_streams() {
+    let item = index("sample.pdf");
+    assert!(matches!(item.kind, SlideKind::Pdf));
+    assert_eq!(item.slide_count, Some(2));
+    assert_eq!(item.slides.len(), 2);
+    assert!(item.slides[0].text.contains("Mitochondria produce energy"));
+    assert!(item.slides[1].text.contains("Ribosomes assemble proteins"));
+    assert!(matches!(item.document_type, Some(DocumentType::Presentation))); // landscape pages
+    assert!(item.keywords.iter().any(|keyword| keyword == "cytoplasm"));
+    assert!(!item.encrypted);
+}
+
+#[test]
+fn indexes_ppt_text() {
+    let item = index("sample.ppt");
+    assert!(matches!(item.kind, SlideKind::Ppt));
+    assert_eq!(item.slide_count, None);
+    assert!(item.snippet.starts_with("Volcanoes form where magma rises"));
+    assert!(item.keywords.iter().any(|keyword| keyword == "magma"));
+    assert_eq!(item.text_coverage, None);
+}